@@ -1,15 +1,16 @@
-use std::path::PathBuf;
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
 
-use failure::{bail, Error};
+use failure::{bail, format_err, Error};
 
 use task_maker_format::{ioi, terry, EvaluationConfig, TaskFormat};
+use task_maker_lang::{Language, LanguageManager};
+
+/// The file, inside the task directory, with the languages defined for that task.
+const TASK_LANGUAGES_FILE: &str = "languages.yaml";
 
 /// Search for a valid task directory, starting from base and going _at most_ `max_depth` times up.
-pub fn find_task<P: Into<PathBuf>>(
-    base: P,
-    max_depth: u32,
-    eval_config: &EvaluationConfig,
-) -> Result<Box<dyn TaskFormat>, Error> {
+pub fn find_task_dir<P: Into<PathBuf>>(base: P, max_depth: u32) -> Result<PathBuf, Error> {
     let mut base = base.into();
     if !base.is_absolute() {
         base = getcwd().join(base);
@@ -27,10 +28,33 @@ pub fn find_task<P: Into<PathBuf>>(
             _ => break,
         };
     }
-    match (possible_ioi, possible_terry) {
-        (true, true) => bail!("Ambiguous task directory, can be either IOI and terry"),
-        (false, false) => bail!("No task directory found!"),
-        (true, false) => match ioi::IOITask::new(&base, eval_config) {
+    if possible_ioi && possible_terry {
+        bail!("Ambiguous task directory, can be either IOI and terry");
+    } else if !possible_ioi && !possible_terry {
+        bail!("No task directory found!");
+    }
+    Ok(base)
+}
+
+/// The languages defined in the directory of the task, if any. They are needed for parsing the
+/// source files of the task, so they should be registered with
+/// `LanguageManager::scoped_languages` before calling `parse_task`.
+pub fn task_languages(task_dir: &Path) -> Result<Vec<Arc<dyn Language>>, Error> {
+    let languages = task_dir.join(TASK_LANGUAGES_FILE);
+    if !languages.exists() {
+        return Ok(vec![]);
+    }
+    LanguageManager::load_languages(&languages)
+        .map_err(|e| format_err!("Cannot load the languages of the task: {}", e))
+}
+
+/// Parse the task inside the directory found by `find_task_dir`.
+pub fn parse_task(
+    task_dir: &Path,
+    eval_config: &EvaluationConfig,
+) -> Result<Box<dyn TaskFormat>, Error> {
+    if ioi::IOITask::is_valid(task_dir) {
+        match ioi::IOITask::new(task_dir, eval_config) {
             Ok(task) => {
                 trace!("The task is IOI: {:#?}", task);
                 Ok(Box::new(task))
@@ -39,8 +63,9 @@ pub fn find_task<P: Into<PathBuf>>(
                 warn!("Invalid task: {:?}", e);
                 Err(e)
             }
-        },
-        (false, true) => match terry::TerryTask::new(&base, eval_config) {
+        }
+    } else {
+        match terry::TerryTask::new(task_dir, eval_config) {
             Ok(task) => {
                 trace!("The task is Terry: {:#?}", task);
                 Ok(Box::new(task))
@@ -49,7 +74,7 @@ pub fn find_task<P: Into<PathBuf>>(
                 warn!("Invalid task: {:?}", e);
                 Err(e)
             }
-        },
+        }
     }
 }
 
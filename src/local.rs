@@ -9,10 +9,11 @@ use task_maker_exec::executors::{LocalExecutor, RemoteEntityMessage, RemoteEntit
 use task_maker_exec::proto::ExecutorClientMessage;
 use task_maker_exec::ExecutorClient;
 use task_maker_format::ui::{UIMessage, UIType, UI};
-use task_maker_format::{EvaluationData, TaskFormat, UISender, VALID_TAGS};
+use task_maker_format::{EvaluationData, Tag, TaskFormat, UISender, VALID_TAGS};
+use task_maker_lang::LanguageManager;
 use task_maker_store::FileStore;
 
-use crate::detect_format::find_task;
+use crate::detect_format::{find_task_dir, parse_task, task_languages};
 use crate::error::NiceError;
 use crate::opt::Opt;
 use crate::print_dag;
@@ -50,13 +51,22 @@ pub fn run_evaluation<F>(opt: Opt, mut on_message: F) -> Result<Evaluation, Erro
 where
     F: 'static + FnMut(&mut dyn UI, UIMessage) + Send,
 {
-    if opt.exclusive {
-        bail!("This option is not implemented yet");
+    let task_dir = find_task_dir(&opt.task_dir, opt.max_depth)
+        .map_err(|e| format_err!("Invalid task directory: {}", e.to_string()))?;
+    // register the user-defined languages before looking at the source files of the task, the
+    // ones of the task taking the precedence. They are unregistered when the evaluation ends.
+    let mut languages = task_languages(&task_dir)?;
+    if let Some(path) = opt.languages_config() {
+        languages.extend(
+            LanguageManager::load_languages(&path)
+                .map_err(|e| format_err!("Cannot load the user-defined languages: {}", e))?,
+        );
     }
+    let _languages = LanguageManager::scoped_languages(languages);
 
     // setup the task
     let eval_config = opt.to_config();
-    let task: Box<dyn TaskFormat> = find_task(&opt.task_dir, opt.max_depth, &eval_config)
+    let task: Box<dyn TaskFormat> = parse_task(&task_dir, &eval_config)
         .map_err(|e| format_err!("Invalid task directory: {}", e.to_string()))?;
 
     if opt.task_info {
@@ -87,6 +97,11 @@ where
         .cache_mode(CacheMode::try_from(&opt.no_cache, &VALID_TAGS)?)
         .copy_exe(opt.copy_exe)
         .copy_logs(opt.copy_logs);
+    if opt.exclusive {
+        config.exclusive_tag(Tag::Evaluation.into());
+    }
+    config.dedicated_core_tag(Tag::Evaluation.into());
+    config.repetition_policy(opt.repeat_policy);
     if let Some(extra_time) = opt.extra_time {
         if extra_time < 0.0 {
             bail!("The extra time ({}) cannot be negative!", extra_time);
@@ -113,9 +128,11 @@ where
     // setup the executor
     let cache = Cache::new(store_path.join("cache"))
         .map_err(|e| format_err!("Cannot create the cache: {}", e.to_string()))?;
-    let num_cores = opt.num_cores.unwrap_or_else(num_cpus::get);
+    let core_assignment = opt.core_assignment()?;
+    let num_cores = opt.num_workers(core_assignment.as_ref())?;
     let sandbox_path = store_path.join("sandboxes");
-    let executor = LocalExecutor::new(file_store.clone(), num_cores, sandbox_path);
+    let mut executor = LocalExecutor::new(file_store.clone(), num_cores, sandbox_path);
+    executor.core_assignment = core_assignment;
 
     // build the DAG for the task
     if let Err(e) = task.build_dag(&mut eval, &eval_config) {
@@ -200,7 +217,12 @@ where
         }
     }
 
-    let EvaluationData { sender, dag, .. } = eval;
+    let EvaluationData {
+        sender,
+        dag,
+        post_evaluation_hooks,
+        ..
+    } = eval;
     defer! {
         // wait for the server and the ui to exit
         if let Some(server) = server {
@@ -231,6 +253,9 @@ where
 
     task.sanity_check_post_hook(&mut sender.lock().unwrap())
         .map_err(|e| format_err!("Sanity checks failed: {}", e.to_string()))?;
+    for hook in post_evaluation_hooks {
+        hook(&mut sender.lock().unwrap())?;
+    }
     Ok(Evaluation::Done)
 }
 
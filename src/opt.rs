@@ -1,8 +1,10 @@
 use std::path::PathBuf;
 
+use failure::{format_err, Error};
 use structopt::StructOpt;
 
 use itertools::Itertools;
+use task_maker_exec::{parse_core_list, CoreAssignment};
 use task_maker_format::get_sanity_check_names;
 use task_maker_format::terry::Seed;
 use task_maker_format::{EvaluationConfig, VALID_TAGS};
@@ -40,7 +42,10 @@ pub struct Opt {
     #[allow(clippy::option_option)]
     pub no_cache: Option<Option<String>>,
 
-    /// Do not run in parallel time critical executions on the same machine
+    /// Do not run in parallel time critical executions
+    ///
+    /// The evaluations of the solutions never run in parallel with each other, while the other
+    /// executions (e.g. compilations and generations) still do.
     #[structopt(long = "exclusive")]
     pub exclusive: bool,
 
@@ -84,6 +89,11 @@ pub struct Opt {
     #[structopt(long = "store-dir")]
     pub store_dir: Option<PathBuf>,
 
+    /// The YAML file with the user-defined languages. If not specified the languages.yaml file in the
+    /// configuration directory is used, if present
+    #[structopt(long = "languages-config")]
+    pub languages_config: Option<PathBuf>,
+
     /// Maximum size of the storage directory, in MiB
     #[structopt(long = "max-cache", default_value = "3072")]
     pub max_cache: u64,
@@ -96,6 +106,49 @@ pub struct Opt {
     #[structopt(long = "num-cores")]
     pub num_cores: Option<usize>,
 
+    /// Reserve these cores for the evaluations of the solutions (e.g. 2-7)
+    ///
+    /// Each evaluation is pinned to one of these cores, while all the other executions run on the
+    /// remaining ones. The hyperthreading siblings of the reserved cores are left unused. Unless
+    /// --num-cores is specified, one worker per reserved core is spawned. --num-cores cannot be
+    /// greater than the number of reserved cores, which are split between the workers.
+    #[structopt(long = "eval-cores")]
+    pub eval_cores: Option<String>,
+
+    /// Run each evaluation of the solutions this number of times
+    ///
+    /// The reported time is the one of the run chosen by --repeat-policy, and the spread of the
+    /// timings is shown at the end of the evaluation. The cache keeps all the runs, so running
+    /// again with a higher number only executes the missing runs.
+    #[structopt(long = "repeat", default_value = "1")]
+    pub repeat: usize,
+
+    /// Run the evaluations of the solutions starting with NAME this number of times (NAME=N)
+    ///
+    /// This overrides --repeat for those solutions. Just the file name is checked, like for the
+    /// solution filter.
+    #[structopt(long = "repeat-solution", parse(try_from_str = parse_solution_repetitions))]
+    pub repeat_solution: Vec<(String, usize)>,
+
+    /// Which of the repeated runs decides the outcome of an evaluation: best, median or worst
+    #[structopt(long = "repeat-policy", default_value = "median")]
+    pub repeat_policy: task_maker_dag::RepetitionPolicy,
+
+    /// Suggest the time and memory limits of the task from the resources used by the solutions
+    ///
+    /// The solutions annotated with @check-accepted should fit in the limits on those subtasks,
+    /// while the ones annotated with @check-time-limit-exceeded should exceed the time limit.
+    #[structopt(long = "suggest-limits")]
+    pub suggest_limits: bool,
+
+    /// With --suggest-limits, consider correct the solutions whose names start with this prefix
+    #[structopt(long = "correct-solution")]
+    pub correct_solution: Vec<String>,
+
+    /// With --suggest-limits, consider too slow the solutions whose names start with this prefix
+    #[structopt(long = "too-slow-solution")]
+    pub too_slow_solution: Vec<String>,
+
     /// Include the solutions in the booklet
     #[structopt(long = "booklet-solutions")]
     pub booklet_solutions: bool,
@@ -176,6 +229,19 @@ pub struct WorkerOptions {
     pub server_addr: String,
 }
 
+/// Parse the value of --repeat-solution, in the NAME=N format.
+fn parse_solution_repetitions(value: &str) -> Result<(String, usize), Error> {
+    let mut parts = value.splitn(2, '=');
+    let name = parts.next().unwrap_or_default();
+    let repetitions = parts
+        .next()
+        .ok_or_else(|| format_err!("Expecting NAME=N, got {:?}", value))?;
+    let repetitions = repetitions
+        .parse()
+        .map_err(|_| format_err!("Invalid number of repetitions: {:?}", repetitions))?;
+    Ok((name.to_string(), repetitions))
+}
+
 /// Returns the long-help for the "skip sanity checks" option.
 fn skip_sanity_checks_long_help() -> &'static str {
     lazy_static! {
@@ -209,6 +275,11 @@ impl Opt {
             disabled_sanity_checks: self.skip_sanity_checks.clone(),
             seed: self.seed,
             dry_run: self.dry_run,
+            repetitions: self.repeat,
+            solution_repetitions: self.repeat_solution.clone(),
+            suggest_limits: self.suggest_limits,
+            correct_solutions: self.correct_solution.clone(),
+            too_slow_solutions: self.too_slow_solution.clone(),
         }
     }
 
@@ -228,6 +299,41 @@ impl Opt {
         }
     }
 
+    /// Get the file with the user-defined languages of this configuration, if any. If nothing is
+    /// specified the file in the configuration directory is used, only if it exists.
+    pub fn languages_config(&self) -> Option<PathBuf> {
+        match &self.languages_config {
+            Some(path) => Some(path.clone()),
+            None => directories::ProjectDirs::from("", "", "task-maker")
+                .map(|project| project.config_dir().join("languages.yaml"))
+                .filter(|path| path.exists()),
+        }
+    }
+
+    /// How to split the cores between the evaluations and the other executions, `None` if
+    /// `--eval-cores` is not specified.
+    pub fn core_assignment(&self) -> Result<Option<CoreAssignment>, Error> {
+        match &self.eval_cores {
+            Some(cores) => Ok(Some(CoreAssignment::new(&parse_core_list(cores)?)?)),
+            None => Ok(None),
+        }
+    }
+
+    /// The number of workers to spawn: by default one per core, or one per reserved core when
+    /// some cores are reserved for the evaluations. With reserved cores, more workers than
+    /// reserved cores are not allowed.
+    pub fn num_workers(&self, core_assignment: Option<&CoreAssignment>) -> Result<usize, Error> {
+        match (self.num_cores, core_assignment) {
+            (Some(num_cores), Some(assignment)) => {
+                assignment.check_num_workers(num_cores)?;
+                Ok(num_cores)
+            }
+            (Some(num_cores), None) => Ok(num_cores),
+            (None, Some(assignment)) => Ok(assignment.dedicated.len()),
+            (None, None) => Ok(num_cpus::get()),
+        }
+    }
+
     pub fn enable_log(&mut self) {
         // configure the logger based on the verbosity level
         let mut show_warning = false;
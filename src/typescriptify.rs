@@ -3,21 +3,26 @@ use std::time::SystemTime;
 use regex::Regex;
 use typescript_definitions::TypeScriptifyTrait;
 
-use task_maker_dag::{ExecutionResourcesUsage, ExecutionResult, ExecutionStatus, File};
+use task_maker_dag::{
+    ExecutionResourcesUsage, ExecutionResult, ExecutionSample, ExecutionStatus, File,
+};
 use task_maker_exec::{ClientInfo, ExecutorStatus, ExecutorWorkerStatus, WorkerCurrentJobStatus};
 use task_maker_format::ioi::{
     BatchTypeData, Booklet, BookletConfig, Checker, CommunicationTypeData, IOITask, InputGenerator,
-    InputValidator, OutputGenerator, Statement, StatementConfig, SubtaskInfo, TaskInfoScoring,
-    TaskInfoStatement, TaskType, TestcaseInfo, TestcaseScoreAggregator,
+    InputValidator, LimitSuggestion, LimitsMultipliers, OutputGenerator, SolutionTiming, Statement,
+    StatementConfig, SubtaskInfo, TaskInfoScoring, TaskInfoStatement, TaskType, TestcaseInfo,
+    TestcaseScoreAggregator,
+};
+use task_maker_format::ioi::{
+    IOITaskInfo, TaskInfoAttachment, TaskInfoLimits, TaskInfoSubtask, TaskInfoTestcase,
 };
-use task_maker_format::ioi::{IOITaskInfo, TaskInfoAttachment, TaskInfoLimits, TaskInfoSubtask};
 use task_maker_format::terry::TerryTaskInfo;
 use task_maker_format::terry::{
     CaseStatus, SolutionAlert, SolutionFeedback, SolutionFeedbackCase, SolutionOutcome,
     SolutionValidation, SolutionValidationCase, TerryTask,
 };
 use task_maker_format::ui::{UIExecutionStatus, UIMessage};
-use task_maker_format::TaskInfo;
+use task_maker_format::{CompilerSettingsRule, TaskCompilerSettings, TaskInfo};
 use task_maker_lang::{Dependency, GraderMap, SourceFile};
 
 /// Print to stdout the type definition for the specified type.
@@ -56,9 +61,15 @@ fn main() {
     export_ts!(TerryTask);
     export_ts!(SolutionOutcome);
     export_ts!(ExecutionResult);
+    export_ts!(ExecutionSample);
     export_ts!(TaskType);
     export_ts!(SubtaskInfo);
     export_ts!(TestcaseInfo);
+    export_ts!(LimitsMultipliers);
+    export_ts!(CompilerSettingsRule);
+    export_ts!(TaskCompilerSettings);
+    export_ts!(LimitSuggestion);
+    export_ts!(SolutionTiming);
     export_ts!(TestcaseScoreAggregator);
     export_ts!(GraderMap);
     export_ts!(Dependency);
@@ -87,6 +98,7 @@ fn main() {
     export_ts!(TaskInfoLimits);
     export_ts!(TaskInfoAttachment);
     export_ts!(TaskInfoSubtask);
+    export_ts!(TaskInfoTestcase);
     export_ts!(TaskInfoScoring);
     export_ts!(TaskInfoStatement);
     export_ts!(TerryTaskInfo);
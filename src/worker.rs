@@ -25,7 +25,12 @@ pub fn main_worker(opt: Opt, worker_opt: WorkerOptions) {
         .nice_expect("Cannot create the file store"),
     );
     let sandbox_path = store_path.join("sandboxes");
-    let num_workers = opt.num_cores.unwrap_or_else(num_cpus::get);
+    let core_assignment = opt
+        .core_assignment()
+        .nice_expect("Invalid list of evaluation cores");
+    let num_workers = opt
+        .num_workers(core_assignment.as_ref())
+        .nice_expect("Invalid number of workers");
 
     let mut workers = vec![];
     let name = opt
@@ -47,7 +52,7 @@ pub fn main_worker(opt: Opt, worker_opt: WorkerOptions) {
             error!("The server rejected the worker connection: {}", err);
             break;
         }
-        let worker = Worker::new_with_channel(
+        let mut worker = Worker::new_with_channel(
             &format!("{} {}", name, i),
             file_store.clone(),
             sandbox_path.clone(),
@@ -55,6 +60,9 @@ pub fn main_worker(opt: Opt, worker_opt: WorkerOptions) {
             executor_rx.change_type(),
             SelfExecSandboxRunner::default(),
         );
+        if let Some(assignment) = &core_assignment {
+            worker.set_cpu_affinity(assignment.worker_affinity(i, num_workers));
+        }
         workers.push(
             thread::Builder::new()
                 .name(format!("Worker {}", worker))
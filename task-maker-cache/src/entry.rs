@@ -195,15 +195,15 @@ impl CacheEntry {
         }
         let extra_time = group.config().extra_time;
         for (exec, item) in group.executions.iter().zip(self.items.iter()) {
-            match item.result.status {
-                ExecutionStatus::Success => {
-                    // require that the new limits are less restrictive
-                    check_limits!(item.limits, exec.limits, item.extra_time - extra_time);
-                }
-                _ => {
-                    // require that the new limits are more restrictive
-                    check_limits!(exec.limits, item.limits, extra_time - item.extra_time);
-                }
+            // if the execution has been run more than once, each run has to be compatible
+            let samples = item.result.all_samples();
+            if samples.iter().any(|s| s.status == ExecutionStatus::Success) {
+                // require that the new limits are less restrictive
+                check_limits!(item.limits, exec.limits, item.extra_time - extra_time);
+            }
+            if samples.iter().any(|s| s.status != ExecutionStatus::Success) {
+                // require that the new limits are more restrictive
+                check_limits!(exec.limits, item.limits, extra_time - item.extra_time);
             }
         }
         true
@@ -218,7 +218,8 @@ mod tests {
     use std::io::Write;
     use std::path::{Path, PathBuf};
     use task_maker_dag::{
-        Execution, ExecutionCommand, ExecutionResourcesUsage, ExecutionResult, ExecutionStatus,
+        Execution, ExecutionCommand, ExecutionResourcesUsage, ExecutionResult, ExecutionSample,
+        ExecutionStatus,
     };
     use task_maker_store::{FileStore, FileStoreHandle, FileStoreKey, ReadFileIterator};
 
@@ -249,6 +250,7 @@ mod tests {
                         },
                         stdout: None,
                         stderr: None,
+                        samples: vec![],
                     },
                     limits: Default::default(),
                     extra_time: exec.config().extra_time,
@@ -400,6 +402,31 @@ mod tests {
         assert!(entry.is_compatible(&exec4.into()));
     }
 
+    #[test]
+    fn test_compatible_repeated_cpu_time() {
+        let (mut entry, mut exec1) = empty_entry();
+        exec1.limits.cpu_time = Some(1.0);
+        entry.items[0].limits.cpu_time = Some(1.0);
+        let sample = |status| ExecutionSample {
+            status,
+            was_killed: false,
+            resources: ExecutionResourcesUsage::default(),
+        };
+        entry.items[0].result.samples = vec![
+            sample(ExecutionStatus::Success),
+            sample(ExecutionStatus::TimeLimitExceeded),
+        ];
+        assert!(entry.is_compatible(&exec1.into()));
+
+        let mut exec2 = Execution::new("exec", ExecutionCommand::local("foo"));
+        exec2.limits.cpu_time = Some(2.0);
+        assert!(!entry.is_compatible(&exec2.into()));
+
+        let mut exec3 = Execution::new("exec", ExecutionCommand::local("foo"));
+        exec3.limits.cpu_time = Some(0.5);
+        assert!(!entry.is_compatible(&exec3.into()));
+    }
+
     #[test]
     fn test_compatible_success_read_only() {
         let (mut entry, mut exec1) = empty_entry();
@@ -2,7 +2,7 @@ use itertools::Itertools;
 use serde::{Deserialize, Serialize};
 use std::collections::HashMap;
 use std::path::PathBuf;
-use task_maker_dag::{Execution, ExecutionCommand, ExecutionGroup, FileUuid};
+use task_maker_dag::{Execution, ExecutionCommand, ExecutionGroup, Fifo, FileUuid};
 use task_maker_store::{FileStoreHandle, FileStoreKey};
 
 /// The cache key of a single execution of a group.
@@ -15,6 +15,10 @@ struct CacheKeyItem {
     pub args: Vec<String>,
     /// The key (aka the hash) of the stdin, if any.
     pub stdin: Option<FileStoreKey>,
+    /// The normalized name of the FIFO used as stdin, if any.
+    pub stdin_fifo: Option<String>,
+    /// The normalized name of the FIFO used as stdout, if any.
+    pub stdout_fifo: Option<String>,
     /// The key (aka the hash) of the input files, and if they are executable. Note that because the
     /// order matters here (it changes the final hash of the key) those values are sorted
     /// lexicographically.
@@ -48,28 +52,30 @@ impl CacheKeyItem {
             .sorted()
             .collect_vec();
         let env = execution.env.clone().into_iter().sorted().collect_vec();
-        let args = if let Some(group) = group {
-            let mut fifos = HashMap::new();
+        // the paths of the FIFOs are random, replace them with a name that depends only on their
+        // position inside the group
+        let mut fifos = HashMap::new();
+        if let Some(group) = group {
             for (i, fifo) in group.fifo.iter().enumerate() {
                 let name = fifo.sandbox_path().to_str().unwrap().to_string();
                 fifos.insert(name, format!("tm_fifo_{}", i));
             }
-            let mut args = Vec::new();
-            for arg in &execution.args {
-                if let Some(name) = fifos.get(arg) {
-                    args.push(name.clone());
-                } else {
-                    args.push(arg.clone());
-                }
-            }
-            args
-        } else {
-            execution.args.clone()
+        }
+        let args = execution
+            .args
+            .iter()
+            .map(|arg| fifos.get(arg).unwrap_or(arg).clone())
+            .collect();
+        let fifo_name = |fifo: &Fifo| {
+            let path = fifo.sandbox_path().to_str().unwrap().to_string();
+            fifos.get(&path).cloned().unwrap_or(path)
         };
         CacheKeyItem {
             command: execution.command.clone(),
             args,
             stdin,
+            stdin_fifo: execution.stdin_fifo.as_ref().map(fifo_name),
+            stdout_fifo: execution.stdout_fifo.as_ref().map(fifo_name),
             inputs,
             env,
         }
@@ -295,4 +301,34 @@ mod tests {
         let key2 = CacheKey::from_execution_group(&group2, &HashMap::new());
         assert_eq!(key1, key2);
     }
+
+    #[test]
+    fn test_fifo_redirect() {
+        let mut group1 = ExecutionGroup::new("group");
+        let fifo1 = group1.new_fifo();
+        let mut exec1 = Execution::new("exec1", ExecutionCommand::local("foo"));
+        exec1.stdout_fifo(fifo1);
+        group1.add_execution(exec1);
+        let exec1 = &group1.executions[0];
+        let key1 = CacheKeyItem::from_execution(exec1, &HashMap::new(), Some(&group1));
+        assert_eq!(key1.stdout_fifo, Some("tm_fifo_0".to_string()));
+
+        let mut group2 = ExecutionGroup::new("group 2");
+        let fifo2 = group2.new_fifo();
+        let mut exec2 = Execution::new("exec2", ExecutionCommand::local("foo"));
+        exec2.stdout_fifo(fifo2);
+        group2.add_execution(exec2);
+        let exec2 = &group2.executions[0];
+        let key2 = CacheKeyItem::from_execution(exec2, &HashMap::new(), Some(&group2));
+        assert_eq!(key1, key2);
+
+        let mut group3 = ExecutionGroup::new("group 3");
+        let fifo3 = group3.new_fifo();
+        let mut exec3 = Execution::new("exec3", ExecutionCommand::local("foo"));
+        exec3.stdin_fifo(fifo3);
+        group3.add_execution(exec3);
+        let exec3 = &group3.executions[0];
+        let key3 = CacheKeyItem::from_execution(exec3, &HashMap::new(), Some(&group3));
+        assert_ne!(key1, key3);
+    }
 }
@@ -53,6 +53,7 @@
 //!     was_cached: false,
 //!     stderr: None,
 //!     stdout: None,
+//!     samples: vec![],
 //! };
 //!
 //! // make the FileUuid -> FileStoreHandle map
@@ -91,7 +92,10 @@ use std::path::{Path, PathBuf};
 use failure::Error;
 use itertools::Itertools;
 
-use task_maker_dag::{ExecutionGroup, ExecutionResult, ExecutionStatus, FileUuid};
+use task_maker_dag::{
+    Execution, ExecutionGroup, ExecutionResourcesUsage, ExecutionResult, ExecutionSample,
+    ExecutionStatus, FileUuid,
+};
 use task_maker_store::{FileStore, FileStoreHandle};
 
 /// The name of the file which holds the cache data.
@@ -186,19 +190,32 @@ impl Cache {
                     if entry.is_compatible(group) {
                         let mut results = Vec::new();
                         for (exec, item) in group.executions.iter().zip(entry.items.iter()) {
-                            let (exit_status, signal) = match &item.result.status {
-                                ExecutionStatus::ReturnCode(c) => (*c, None),
-                                ExecutionStatus::Signal(s, name) => (0, Some((*s, name.clone()))),
-                                _ => (0, None),
-                            };
-                            results.push(ExecutionResult {
-                                status: exec.status(exit_status, signal, &item.result.resources),
+                            // the limits may have changed, so the status of all the runs has to be
+                            // computed again
+                            let samples = item
+                                .result
+                                .samples
+                                .iter()
+                                .map(|sample| ExecutionSample {
+                                    status: Cache::status(exec, &sample.status, &sample.resources),
+                                    was_killed: sample.was_killed,
+                                    resources: sample.resources.clone(),
+                                })
+                                .collect();
+                            let result = ExecutionResult {
+                                status: Cache::status(
+                                    exec,
+                                    &item.result.status,
+                                    &item.result.resources,
+                                ),
                                 was_killed: item.result.was_killed,
                                 was_cached: true,
                                 resources: item.result.resources.clone(),
                                 stdout: item.result.stdout.clone(),
                                 stderr: item.result.stderr.clone(),
-                            });
+                                samples: vec![],
+                            };
+                            results.push(result.with_samples(samples));
                         }
                         return CacheResult::Hit {
                             result: results,
@@ -211,6 +228,20 @@ impl Cache {
         CacheResult::Miss
     }
 
+    /// Compute the status of a cached run of the execution, according to its current limits.
+    fn status(
+        exec: &Execution,
+        status: &ExecutionStatus,
+        resources: &ExecutionResourcesUsage,
+    ) -> ExecutionStatus {
+        let (exit_status, signal) = match status {
+            ExecutionStatus::ReturnCode(c) => (*c, None),
+            ExecutionStatus::Signal(s, name) => (0, Some((*s, name.clone()))),
+            _ => (0, None),
+        };
+        exec.status(exit_status, signal, resources)
+    }
+
     /// Checks whether a result is allowed in the cache.
     pub fn is_cacheable(result: &ExecutionResult) -> bool {
         if let ExecutionStatus::InternalError(_) = result.status {
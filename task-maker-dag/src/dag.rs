@@ -39,6 +39,14 @@ pub struct ExecutionDAGConfig {
     pub copy_exe: bool,
     /// Whether to copy the log files of some interesting executions.
     pub copy_logs: bool,
+    /// The tags of the timing-critical executions: an execution with one of these tags never runs
+    /// in parallel with the other executions with the same tag.
+    pub exclusive_tags: HashSet<ExecutionTag>,
+    /// The tags of the timing-sensitive executions: on the workers with dedicated cores, each
+    /// execution with one of these tags is pinned to its own dedicated core.
+    pub dedicated_core_tags: HashSet<ExecutionTag>,
+    /// How the outcome of an execution run more than once is chosen among its runs.
+    pub repetition_policy: RepetitionPolicy,
 }
 
 /// A wrapper around a `File` provided by the client, this means that the client knows the
@@ -260,6 +268,9 @@ impl ExecutionDAGConfig {
             extra_time: 0.5,
             copy_exe: false,
             copy_logs: false,
+            exclusive_tags: HashSet::new(),
+            dedicated_core_tags: HashSet::new(),
+            repetition_policy: RepetitionPolicy::default(),
         }
     }
 
@@ -299,6 +310,26 @@ impl ExecutionDAGConfig {
         self.copy_logs = copy_logs;
         self
     }
+
+    /// Mark the executions with the specified tag as exclusive: they won't run in parallel with
+    /// the other executions with the same tag, for having more reliable timings.
+    pub fn exclusive_tag(&mut self, tag: ExecutionTag) -> &mut Self {
+        self.exclusive_tags.insert(tag);
+        self
+    }
+
+    /// Mark the executions with the specified tag as timing-sensitive: if the worker has dedicated
+    /// cores each of them is pinned to its own core, while the other executions never run there.
+    pub fn dedicated_core_tag(&mut self, tag: ExecutionTag) -> &mut Self {
+        self.dedicated_core_tags.insert(tag);
+        self
+    }
+
+    /// Set how the outcome of an execution run more than once is chosen among its runs.
+    pub fn repetition_policy(&mut self, policy: RepetitionPolicy) -> &mut Self {
+        self.repetition_policy = policy;
+        self
+    }
 }
 
 impl Default for ExecutionDAGConfig {
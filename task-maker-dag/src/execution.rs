@@ -1,15 +1,17 @@
+use std::cmp::Ordering;
 use std::collections::HashMap;
+use std::hash::{Hash, Hasher};
 use std::path::PathBuf;
 
 use boxfnonce::BoxFnOnce;
-use failure::Error;
 use failure::_core::fmt::Formatter;
+use failure::Error;
 use serde::{Deserialize, Serialize};
 use typescript_definitions::TypeScriptify;
 use uuid::Uuid;
 
 use crate::file::*;
-use crate::ExecutionDAGConfig;
+use crate::{ExecutionDAGConfig, Fifo};
 
 /// The identifier of an execution, it's globally unique and it identifies an execution only during
 /// a single evaluation.
@@ -44,7 +46,8 @@ pub struct ExecutionTag {
 /// Command of an [`Execution`](struct.Execution.html) to execute.
 ///
 /// There is a distinction between a `System` command, which has to be searched in the `PATH`
-/// env var, and a `Local` command, which is relative to the sandbox directory.
+/// env var, a `Local` command, which is relative to the sandbox directory, and a `Builtin` command,
+/// which is implemented directly by the workers without spawning any process.
 ///
 /// ```
 /// use task_maker_dag::ExecutionCommand;
@@ -60,6 +63,33 @@ pub enum ExecutionCommand {
     System(PathBuf),
     /// A command relative to the sandbox directory, not to be searched in the `$PATH`.
     Local(PathBuf),
+    /// A command implemented by the workers themselves. The arguments of the execution are the
+    /// paths, inside the sandbox, of the input files to work on.
+    Builtin(BuiltinCommand),
+}
+
+/// A command that the workers know how to execute without spawning a process. Those commands
+/// behave like a checker: they write the score to stdout and a message to stderr, and they always
+/// exit successfully.
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub enum BuiltinCommand {
+    /// Compare two files (the correct one and the one to check) line by line, ignoring the blank
+    /// lines and the changes in the amount of white spaces, like `diff --ignore-blank-lines
+    /// --ignore-space-change`.
+    CompareWhiteDiff,
+    /// Compare two files token by token, where the tokens are separated by white spaces.
+    CompareTokens,
+    /// Compare two files token by token, ignoring the case of the letters.
+    CompareTokensCaseInsensitive,
+    /// Compare two files token by token, the tokens that are numbers in the correct file are
+    /// considered equal if the absolute or the relative difference is within the epsilons. `nan`
+    /// and the infinities match only themselves.
+    CompareFloats {
+        /// The maximum absolute difference between two numbers.
+        absolute_epsilon: f64,
+        /// The maximum difference between two numbers, relative to the correct one.
+        relative_epsilon: f64,
+    },
 }
 
 /// An input file of an [`Execution`](struct.Execution.html), can be marked as executable if it has
@@ -140,6 +170,10 @@ pub struct Execution {
     pub capture_stdout: Option<usize>,
     /// When not `None`, ask the sandbox to capture that many bytes from the standard error.
     pub capture_stderr: Option<usize>,
+    /// Optional FIFO of the group to use as standard input, instead of a file.
+    pub stdin_fifo: Option<Fifo>,
+    /// Optional FIFO of the group to use as standard output, instead of a file.
+    pub stdout_fifo: Option<Fifo>,
     /// List of input files that should be put inside the sandbox.
     pub inputs: HashMap<PathBuf, ExecutionInput>,
     /// List of the output files that should be capture from the sandbox.
@@ -161,6 +195,9 @@ pub struct Execution {
     /// priority order is followed only between ready executions, i.e. a lower priority one can be
     /// executed before if its dependencies are ready earlier.
     pub priority: Priority,
+    /// How many times the execution is run for measuring its resources more reliably. The outcome
+    /// of the execution is chosen among the runs by the `RepetitionPolicy` of the DAG.
+    pub repetitions: usize,
 }
 
 /// Limits on an [`Execution`](struct.Execution.html). On some worker platforms some of the fields
@@ -230,6 +267,43 @@ pub struct ExecutionResourcesUsage {
     pub memory: u64,
 }
 
+/// The outcome of a single run of an [`Execution`](struct.Execution.html) that is run more than
+/// once.
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, TypeScriptify)]
+pub struct ExecutionSample {
+    /// Status of the run.
+    pub status: ExecutionStatus,
+    /// Whether the run has been killed by the sandbox.
+    pub was_killed: bool,
+    /// Resources used by the run.
+    pub resources: ExecutionResourcesUsage,
+}
+
+/// How the outcome of an [`Execution`](struct.Execution.html) run more than once is chosen among
+/// the outcomes of its runs. The runs are sorted putting the successful ones first, from the
+/// fastest to the slowest CPU time, followed by the failed ones.
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq)]
+pub enum RepetitionPolicy {
+    /// Use the first run: the fastest successful one, if any.
+    Best,
+    /// Use the run in the middle.
+    Median,
+    /// Use the last run: a failed one, if any, otherwise the slowest one.
+    Worst,
+}
+
+/// The statistics of the CPU time of the runs of an [`Execution`](struct.Execution.html) run more
+/// than once.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct TimingStats {
+    /// The CPU time of the fastest run, in seconds.
+    pub min: f64,
+    /// The median of the CPU times of the runs, in seconds.
+    pub median: f64,
+    /// The difference between the CPU times of the slowest and the fastest runs, in seconds.
+    pub spread: f64,
+}
+
 /// The result of an [`Execution`](struct.Execution.html).
 #[derive(Clone, Serialize, Deserialize, PartialEq, TypeScriptify)]
 pub struct ExecutionResult {
@@ -245,6 +319,10 @@ pub struct ExecutionResult {
     pub stdout: Option<Vec<u8>>,
     /// Captured standard error of the execution, if the capture was requested.
     pub stderr: Option<Vec<u8>>,
+    /// The outcomes of all the runs of the execution, if it has been run more than once. The
+    /// status and the resources of the result are the ones of the run chosen by the
+    /// `RepetitionPolicy`.
+    pub samples: Vec<ExecutionSample>,
 }
 
 impl ExecutionLimits {
@@ -362,6 +440,92 @@ impl std::default::Default for ExecutionLimits {
     }
 }
 
+impl RepetitionPolicy {
+    /// The index of the run chosen by this policy among the provided ones, which must not be empty.
+    pub fn choose(self, samples: &[ExecutionSample]) -> usize {
+        let key =
+            |sample: &ExecutionSample| (!sample.status.is_success(), sample.resources.cpu_time);
+        let mut order: Vec<usize> = (0..samples.len()).collect();
+        order.sort_by(|a, b| {
+            key(&samples[*a])
+                .partial_cmp(&key(&samples[*b]))
+                .unwrap_or(Ordering::Equal)
+        });
+        match self {
+            RepetitionPolicy::Best => order[0],
+            RepetitionPolicy::Median => order[order.len() / 2],
+            RepetitionPolicy::Worst => order[order.len() - 1],
+        }
+    }
+}
+
+impl Default for RepetitionPolicy {
+    fn default() -> Self {
+        RepetitionPolicy::Median
+    }
+}
+
+impl std::str::FromStr for RepetitionPolicy {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<RepetitionPolicy, Self::Err> {
+        match s.to_ascii_lowercase().as_str() {
+            "best" => Ok(RepetitionPolicy::Best),
+            "median" => Ok(RepetitionPolicy::Median),
+            "worst" => Ok(RepetitionPolicy::Worst),
+            _ => Err(format!("Unknown repetition policy: {}", s)),
+        }
+    }
+}
+
+impl ExecutionResult {
+    /// The outcomes of all the runs of the execution: just one if it has not been repeated.
+    pub fn all_samples(&self) -> Vec<ExecutionSample> {
+        if self.samples.is_empty() {
+            vec![ExecutionSample {
+                status: self.status.clone(),
+                was_killed: self.was_killed,
+                resources: self.resources.clone(),
+            }]
+        } else {
+            self.samples.clone()
+        }
+    }
+
+    /// Attach the outcomes of all the runs of the execution to this result, which should be the
+    /// one of the run chosen by the `RepetitionPolicy`.
+    pub fn with_samples(mut self, samples: Vec<ExecutionSample>) -> ExecutionResult {
+        if samples.len() > 1 {
+            self.samples = samples;
+        }
+        self
+    }
+
+    /// The statistics of the CPU time of the runs, `None` if the execution has not been repeated.
+    pub fn timing_stats(&self) -> Option<TimingStats> {
+        if self.samples.len() <= 1 {
+            return None;
+        }
+        let mut times: Vec<f64> = self
+            .samples
+            .iter()
+            .map(|sample| sample.resources.cpu_time)
+            .collect();
+        times.sort_by(|a, b| a.partial_cmp(b).unwrap_or(Ordering::Equal));
+        let n = times.len();
+        let median = if n % 2 == 1 {
+            times[n / 2]
+        } else {
+            (times[n / 2 - 1] + times[n / 2]) / 2.0
+        };
+        Some(TimingStats {
+            min: times[0],
+            median,
+            spread: times[n - 1] - times[0],
+        })
+    }
+}
+
 impl ExecutionStatus {
     /// Returns true only if it is `ExecutionStatus::Success`.
     pub fn is_success(&self) -> bool {
@@ -390,6 +554,28 @@ impl ExecutionCommand {
     pub fn local<P: Into<PathBuf>>(path: P) -> ExecutionCommand {
         ExecutionCommand::Local(path.into())
     }
+
+    /// Make a new `ExecutionCommand::Builtin`.
+    pub fn builtin(command: BuiltinCommand) -> ExecutionCommand {
+        ExecutionCommand::Builtin(command)
+    }
+}
+
+// the epsilons are never NaN (the task parsers reject them), so the equality is reflexive
+impl Eq for BuiltinCommand {}
+
+impl Hash for BuiltinCommand {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        std::mem::discriminant(self).hash(state);
+        if let BuiltinCommand::CompareFloats {
+            absolute_epsilon,
+            relative_epsilon,
+        } = self
+        {
+            absolute_epsilon.to_bits().hash(state);
+            relative_epsilon.to_bits().hash(state);
+        }
+    }
 }
 
 impl Execution {
@@ -413,6 +599,8 @@ impl Execution {
             stderr: None,
             capture_stdout: None,
             capture_stderr: None,
+            stdin_fifo: None,
+            stdout_fifo: None,
             inputs: HashMap::new(),
             outputs: HashMap::new(),
 
@@ -424,6 +612,7 @@ impl Execution {
 
             tag: None,
             priority: Priority::default(),
+            repetitions: 1,
         }
     }
 
@@ -505,6 +694,47 @@ impl Execution {
         self
     }
 
+    /// Bind the standard input to a FIFO of the group of this execution, removing the file bound
+    /// to the standard input, if any. The execution must be added to the same `ExecutionGroup` of
+    /// the FIFO.
+    ///
+    /// ```
+    /// use task_maker_dag::{Execution, ExecutionCommand, ExecutionGroup};
+    ///
+    /// let mut group = ExecutionGroup::new("group");
+    /// let fifo = group.new_fifo();
+    /// let mut exec = Execution::new("reader", ExecutionCommand::local("foo"));
+    /// exec.stdin_fifo(fifo);
+    /// assert_eq!(exec.stdin_fifo.unwrap().uuid, fifo.uuid);
+    /// assert_eq!(exec.stdin, None);
+    /// ```
+    pub fn stdin_fifo(&mut self, fifo: Fifo) -> &mut Self {
+        self.stdin = None;
+        self.stdin_fifo = Some(fifo);
+        self
+    }
+
+    /// Bind the standard output to a FIFO of the group of this execution, removing the file bound
+    /// to the standard output, if any. The execution must be added to the same `ExecutionGroup` of
+    /// the FIFO.
+    ///
+    /// ```
+    /// use task_maker_dag::{Execution, ExecutionCommand, ExecutionGroup};
+    ///
+    /// let mut group = ExecutionGroup::new("group");
+    /// let fifo = group.new_fifo();
+    /// let mut exec = Execution::new("writer", ExecutionCommand::local("foo"));
+    /// exec.stdout_fifo(fifo);
+    /// assert_eq!(exec.stdout_fifo.unwrap().uuid, fifo.uuid);
+    /// assert_eq!(exec.stdout, None);
+    /// ```
+    pub fn stdout_fifo(&mut self, fifo: Fifo) -> &mut Self {
+        self.stdout = None;
+        self.capture_stdout = None;
+        self.stdout_fifo = Some(fifo);
+        self
+    }
+
     /// Handle to the standard output of the execution. This should be called at least once before
     /// the evaluation starts in order to track the file. Calling this method more than once will
     /// return the same value.
@@ -667,6 +897,12 @@ impl Execution {
         self
     }
 
+    /// Run this `Execution` that many times, for measuring its resources more reliably.
+    pub fn repetitions(&mut self, repetitions: usize) -> &mut Self {
+        self.repetitions = repetitions.max(1);
+        self
+    }
+
     /// Compute the [`ExecutionStatus`](struct.ExecutionStatus.html) based on the result of the
     /// execution, checking the signals, the return code and the time/memory constraints.
     pub fn status(
@@ -736,6 +972,7 @@ impl std::fmt::Debug for ExecutionResult {
             .field("was_killed", &self.was_killed)
             .field("was_cached", &self.was_cached)
             .field("resources", &self.resources)
+            .field("samples", &self.samples)
             .field(
                 "stdout",
                 &self
@@ -879,4 +1116,69 @@ mod tests {
         );
         assert_eq!(ExecutionStatus::ReturnCode(1), status);
     }
+
+    fn sample(status: ExecutionStatus, cpu_time: f64) -> ExecutionSample {
+        ExecutionSample {
+            status,
+            was_killed: false,
+            resources: ExecutionResourcesUsage {
+                cpu_time,
+                ..Default::default()
+            },
+        }
+    }
+
+    #[test]
+    fn test_repetition_policy() {
+        let samples = vec![
+            sample(ExecutionStatus::Success, 0.5),
+            sample(ExecutionStatus::TimeLimitExceeded, 1.1),
+            sample(ExecutionStatus::Success, 0.3),
+            sample(ExecutionStatus::Success, 0.4),
+        ];
+        assert_eq!(RepetitionPolicy::Best.choose(&samples), 2);
+        assert_eq!(RepetitionPolicy::Median.choose(&samples), 0);
+        assert_eq!(RepetitionPolicy::Worst.choose(&samples), 1);
+    }
+
+    #[test]
+    fn test_with_samples() {
+        let result = ExecutionResult {
+            status: ExecutionStatus::Success,
+            was_killed: false,
+            was_cached: false,
+            resources: ExecutionResourcesUsage::default(),
+            stdout: Some(vec![42]),
+            stderr: None,
+            samples: vec![],
+        };
+        let samples = vec![
+            sample(ExecutionStatus::Success, 0.5),
+            sample(ExecutionStatus::TimeLimitExceeded, 1.1),
+            sample(ExecutionStatus::Success, 0.3),
+        ];
+        let result = result.with_samples(samples);
+        assert_eq!(result.status, ExecutionStatus::Success);
+        assert_eq!(result.stdout, Some(vec![42]));
+        assert_eq!(result.all_samples().len(), 3);
+        let stats = result.timing_stats().unwrap();
+        assert_abs_diff_eq!(stats.min, 0.3);
+        assert_abs_diff_eq!(stats.median, 0.5);
+        assert_abs_diff_eq!(stats.spread, 0.8);
+    }
+
+    #[test]
+    fn test_timing_stats_single_run() {
+        let result = ExecutionResult {
+            status: ExecutionStatus::Success,
+            was_killed: false,
+            was_cached: false,
+            resources: ExecutionResourcesUsage::default(),
+            stdout: None,
+            stderr: None,
+            samples: vec![],
+        };
+        assert_eq!(result.all_samples().len(), 1);
+        assert!(result.timing_stats().is_none());
+    }
 }
@@ -86,6 +86,15 @@ impl ExecutionGroup {
             .unwrap_or(0)
     }
 
+    /// How many times this group is run: the most repetitions among its executions.
+    pub fn repetitions(&self) -> usize {
+        self.executions
+            .iter()
+            .map(|e| e.repetitions)
+            .max()
+            .unwrap_or(1)
+    }
+
     /// A reference to the configuration of the underlying DAG.
     pub fn config(&self) -> &ExecutionDAGConfig {
         self.executions
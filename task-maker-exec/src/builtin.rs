@@ -0,0 +1,363 @@
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+use std::time::Instant;
+
+use failure::{bail, format_err, Error};
+
+use task_maker_dag::{
+    BuiltinCommand, Execution, ExecutionResourcesUsage, ExecutionResult, ExecutionStatus, FileUuid,
+};
+use task_maker_store::FileStoreHandle;
+
+/// The maximum number of characters of a token to include in the messages.
+const MAX_TOKEN_LENGTH: usize = 32;
+
+/// Run a builtin command of an execution directly inside the worker, reading the input files from
+/// the file store. The score and the message are written like the stdout and stderr of a process:
+/// the files are stored in `output_dir` and the returned map tells where each output file is.
+pub(crate) fn run_builtin(
+    command: &BuiltinCommand,
+    execution: &Execution,
+    files: &HashMap<FileUuid, FileStoreHandle>,
+    output_dir: &Path,
+) -> (ExecutionResult, HashMap<FileUuid, PathBuf>) {
+    let start = Instant::now();
+    let (mut status, stdout, stderr) = match run_comparison(command, execution, files) {
+        Ok((score, message)) => (
+            ExecutionStatus::Success,
+            format!("{}\n", score),
+            format!("{}\n", message),
+        ),
+        Err(e) => (
+            ExecutionStatus::InternalError(e.to_string()),
+            String::new(),
+            String::new(),
+        ),
+    };
+    let mut outputs = HashMap::new();
+    let streams = [
+        (&execution.stdout, "stdout", &stdout),
+        (&execution.stderr, "stderr", &stderr),
+    ];
+    for (file, name, content) in streams.iter() {
+        if let Some(file) = file {
+            let path = output_dir.join(name);
+            if let Err(e) = std::fs::write(&path, content) {
+                status = ExecutionStatus::InternalError(format!("Cannot write {}: {}", name, e));
+            }
+            outputs.insert(file.uuid, path);
+        }
+    }
+    let result = ExecutionResult {
+        status,
+        was_killed: false,
+        was_cached: false,
+        resources: ExecutionResourcesUsage {
+            wall_time: start.elapsed().as_secs_f64(),
+            ..Default::default()
+        },
+        stdout: capture(stdout, execution.capture_stdout),
+        stderr: capture(stderr, execution.capture_stderr),
+        samples: vec![],
+    };
+    (result, outputs)
+}
+
+/// Read the files named by the arguments of the execution and compare them.
+fn run_comparison(
+    command: &BuiltinCommand,
+    execution: &Execution,
+    files: &HashMap<FileUuid, FileStoreHandle>,
+) -> Result<(f64, String), Error> {
+    if !execution.outputs.is_empty() {
+        bail!("Builtin command {:?} cannot produce output files", command);
+    }
+    if execution.args.len() != 2 {
+        bail!(
+            "Builtin command {:?} needs 2 arguments, {} provided",
+            command,
+            execution.args.len()
+        );
+    }
+    let read = |arg: &str| -> Result<Vec<u8>, Error> {
+        let input = execution
+            .inputs
+            .get(Path::new(arg))
+            .ok_or_else(|| format_err!("Builtin command input {} not found", arg))?;
+        let handle = files
+            .get(&input.file)
+            .ok_or_else(|| format_err!("Builtin command input {} not provided", arg))?;
+        Ok(std::fs::read(handle.path())?)
+    };
+    let correct = read(&execution.args[0])?;
+    let test = read(&execution.args[1])?;
+    Ok(compare(command, &correct, &test))
+}
+
+/// Keep at most `count` bytes of the content, if `count` is `None` nothing is captured.
+fn capture(content: String, count: Option<usize>) -> Option<Vec<u8>> {
+    count.map(|count| content.into_bytes().into_iter().take(count).collect())
+}
+
+/// Compare the correct file with the file to check using the builtin command, returning the score
+/// and the message for the user.
+fn compare(command: &BuiltinCommand, correct: &[u8], test: &[u8]) -> (f64, String) {
+    if let BuiltinCommand::CompareWhiteDiff = command {
+        return if lines(correct).eq(lines(test)) {
+            (1.0, "Output is correct".into())
+        } else {
+            (0.0, "Output is incorrect".into())
+        };
+    }
+    let mut correct_tokens = tokens(correct);
+    let mut test_tokens = tokens(test);
+    let mut index = 1;
+    loop {
+        match (correct_tokens.next(), test_tokens.next()) {
+            (None, None) => return (1.0, "Output is correct".into()),
+            (Some(expected), None) => {
+                return (
+                    0.0,
+                    format!(
+                        "Output is incorrect: expected {} at token {}, found end of file",
+                        display_token(expected),
+                        index
+                    ),
+                )
+            }
+            (None, Some(found)) => {
+                return (
+                    0.0,
+                    format!(
+                        "Output is incorrect: expected end of file at token {}, found {}",
+                        index,
+                        display_token(found)
+                    ),
+                )
+            }
+            (Some(expected), Some(found)) => {
+                if !tokens_match(command, expected, found) {
+                    return (
+                        0.0,
+                        format!(
+                            "Output is incorrect: token {} differs, expected {}, found {}",
+                            index,
+                            display_token(expected),
+                            display_token(found)
+                        ),
+                    );
+                }
+            }
+        }
+        index += 1;
+    }
+}
+
+/// Split the content of a file in lines, like `diff --ignore-blank-lines --ignore-space-change`:
+/// the trailing white spaces are removed, the other sequences of white spaces are replaced by a
+/// single space and the blank lines are skipped.
+fn lines(content: &[u8]) -> impl Iterator<Item = Vec<u8>> + '_ {
+    content
+        .split(|c| *c == b'\n')
+        .map(|line| {
+            let mut normalized = Vec::with_capacity(line.len());
+            let mut in_space = false;
+            for &c in line {
+                if c.is_ascii_whitespace() {
+                    in_space = true;
+                } else {
+                    if in_space {
+                        normalized.push(b' ');
+                        in_space = false;
+                    }
+                    normalized.push(c);
+                }
+            }
+            normalized
+        })
+        .filter(|line| !line.is_empty())
+}
+
+/// Split the content of a file in tokens separated by white spaces.
+fn tokens(content: &[u8]) -> impl Iterator<Item = &[u8]> {
+    content
+        .split(|c| c.is_ascii_whitespace())
+        .filter(|token| !token.is_empty())
+}
+
+/// Check if the token of the file to check matches the correct one.
+fn tokens_match(command: &BuiltinCommand, expected: &[u8], found: &[u8]) -> bool {
+    match command {
+        BuiltinCommand::CompareTokens | BuiltinCommand::CompareWhiteDiff => expected == found,
+        BuiltinCommand::CompareTokensCaseInsensitive => expected.eq_ignore_ascii_case(found),
+        BuiltinCommand::CompareFloats {
+            absolute_epsilon,
+            relative_epsilon,
+        } => match (parse_float(expected), parse_float(found)) {
+            (Some(expected), Some(found)) => {
+                // nan and the infinities are never within the epsilons, they match only themselves
+                if !expected.is_finite() || !found.is_finite() {
+                    return expected == found || (expected.is_nan() && found.is_nan());
+                }
+                let diff = (expected - found).abs();
+                diff <= *absolute_epsilon || diff <= relative_epsilon * expected.abs()
+            }
+            // the correct token is not a number, compare it as is
+            (None, _) => expected == found,
+            (Some(_), None) => false,
+        },
+    }
+}
+
+/// Parse a token as a floating point number, if it is one.
+fn parse_float(token: &[u8]) -> Option<f64> {
+    std::str::from_utf8(token).ok()?.parse().ok()
+}
+
+/// Format a token to be included in a message, truncating it if it's too long.
+fn display_token(token: &[u8]) -> String {
+    let token = String::from_utf8_lossy(token);
+    if token.chars().count() > MAX_TOKEN_LENGTH {
+        let truncated: String = token.chars().take(MAX_TOKEN_LENGTH).collect();
+        format!("'{}...'", truncated)
+    } else {
+        format!("'{}'", token)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn float(absolute_epsilon: f64, relative_epsilon: f64) -> BuiltinCommand {
+        BuiltinCommand::CompareFloats {
+            absolute_epsilon,
+            relative_epsilon,
+        }
+    }
+
+    #[test]
+    fn test_compare_tokens_correct() {
+        let (score, message) = compare(&BuiltinCommand::CompareTokens, b"1 2\n3\n", b"  1\n\n2 3");
+        assert_eq!(score, 1.0);
+        assert_eq!(message, "Output is correct");
+    }
+
+    #[test]
+    fn test_compare_tokens_differ() {
+        let (score, message) = compare(&BuiltinCommand::CompareTokens, b"1 2 3", b"1 4 3");
+        assert_eq!(score, 0.0);
+        assert_eq!(
+            message,
+            "Output is incorrect: token 2 differs, expected '2', found '4'"
+        );
+    }
+
+    #[test]
+    fn test_compare_tokens_missing() {
+        let (score, message) = compare(&BuiltinCommand::CompareTokens, b"1 2 3", b"1 2");
+        assert_eq!(score, 0.0);
+        assert_eq!(
+            message,
+            "Output is incorrect: expected '3' at token 3, found end of file"
+        );
+    }
+
+    #[test]
+    fn test_compare_tokens_extra() {
+        let (score, message) = compare(&BuiltinCommand::CompareTokens, b"1 2", b"1 2 3");
+        assert_eq!(score, 0.0);
+        assert_eq!(
+            message,
+            "Output is incorrect: expected end of file at token 3, found '3'"
+        );
+    }
+
+    #[test]
+    fn test_compare_tokens_case_sensitive() {
+        let (score, _) = compare(&BuiltinCommand::CompareTokens, b"YES", b"yes");
+        assert_eq!(score, 0.0);
+    }
+
+    #[test]
+    fn test_compare_case_insensitive() {
+        let command = BuiltinCommand::CompareTokensCaseInsensitive;
+        assert_eq!(compare(&command, b"YES no", b"yes No").0, 1.0);
+        assert_eq!(compare(&command, b"YES", b"yess").0, 0.0);
+    }
+
+    #[test]
+    fn test_compare_floats_absolute() {
+        let command = float(1e-3, 0.0);
+        assert_eq!(compare(&command, b"1.0 2.0", b"1.0005 1.9995").0, 1.0);
+        assert_eq!(compare(&command, b"1.0", b"1.01").0, 0.0);
+    }
+
+    #[test]
+    fn test_compare_floats_relative() {
+        let command = float(0.0, 1e-3);
+        assert_eq!(compare(&command, b"1000", b"1000.5").0, 1.0);
+        assert_eq!(compare(&command, b"1", b"1.5").0, 0.0);
+    }
+
+    #[test]
+    fn test_compare_floats_not_numbers() {
+        let command = float(1e-3, 1e-3);
+        assert_eq!(compare(&command, b"case 1.0", b"case 1.0001").0, 1.0);
+        assert_eq!(compare(&command, b"case", b"Case").0, 0.0);
+        let (score, message) = compare(&command, b"1.0", b"one");
+        assert_eq!(score, 0.0);
+        assert_eq!(
+            message,
+            "Output is incorrect: token 1 differs, expected '1.0', found 'one'"
+        );
+    }
+
+    #[test]
+    fn test_compare_floats_nan() {
+        let command = float(1e-3, 1e-3);
+        assert_eq!(compare(&command, b"nan", b"NaN").0, 1.0);
+        assert_eq!(compare(&command, b"nan", b"1.0").0, 0.0);
+        assert_eq!(compare(&command, b"1.0", b"nan").0, 0.0);
+    }
+
+    #[test]
+    fn test_compare_floats_infinite() {
+        let command = float(1e-3, 1e-3);
+        assert_eq!(compare(&command, b"inf", b"inf").0, 1.0);
+        assert_eq!(compare(&command, b"inf", b"-inf").0, 0.0);
+        assert_eq!(compare(&command, b"inf", b"1e300").0, 0.0);
+        assert_eq!(compare(&command, b"1.0", b"inf").0, 0.0);
+    }
+
+    #[test]
+    fn test_compare_white_diff_correct() {
+        let command = BuiltinCommand::CompareWhiteDiff;
+        let (score, message) = compare(&command, b"1  2\n\n3\n", b"1 2 \n3\n\n\n");
+        assert_eq!(score, 1.0);
+        assert_eq!(message, "Output is correct");
+    }
+
+    #[test]
+    fn test_compare_white_diff_incorrect() {
+        let command = BuiltinCommand::CompareWhiteDiff;
+        let (score, message) = compare(&command, b"1 2\n3\n", b"1\n2 3\n");
+        assert_eq!(score, 0.0);
+        assert_eq!(message, "Output is incorrect");
+        assert_eq!(compare(&command, b"1 2\n", b" 1 2\n").0, 0.0);
+        assert_eq!(compare(&command, b"1 2\n", b"1 2\n3\n").0, 0.0);
+    }
+
+    #[test]
+    fn test_capture() {
+        assert_eq!(capture("1234".into(), Some(2)), Some(b"12".to_vec()));
+        assert_eq!(capture("1234".into(), None), None);
+    }
+
+    #[test]
+    fn test_display_token_truncated() {
+        let token = vec![b'a'; 100];
+        let expected = format!("'{}...'", "a".repeat(MAX_TOKEN_LENGTH));
+        assert_eq!(display_token(&token), expected);
+    }
+}
@@ -0,0 +1,324 @@
+use std::collections::BTreeSet;
+use std::path::Path;
+
+use failure::{bail, format_err, Error};
+use serde::{Deserialize, Serialize};
+
+use task_maker_dag::Execution;
+
+/// The file listing the online CPU cores of the system.
+const ONLINE_CORES_PATH: &str = "/sys/devices/system/cpu/online";
+
+/// The set of cores a worker is allowed to use for its sandboxes.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct CpuAffinity {
+    /// The cores reserved to this worker for running the timing-sensitive executions, one per
+    /// execution of the same group.
+    pub dedicated_cores: Vec<usize>,
+    /// The cores shared among all the workers for running all the other executions. If empty the
+    /// other executions are not pinned.
+    pub shared_cores: Vec<usize>,
+}
+
+/// How the cores of the machine are split between the timing-sensitive executions and all the other
+/// executions.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct CoreAssignment {
+    /// The cores dedicated to the timing-sensitive executions, one per worker.
+    pub dedicated: Vec<usize>,
+    /// The cores left for all the other executions.
+    pub shared: Vec<usize>,
+}
+
+impl CpuAffinity {
+    /// The cores the sandboxes of the executions of a group should be pinned to, `None` if the
+    /// sandbox can run on any core. Each timing-sensitive execution gets its own dedicated core;
+    /// the ones exceeding the dedicated cores of this worker run on the shared cores.
+    pub fn cores_for(&self, executions: &[Execution]) -> Vec<Option<Vec<usize>>> {
+        let mut dedicated = self.dedicated_cores.iter();
+        executions
+            .iter()
+            .map(|execution| {
+                let timing_sensitive = match &execution.tag {
+                    Some(tag) => execution.config().dedicated_core_tags.contains(tag),
+                    None => false,
+                };
+                if timing_sensitive && !self.dedicated_cores.is_empty() {
+                    if let Some(core) = dedicated.next() {
+                        return Some(vec![*core]);
+                    }
+                    warn!(
+                        "Execution {} does not have a dedicated core, only {} are available",
+                        execution.description,
+                        self.dedicated_cores.len()
+                    );
+                }
+                if self.shared_cores.is_empty() {
+                    None
+                } else {
+                    Some(self.shared_cores.clone())
+                }
+            })
+            .collect()
+    }
+}
+
+impl CoreAssignment {
+    /// Reserve the specified cores for the timing-sensitive executions. The hyperthreading
+    /// siblings of the reserved cores are not used by anyone, since they share the execution units
+    /// with the reserved ones. All the remaining online cores are shared by the other executions.
+    pub fn new(eval_cores: &[usize]) -> Result<CoreAssignment, Error> {
+        let online = std::fs::read_to_string(ONLINE_CORES_PATH)
+            .map_err(|e| format_err!("Cannot read the list of online cores: {}", e))?;
+        let online = parse_core_list(&online)?;
+        CoreAssignment::with_topology(eval_cores, &online, thread_siblings)
+    }
+
+    /// Split the `online` cores given the function that returns the hyperthreading siblings of a
+    /// core (including the core itself).
+    fn with_topology<F>(
+        eval_cores: &[usize],
+        online: &[usize],
+        siblings: F,
+    ) -> Result<CoreAssignment, Error>
+    where
+        F: Fn(usize) -> Vec<usize>,
+    {
+        let mut reserved = BTreeSet::new();
+        let mut dedicated = Vec::new();
+        for &core in eval_cores {
+            if !online.contains(&core) {
+                bail!("Core {} is not online", core);
+            }
+            // this core is a sibling of an already dedicated one
+            if reserved.contains(&core) {
+                continue;
+            }
+            dedicated.push(core);
+            reserved.insert(core);
+            reserved.extend(siblings(core));
+        }
+        let shared: Vec<_> = online
+            .iter()
+            .filter(|core| !reserved.contains(core))
+            .cloned()
+            .collect();
+        if shared.is_empty() {
+            bail!("At least one core should be left for the non-evaluation executions");
+        }
+        Ok(CoreAssignment { dedicated, shared })
+    }
+
+    /// The affinity of the worker with the specified index, out of `num_workers`: the dedicated
+    /// cores are split evenly between the workers, so that each worker has at least one of them.
+    pub fn worker_affinity(&self, index: usize, num_workers: usize) -> CpuAffinity {
+        CpuAffinity {
+            dedicated_cores: self
+                .dedicated
+                .iter()
+                .skip(index)
+                .step_by(num_workers.max(1))
+                .cloned()
+                .collect(),
+            shared_cores: self.shared.clone(),
+        }
+    }
+
+    /// Check that each of the `num_workers` workers can have at least a dedicated core, otherwise
+    /// some evaluations would not be pinned.
+    pub fn check_num_workers(&self, num_workers: usize) -> Result<(), Error> {
+        if num_workers > self.dedicated.len() {
+            bail!(
+                "Cannot spawn {} workers with only {} evaluation cores: each worker needs at \
+                 least one of them",
+                num_workers,
+                self.dedicated.len()
+            );
+        }
+        Ok(())
+    }
+}
+
+/// Parse a list of cores in the format used by the kernel (e.g. `0-3,6,8-9`).
+pub fn parse_core_list(list: &str) -> Result<Vec<usize>, Error> {
+    let mut cores = Vec::new();
+    for range in list.trim().split(',') {
+        let range = range.trim();
+        let parse = |core: &str| -> Result<usize, Error> {
+            core.trim()
+                .parse()
+                .map_err(|_| format_err!("Invalid core number: {:?}", core))
+        };
+        if let Some(dash) = range.find('-') {
+            let (from, to) = (parse(&range[..dash])?, parse(&range[dash + 1..])?);
+            if from > to {
+                bail!("Invalid core range: {:?}", range);
+            }
+            cores.extend(from..=to);
+        } else {
+            cores.push(parse(range)?);
+        }
+    }
+    cores.sort();
+    cores.dedup();
+    Ok(cores)
+}
+
+/// The hyperthreading siblings of a core, including the core itself. If the topology of the core
+/// is not available, the core is assumed to have no siblings.
+fn thread_siblings(core: usize) -> Vec<usize> {
+    let path = Path::new("/sys/devices/system/cpu")
+        .join(format!("cpu{}", core))
+        .join("topology/thread_siblings_list");
+    std::fs::read_to_string(path)
+        .ok()
+        .and_then(|list| parse_core_list(&list).ok())
+        .unwrap_or_else(|| vec![core])
+}
+
+/// Pin the current thread to the specified cores. The processes spawned by this thread inherit
+/// the affinity.
+#[cfg(target_os = "linux")]
+pub(crate) fn set_thread_affinity(cores: &[usize]) -> Result<(), Error> {
+    use nix::sched::{sched_setaffinity, CpuSet};
+    use nix::unistd::Pid;
+
+    let mut cpu_set = CpuSet::new();
+    for &core in cores {
+        cpu_set.set(core)?;
+    }
+    // PID 0 is the calling thread
+    sched_setaffinity(Pid::from_raw(0), &cpu_set)?;
+    Ok(())
+}
+
+/// Pin the current thread to the specified cores. The processes spawned by this thread inherit
+/// the affinity.
+#[cfg(not(target_os = "linux"))]
+pub(crate) fn set_thread_affinity(_cores: &[usize]) -> Result<(), Error> {
+    bail!("Pinning the executions to a core is supported only on Linux");
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use task_maker_dag::{ExecutionCommand, ExecutionDAG, ExecutionGroup, ExecutionTag};
+
+    fn no_siblings(core: usize) -> Vec<usize> {
+        vec![core]
+    }
+
+    fn ht_siblings(core: usize) -> Vec<usize> {
+        // cores N and N+4 share the same physical core
+        vec![core % 4, core % 4 + 4]
+    }
+
+    #[test]
+    fn test_parse_core_list() {
+        assert_eq!(parse_core_list("3").unwrap(), vec![3]);
+        assert_eq!(parse_core_list("2-5").unwrap(), vec![2, 3, 4, 5]);
+        assert_eq!(parse_core_list("0-1,4,6-7\n").unwrap(), vec![0, 1, 4, 6, 7]);
+        assert_eq!(parse_core_list("4,1-2,2").unwrap(), vec![1, 2, 4]);
+    }
+
+    #[test]
+    fn test_parse_core_list_invalid() {
+        assert!(parse_core_list("").is_err());
+        assert!(parse_core_list("a-3").is_err());
+        assert!(parse_core_list("5-2").is_err());
+        assert!(parse_core_list("1,,2").is_err());
+    }
+
+    #[test]
+    fn test_core_assignment() {
+        let online: Vec<_> = (0..8).collect();
+        let assignment = CoreAssignment::with_topology(&[2, 3, 4], &online, no_siblings).unwrap();
+        assert_eq!(assignment.dedicated, vec![2, 3, 4]);
+        assert_eq!(assignment.shared, vec![0, 1, 5, 6, 7]);
+    }
+
+    #[test]
+    fn test_core_assignment_siblings() {
+        let online: Vec<_> = (0..8).collect();
+        let assignment = CoreAssignment::with_topology(&[2, 3, 6], &online, ht_siblings).unwrap();
+        assert_eq!(assignment.dedicated, vec![2, 3]);
+        assert_eq!(assignment.shared, vec![0, 1, 4, 5]);
+    }
+
+    #[test]
+    fn test_core_assignment_offline() {
+        let online: Vec<_> = (0..4).collect();
+        assert!(CoreAssignment::with_topology(&[5], &online, no_siblings).is_err());
+    }
+
+    #[test]
+    fn test_core_assignment_no_shared() {
+        let online: Vec<_> = (0..4).collect();
+        assert!(CoreAssignment::with_topology(&[0, 1, 2, 3], &online, no_siblings).is_err());
+    }
+
+    #[test]
+    fn test_worker_affinity() {
+        let assignment = CoreAssignment {
+            dedicated: vec![2, 3],
+            shared: vec![0, 1],
+        };
+        let affinity = assignment.worker_affinity(1, 2);
+        assert_eq!(affinity.dedicated_cores, vec![3]);
+        assert_eq!(affinity.shared_cores, vec![0, 1]);
+        let affinity = assignment.worker_affinity(0, 1);
+        assert_eq!(affinity.dedicated_cores, vec![2, 3]);
+        assert_eq!(affinity.shared_cores, vec![0, 1]);
+    }
+
+    #[test]
+    fn test_check_num_workers() {
+        let assignment = CoreAssignment {
+            dedicated: vec![2, 3],
+            shared: vec![0, 1],
+        };
+        assert!(assignment.check_num_workers(1).is_ok());
+        assert!(assignment.check_num_workers(2).is_ok());
+        assert!(assignment.check_num_workers(3).is_err());
+    }
+
+    #[test]
+    fn test_cores_for() {
+        let tag = ExecutionTag::from("evaluation");
+        let mut dag = ExecutionDAG::new();
+        dag.config_mut().dedicated_core_tag(tag.clone());
+        let mut group = ExecutionGroup::new("group");
+        for name in &["eval1", "eval2", "eval3"] {
+            let mut exec = Execution::new(*name, ExecutionCommand::system("true"));
+            exec.tag(tag.clone());
+            group.add_execution(exec);
+        }
+        group.add_execution(Execution::new("other", ExecutionCommand::system("true")));
+        dag.add_execution_group(group);
+        let execs = &dag
+            .data
+            .execution_groups
+            .values()
+            .next()
+            .unwrap()
+            .executions;
+        let affinity = CpuAffinity {
+            dedicated_cores: vec![3, 4],
+            shared_cores: vec![0, 1],
+        };
+        assert_eq!(
+            affinity.cores_for(execs),
+            vec![
+                Some(vec![3]),
+                Some(vec![4]),
+                Some(vec![0, 1]),
+                Some(vec![0, 1])
+            ]
+        );
+        assert_eq!(
+            CpuAffinity::default().cores_for(execs),
+            vec![None, None, None, None]
+        );
+    }
+}
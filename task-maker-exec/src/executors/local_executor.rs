@@ -10,6 +10,7 @@ use uuid::Uuid;
 use task_maker_cache::Cache;
 use task_maker_store::FileStore;
 
+use crate::cpu_affinity::CoreAssignment;
 use crate::executor::{Executor, ExecutorInMessage};
 use crate::proto::{ExecutorClientMessage, ExecutorServerMessage};
 use crate::sandbox_runner::SandboxRunner;
@@ -24,6 +25,9 @@ pub struct LocalExecutor {
     sandbox_path: PathBuf,
     /// The number of local workers to spawn.
     pub num_workers: usize,
+    /// How to split the cores between the timing-sensitive executions and the other ones. If
+    /// `None` the sandboxes are not pinned.
+    pub core_assignment: Option<CoreAssignment>,
 }
 
 impl LocalExecutor {
@@ -39,6 +43,7 @@ impl LocalExecutor {
             num_workers,
             file_store,
             sandbox_path: sandbox_path.into(),
+            core_assignment: None,
         }
     }
 
@@ -65,17 +70,23 @@ impl LocalExecutor {
         // share the runner for all the workers
         let sandbox_runner = Arc::new(sandbox_runner);
 
+        if let Some(assignment) = &self.core_assignment {
+            assignment.check_num_workers(self.num_workers)?;
+        }
         info!("Spawning {} workers", self.num_workers);
         let mut workers = vec![];
         // spawn the workers and connect them to the executor
         for i in 0..self.num_workers {
             let runner = sandbox_runner.clone();
-            let (worker, conn) = Worker::new(
+            let (mut worker, conn) = Worker::new(
                 &format!("Local worker {}", i),
                 self.file_store.clone(),
                 self.sandbox_path.clone(),
                 runner,
             );
+            if let Some(assignment) = &self.core_assignment {
+                worker.set_cpu_affinity(assignment.worker_affinity(i, self.num_workers));
+            }
             executor_tx
                 .send(ExecutorInMessage::WorkerConnected { worker: conn })
                 .unwrap();
@@ -13,7 +13,8 @@
 //! cancelled.
 //!
 //! All the tasks are run inside a [`Sandbox`](struct.Sandbox.html) provided by
-//! [`tabox`](https://crates.io/crates/tabox).
+//! [`tabox`](https://crates.io/crates/tabox), except for the builtin commands (i.e. the output
+//! comparators) which are run directly by the workers without spawning any process.
 //!
 //! ## Implementation details
 //!
@@ -82,6 +83,7 @@ use failure::Error;
 use scrypt::ScryptParams;
 
 pub use client::ExecutorClient;
+pub use cpu_affinity::{parse_core_list, CoreAssignment, CpuAffinity};
 pub use executor::{ExecutorStatus, ExecutorWorkerStatus, WorkerCurrentJobStatus};
 pub use sandbox::RawSandboxResult;
 pub use sandbox_runner::{
@@ -93,8 +95,10 @@ use task_maker_dag::ExecutionDAG;
 use task_maker_store::FileStore;
 pub use worker::{Worker, WorkerConn};
 
+mod builtin;
 mod check_dag;
 mod client;
+mod cpu_affinity;
 mod executor;
 pub mod executors;
 pub mod proto;
@@ -158,7 +162,7 @@ mod tests {
     extern crate pretty_assertions;
 
     use std::path::Path;
-    use std::sync::atomic::{AtomicBool, Ordering};
+    use std::sync::atomic::{AtomicBool, AtomicUsize, Ordering};
     use std::sync::Arc;
 
     use tempdir::TempDir;
@@ -247,4 +251,137 @@ mod tests {
         assert!(!cwd.path().join("stdout2").exists());
         assert!(!cwd.path().join("output3").exists());
     }
+
+    #[test]
+    fn test_local_evaluation_builtin() {
+        let cwd = TempDir::new("tm-test").unwrap();
+        std::fs::write(cwd.path().join("correct"), "1 2\n3\n").unwrap();
+        std::fs::write(cwd.path().join("test"), "1  2\n\n3").unwrap();
+        let mut dag = ExecutionDAG::new();
+
+        let correct = File::new("Correct file");
+        let test = File::new("Test file");
+        let mut exec = Execution::new(
+            "Compare",
+            ExecutionCommand::builtin(BuiltinCommand::CompareWhiteDiff),
+        );
+        exec.args(vec!["correct", "test"])
+            .input(&correct, "correct", false)
+            .input(&test, "test", false)
+            .capture_stdout(128)
+            .capture_stderr(1024);
+
+        let exec_done = Arc::new(AtomicBool::new(false));
+        let exec_done2 = exec_done.clone();
+        dag.provide_file(correct, cwd.path().join("correct"))
+            .unwrap();
+        dag.provide_file(test, cwd.path().join("test")).unwrap();
+        dag.on_execution_done(&exec.uuid, move |res| {
+            assert!(res.status.is_success());
+            assert_eq!(res.stdout.unwrap(), b"1\n");
+            assert_eq!(res.stderr.unwrap(), b"Output is correct\n");
+            exec_done.store(true, Ordering::Relaxed);
+            Ok(())
+        });
+        dag.add_execution(exec);
+
+        eval_dag_locally(
+            dag,
+            cwd.path(),
+            1,
+            cwd.path(),
+            1000,
+            1000,
+            UnsafeSandboxRunner::default(),
+        );
+
+        assert!(exec_done2.load(Ordering::Relaxed));
+    }
+
+    #[test]
+    fn test_local_evaluation_exclusive() {
+        let cwd = TempDir::new("tm-test").unwrap();
+        let lock = cwd.path().join("lock");
+        let mut dag = ExecutionDAG::new();
+        dag.config_mut()
+            .exclusive_tag(ExecutionTag::from("evaluation"));
+
+        let mut done = vec![];
+        for i in 0..3 {
+            // fails if another execution is holding the lock at the same time
+            let mut exec = Execution::new(format!("Exec {}", i), ExecutionCommand::system("sh"));
+            exec.args(vec![
+                "-c".to_string(),
+                format!(
+                    "mkdir {0} && sleep 0.2 && rmdir {0} && echo {1}",
+                    lock.to_string_lossy(),
+                    i
+                ),
+            ])
+            .tag(ExecutionTag::from("evaluation"));
+            let exec_done = Arc::new(AtomicBool::new(false));
+            let exec_done2 = exec_done.clone();
+            dag.on_execution_done(&exec.uuid, move |res| {
+                assert!(res.status.is_success(), "{:?}", res.status);
+                exec_done.store(true, Ordering::Relaxed);
+                Ok(())
+            });
+            dag.add_execution(exec);
+            done.push(exec_done2);
+        }
+
+        eval_dag_locally(
+            dag,
+            cwd.path(),
+            3,
+            cwd.path(),
+            1000,
+            1000,
+            UnsafeSandboxRunner::default(),
+        );
+
+        assert!(done.iter().all(|done| done.load(Ordering::Relaxed)));
+    }
+
+    #[test]
+    fn test_local_evaluation_repeated() {
+        let cwd = TempDir::new("tm-test").unwrap();
+        let counter = cwd.path().join("counter");
+        let run = |repetitions: usize| {
+            let mut dag = ExecutionDAG::new();
+            let mut exec = Execution::new("Repeated", ExecutionCommand::system("sh"));
+            exec.args(vec![
+                "-c".to_string(),
+                format!("echo run >> {}", counter.to_string_lossy()),
+            ])
+            .repetitions(repetitions);
+            let samples = Arc::new(AtomicUsize::new(0));
+            let samples2 = samples.clone();
+            dag.on_execution_done(&exec.uuid, move |res| {
+                assert!(res.status.is_success(), "{:?}", res.status);
+                samples.store(res.samples.len(), Ordering::Relaxed);
+                Ok(())
+            });
+            dag.add_execution(exec);
+            eval_dag_locally(
+                dag,
+                cwd.path(),
+                2,
+                cwd.path(),
+                1000,
+                1000,
+                UnsafeSandboxRunner::default(),
+            );
+            samples2.load(Ordering::Relaxed)
+        };
+        let runs = || std::fs::read_to_string(&counter).unwrap().lines().count();
+
+        assert_eq!(run(3), 3);
+        assert_eq!(runs(), 3);
+        // the cached runs are reused, only the missing ones are done
+        assert_eq!(run(5), 5);
+        assert_eq!(runs(), 5);
+        assert_eq!(run(2), 5);
+        assert_eq!(runs(), 5);
+    }
 }
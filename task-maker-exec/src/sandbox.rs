@@ -17,6 +17,7 @@ use tempdir::TempDir;
 use task_maker_dag::*;
 use task_maker_store::*;
 
+use crate::cpu_affinity::set_thread_affinity;
 use crate::sandbox_runner::SandboxRunner;
 use std::fs::Permissions;
 
@@ -68,6 +69,8 @@ struct SandboxData {
     fifo_dir: Option<PathBuf>,
     /// The PID of the sandbox process, zero if not available or not spawned yet.
     box_pid: Arc<AtomicU32>,
+    /// The cores the sandbox process is pinned to, `None` if it can run on any core.
+    cpu_cores: Option<Vec<usize>>,
 }
 
 /// Response of the internal implementation of the sandbox.
@@ -109,6 +112,7 @@ impl Sandbox {
                 keep_sandbox: false,
                 fifo_dir,
                 box_pid: Arc::new(AtomicU32::new(0)),
+                cpu_cores: None,
             })),
         })
     }
@@ -116,12 +120,13 @@ impl Sandbox {
     /// Starts the sandbox and blocks the thread until the sandbox exits.
     pub fn run(&self, runner: &dyn SandboxRunner) -> Result<SandboxResult, Error> {
         let mut config = SandboxConfiguration::default();
-        let (boxdir, pid, keep, cmd) = {
+        let (boxdir, pid, keep, cores, cmd) = {
             let data = self.data.lock().unwrap();
             (
                 data.path().to_owned(),
                 data.box_pid.clone(),
                 data.keep_sandbox,
+                data.cpu_cores.clone(),
                 self.build_command(
                     data.path(),
                     &data.execution,
@@ -139,6 +144,14 @@ impl Sandbox {
         }
         trace!("Sandbox configuration: {:#?}", config);
 
+        if let Some(cores) = cores {
+            // the sandbox process is spawned by this thread, so it inherits its affinity
+            if let Err(e) = set_thread_affinity(&cores) {
+                return Ok(SandboxResult::Failed {
+                    error: format!("Cannot pin the sandbox to the cores {:?}: {}", cores, e),
+                });
+            }
+        }
         let raw_result = runner.run(config.build(), pid);
         if keep {
             std::fs::write(boxdir.join("result.txt"), format!("{:#?}", raw_result))?;
@@ -216,6 +229,12 @@ impl Sandbox {
         }
     }
 
+    /// Pin the sandbox process to the specified cores. Since the affinity is set on the thread
+    /// that spawns the sandbox, `run` should be called from a thread dedicated to this sandbox.
+    pub fn pin_to_cores(&mut self, cores: Vec<usize>) {
+        self.data.lock().unwrap().cpu_cores = Some(cores);
+    }
+
     /// Make the sandbox persistent, the sandbox directory won't be deleted after the execution.
     pub fn keep(&mut self) {
         let mut data = self.data.lock().unwrap();
@@ -286,12 +305,22 @@ impl Sandbox {
         // the box directory must be writable otherwise the output files cannot be written
         config.mount(boxdir.join("box"), &box_root, true);
         config.env("PATH", std::env::var("PATH").unwrap_or_default());
-        if execution.stdin.is_some() {
+        let fifo_path = |fifo: &Fifo| -> Result<PathBuf, Error> {
+            match &fifo_dir {
+                Some(dir) => Ok(dir.join(fifo.sandbox_path().file_name().unwrap())),
+                None => bail!("The execution uses a FIFO but it's not in a group"),
+            }
+        };
+        if let Some(fifo) = &execution.stdin_fifo {
+            config.stdin(fifo_path(fifo)?);
+        } else if execution.stdin.is_some() {
             config.stdin(boxdir.join("stdin"));
         } else {
             config.stdin("/dev/null");
         }
-        if execution.stdout.is_some() {
+        if let Some(fifo) = &execution.stdout_fifo {
+            config.stdout(fifo_path(fifo)?);
+        } else if execution.stdout.is_some() {
             config.stdout(boxdir.join("stdout"));
         } else {
             config.stdout("/dev/null");
@@ -355,6 +384,9 @@ impl Sandbox {
             ExecutionCommand::Local(cmd) => {
                 config.executable(box_root.join(cmd));
             }
+            ExecutionCommand::Builtin(cmd) => {
+                bail!("Builtin command {:?} cannot run inside the sandbox", cmd);
+            }
         };
         for arg in execution.args.iter() {
             config.arg(arg);
@@ -477,7 +509,7 @@ mod tests {
     use tabox::configuration::{DirectoryMount, SandboxConfiguration};
     use tabox::syscall_filter::SyscallFilterAction;
 
-    use task_maker_dag::{Execution, ExecutionCommand};
+    use task_maker_dag::{Execution, ExecutionCommand, ExecutionGroup};
 
     use crate::sandbox::Sandbox;
     use crate::ErrorSandboxRunner;
@@ -546,4 +578,45 @@ mod tests {
         assert_eq!(config.executable, Path::new("/box/foo"));
         assert_eq!(config.args, vec!["bar", "baz"]);
     }
+
+    #[test]
+    fn test_command_fifo_redirect() {
+        let tmpdir = tempdir::TempDir::new("tm-test").unwrap();
+        let mut group = ExecutionGroup::new("group");
+        let fifo_in = group.new_fifo();
+        let fifo_out = group.new_fifo();
+        let mut exec = Execution::new("test", ExecutionCommand::local("foo"));
+        exec.stdin_fifo(fifo_in).stdout_fifo(fifo_out);
+        let fifo_dir = tmpdir.path().join("pipes");
+        std::fs::create_dir_all(&fifo_dir).unwrap();
+        let sandbox = Sandbox::new(
+            tmpdir.path(),
+            &exec,
+            &HashMap::new(),
+            Some(fifo_dir.clone()),
+        )
+        .unwrap();
+        let mut config = SandboxConfiguration::default();
+        sandbox
+            .build_command(tmpdir.path(), &exec, &mut config, Some(fifo_dir.clone()))
+            .unwrap();
+        assert_eq!(config.stdin, Some(fifo_dir.join(fifo_in.uuid.to_string())));
+        assert_eq!(
+            config.stdout,
+            Some(fifo_dir.join(fifo_out.uuid.to_string()))
+        );
+    }
+
+    #[test]
+    fn test_command_fifo_redirect_without_group() {
+        let tmpdir = tempdir::TempDir::new("tm-test").unwrap();
+        let mut group = ExecutionGroup::new("group");
+        let mut exec = Execution::new("test", ExecutionCommand::local("foo"));
+        exec.stdin_fifo(group.new_fifo());
+        let sandbox = Sandbox::new(tmpdir.path(), &exec, &HashMap::new(), None).unwrap();
+        let mut config = SandboxConfiguration::default();
+        assert!(sandbox
+            .build_command(tmpdir.path(), &exec, &mut config, None)
+            .is_err());
+    }
 }
@@ -11,7 +11,7 @@ use uuid::Uuid;
 use task_maker_cache::{Cache, CacheResult};
 use task_maker_dag::{
     CacheMode, ExecutionDAGData, ExecutionGroup, ExecutionGroupUuid, ExecutionResult,
-    ExecutionUuid, FileUuid, Priority, WorkerUuid,
+    ExecutionSample, ExecutionTag, ExecutionUuid, FileUuid, Priority, RepetitionPolicy, WorkerUuid,
 };
 use task_maker_store::{FileStore, FileStoreHandle, FileStoreKey};
 
@@ -163,6 +163,66 @@ struct SchedulerClientData {
     /// The list of known [`FileStoreHandle`](../task_maker_store/struct.FileStoreHandle.html)s.
     /// Storing them here prevents the `FileStore` from flushing them away.
     file_handles: HashMap<FileUuid, FileStoreHandle>,
+    /// The runs done so far of the groups that are run more than once.
+    repeated_runs: HashMap<ExecutionGroupUuid, RepeatedRuns>,
+}
+
+/// The runs done so far of an execution group that is run more than once.
+#[derive(Debug, Default)]
+struct RepeatedRuns {
+    /// The outcomes of all the runs, with a list of samples for each execution of the group.
+    samples: Vec<Vec<ExecutionSample>>,
+    /// The runs whose results and outputs are known, the one chosen by the `RepetitionPolicy` is
+    /// sent to the client. The runs taken from the cache contribute only with the run that was
+    /// chosen at that time.
+    runs: Vec<(Vec<ExecutionResult>, HashMap<FileUuid, FileStoreHandle>)>,
+}
+
+impl RepeatedRuns {
+    /// Add a run of the group, with its results and outputs.
+    fn add_run(
+        &mut self,
+        result: Vec<ExecutionResult>,
+        outputs: HashMap<FileUuid, FileStoreHandle>,
+    ) {
+        if self.samples.is_empty() {
+            self.samples = vec![Vec::new(); result.len()];
+        }
+        for (samples, res) in self.samples.iter_mut().zip(result.iter()) {
+            samples.extend(res.all_samples());
+        }
+        self.runs.push((result, outputs));
+    }
+
+    /// The number of runs done so far.
+    fn done(&self) -> usize {
+        self.samples.iter().map(Vec::len).min().unwrap_or(0)
+    }
+
+    /// The results and the outputs of the run chosen by the policy, with the samples of all the
+    /// runs. A run is as good as its worst execution: a failed one, if any, otherwise the slowest
+    /// one.
+    fn choose(
+        self,
+        policy: RepetitionPolicy,
+    ) -> (Vec<ExecutionResult>, HashMap<FileUuid, FileStoreHandle>) {
+        let worst: Vec<_> = self
+            .runs
+            .iter()
+            .map(|(result, _)| {
+                let samples: Vec<_> = result.iter().flat_map(|res| res.all_samples()).collect();
+                samples[RepetitionPolicy::Worst.choose(&samples)].clone()
+            })
+            .collect();
+        let chosen = policy.choose(&worst);
+        let (result, outputs) = self.runs.into_iter().nth(chosen).expect("No runs");
+        let result = result
+            .into_iter()
+            .zip(self.samples.into_iter())
+            .map(|(res, samples)| res.with_samples(samples))
+            .collect();
+        (result, outputs)
+    }
 }
 
 impl SchedulerClientData {
@@ -181,6 +241,7 @@ impl SchedulerClientData {
             running_groups: HashSet::new(),
             missing_deps: HashMap::new(),
             file_handles: HashMap::new(),
+            repeated_runs: HashMap::new(),
         }
     }
 
@@ -398,6 +459,27 @@ impl Scheduler {
             bail!("Invalid worker result: the number of results does not match the number of executions");
         }
         client.running_groups.remove(&group_uuid);
+        let repetitions = group.repetitions();
+        let internal_error = result.iter().any(|r| r.status.is_internal_error());
+        let (result, outputs) = if repetitions > 1 && !internal_error {
+            let runs = client.repeated_runs.entry(group_uuid).or_default();
+            runs.add_run(result, outputs);
+            let done = runs.done();
+            if done < repetitions {
+                // run the group again, the client will know the result only after the last run
+                trace!("Execution group {} has run {} times", group_uuid, done);
+                client.ready_groups.insert(group_uuid);
+                self.ready_execs
+                    .push((group.priority(), group_uuid, client_uuid));
+                self.assign_jobs()?;
+                return Ok(());
+            }
+            let runs = client.repeated_runs.remove(&group_uuid).unwrap_or_default();
+            runs.choose(group.config().repetition_policy)
+        } else {
+            client.repeated_runs.remove(&group_uuid);
+            (result, outputs)
+        };
         self.exec_completed(client_uuid, &group, result, outputs)?;
         self.assign_jobs()?;
         self.check_completion(client_uuid)?;
@@ -745,7 +827,10 @@ impl Scheduler {
                 continue;
             }
             let group = dag.execution_groups[group_uuid].clone();
-            if !Scheduler::is_cacheable(&group, &cache_mode) {
+            // the cache has already been checked for the group that is being repeated
+            if !Scheduler::is_cacheable(&group, &cache_mode)
+                || client.repeated_runs.contains_key(&group.uuid)
+            {
                 not_cached.push((*priority, group.uuid, *client_uuid));
                 continue;
             }
@@ -753,6 +838,20 @@ impl Scheduler {
                 .cache
                 .get(&group, &client.file_handles, self.file_store.as_ref());
             match result {
+                CacheResult::Hit { result, outputs }
+                    if Scheduler::missing_runs(&group, &result) > 0 =>
+                {
+                    // keep the cached runs and do just the missing ones
+                    info!(
+                        "Execution {} is a partial cache hit, {} runs missing",
+                        group.uuid,
+                        Scheduler::missing_runs(&group, &result)
+                    );
+                    let mut runs = RepeatedRuns::default();
+                    runs.add_run(result, outputs);
+                    client.repeated_runs.insert(group.uuid, runs);
+                    not_cached.push((*priority, group.uuid, *client_uuid));
+                }
                 CacheResult::Hit { result, outputs } => {
                     info!("Execution {} is a cache hit!", group.uuid);
                     client.ready_groups.remove(&group.uuid);
@@ -772,6 +871,16 @@ impl Scheduler {
         Ok(())
     }
 
+    /// How many more times a group has to be run, given the cached results of its previous runs.
+    fn missing_runs(group: &ExecutionGroup, result: &[ExecutionResult]) -> usize {
+        let done = result
+            .iter()
+            .map(|res| res.all_samples().len())
+            .min()
+            .unwrap_or(0);
+        group.repetitions().saturating_sub(done)
+    }
+
     /// Whether an execution is eligible to be fetch from the cache.
     fn is_cacheable(group: &ExecutionGroup, cache_mode: &CacheMode) -> bool {
         if let (CacheMode::Except(set), Some(tag)) = (cache_mode, group.tag().as_ref()) {
@@ -782,17 +891,26 @@ impl Scheduler {
         true
     }
 
-    /// Give to each free worker a job from the ready executions.
+    /// Give to each free worker a job from the ready executions. The executions that cannot start
+    /// now because of an exclusive execution are kept in the queue.
     fn assign_jobs(&mut self) -> Result<(), Error> {
-        for (worker_uuid, worker) in self.connected_workers.iter_mut() {
-            if worker.current_job.is_some() {
-                continue;
-            }
-            let (_, group_uuid, client_uuid) = match self.ready_execs.pop() {
+        let free_workers: Vec<WorkerUuid> = self
+            .connected_workers
+            .values()
+            .filter(|worker| worker.current_job.is_none())
+            .map(|worker| worker.uuid)
+            .collect();
+        let mut blocked = Vec::new();
+        for worker_uuid in &free_workers {
+            let (_, group_uuid, client_uuid) = match self.next_job(&mut blocked) {
                 Some(exec) => exec,
                 None => break,
             };
             trace!("Assigning {} to worker {}", group_uuid, worker_uuid);
+            let worker = self
+                .connected_workers
+                .get_mut(worker_uuid)
+                .expect("Free worker is gone");
             worker.current_job = Some((client_uuid, group_uuid, Instant::now()));
             let client = if let Some(client) = self.clients.get_mut(&client_uuid) {
                 client
@@ -839,6 +957,121 @@ impl Scheduler {
                 }
             }
         }
+        self.ready_execs.extend(blocked);
         Ok(())
     }
+
+    /// Pop from the ready executions the one with the highest priority that can start now. The
+    /// executions that cannot start are moved to `blocked`.
+    fn next_job(
+        &mut self,
+        blocked: &mut Vec<(Priority, ExecutionGroupUuid, ClientUuid)>,
+    ) -> Option<(Priority, ExecutionGroupUuid, ClientUuid)> {
+        while let Some(exec) = self.ready_execs.pop() {
+            if self.is_blocked(exec.2, exec.1) {
+                trace!("Execution {} is blocked by an exclusive execution", exec.1);
+                blocked.push(exec);
+            } else {
+                return Some(exec);
+            }
+        }
+        None
+    }
+
+    /// Whether an execution group cannot start now because it would run in parallel with an
+    /// exclusive execution: an execution whose tag is exclusive for its client never runs in
+    /// parallel with the other executions with the same tag.
+    fn is_blocked(&self, client_uuid: ClientUuid, group_uuid: ExecutionGroupUuid) -> bool {
+        let (tag, exclusive) = match self.group_tag(client_uuid, group_uuid) {
+            Some(tag) => tag,
+            None => return false,
+        };
+        self.connected_workers
+            .values()
+            .filter_map(|worker| worker.current_job.as_ref())
+            .filter_map(|(client, group, _)| self.group_tag(*client, *group))
+            .any(|(running_tag, running_exclusive)| {
+                running_tag == tag && (exclusive || running_exclusive)
+            })
+    }
+
+    /// The tag of an execution group of a client, and whether that tag is exclusive for the
+    /// client.
+    fn group_tag(
+        &self,
+        client_uuid: ClientUuid,
+        group_uuid: ExecutionGroupUuid,
+    ) -> Option<(ExecutionTag, bool)> {
+        let client = self.clients.get(&client_uuid)?;
+        let tag = client.dag.execution_groups.get(&group_uuid)?.tag()?;
+        let exclusive = client.dag.config.exclusive_tags.contains(&tag);
+        Some((tag, exclusive))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use task_maker_dag::{ExecutionResourcesUsage, ExecutionStatus};
+
+    fn result(status: ExecutionStatus, cpu_time: f64, stdout: u8) -> ExecutionResult {
+        ExecutionResult {
+            status,
+            was_killed: false,
+            was_cached: false,
+            resources: ExecutionResourcesUsage {
+                cpu_time,
+                ..Default::default()
+            },
+            stdout: Some(vec![stdout]),
+            stderr: None,
+            samples: vec![],
+        }
+    }
+
+    #[test]
+    fn test_repeated_runs_choose() {
+        let mut runs = RepeatedRuns::default();
+        runs.add_run(
+            vec![result(ExecutionStatus::Success, 0.5, 1)],
+            HashMap::new(),
+        );
+        runs.add_run(
+            vec![result(ExecutionStatus::Success, 0.3, 2)],
+            HashMap::new(),
+        );
+        runs.add_run(
+            vec![result(ExecutionStatus::TimeLimitExceeded, 1.1, 3)],
+            HashMap::new(),
+        );
+        assert_eq!(runs.done(), 3);
+        let (result, _) = runs.choose(RepetitionPolicy::Best);
+        assert_eq!(result[0].status, ExecutionStatus::Success);
+        assert_eq!(result[0].stdout, Some(vec![2]));
+        assert_eq!(result[0].samples.len(), 3);
+    }
+
+    #[test]
+    fn test_repeated_runs_choose_group() {
+        let mut runs = RepeatedRuns::default();
+        runs.add_run(
+            vec![
+                result(ExecutionStatus::Success, 0.1, 1),
+                result(ExecutionStatus::Success, 0.9, 2),
+            ],
+            HashMap::new(),
+        );
+        runs.add_run(
+            vec![
+                result(ExecutionStatus::Success, 0.5, 3),
+                result(ExecutionStatus::Success, 0.5, 4),
+            ],
+            HashMap::new(),
+        );
+        // the runs are compared by their slowest execution
+        let (result, _) = runs.choose(RepetitionPolicy::Best);
+        assert_eq!(result[0].stdout, Some(vec![3]));
+        assert_eq!(result[1].stdout, Some(vec![4]));
+    }
 }
@@ -16,6 +16,8 @@ use uuid::Uuid;
 use task_maker_dag::*;
 use task_maker_store::*;
 
+use crate::builtin::run_builtin;
+use crate::cpu_affinity::CpuAffinity;
 use crate::executor::WorkerJob;
 use crate::proto::*;
 use crate::sandbox::{Sandbox, SandboxResult};
@@ -53,6 +55,8 @@ pub struct Worker {
     sandbox_path: PathBuf,
     /// The function that spawns an actual sandbox.
     sandbox_runner: Arc<dyn SandboxRunner>,
+    /// The cores this worker can pin its sandboxes to.
+    cpu_affinity: CpuAffinity,
 }
 
 /// An handle of the connection to the worker.
@@ -146,9 +150,15 @@ impl Worker {
             current_job: Arc::new(Mutex::new(WorkerCurrentJob::new())),
             sandbox_path,
             sandbox_runner: Arc::new(runner),
+            cpu_affinity: CpuAffinity::default(),
         }
     }
 
+    /// Set the cores this worker pins its sandboxes to. By default the sandboxes are not pinned.
+    pub fn set_cpu_affinity(&mut self, cpu_affinity: CpuAffinity) {
+        self.cpu_affinity = cpu_affinity;
+    }
+
     /// The worker body, this function will block until the worker disconnects.
     #[allow(clippy::cognitive_complexity)]
     pub fn work(self) -> Result<(), Error> {
@@ -164,6 +174,7 @@ impl Worker {
                     &$self.sender,
                     &$self.sandbox_path,
                     $self.sandbox_runner.clone(),
+                    &$self.cpu_affinity,
                 )?;
                 $current_sandbox_thread = Some(thread);
             }};
@@ -302,7 +313,25 @@ fn execute_job(
     sender: &ChannelSender<WorkerClientMessage>,
     sandbox_path: &Path,
     runner: Arc<dyn SandboxRunner>,
+    cpu_affinity: &CpuAffinity,
 ) -> Result<JoinHandle<()>, Error> {
+    let builtin = {
+        let current_job = current_job.lock().unwrap();
+        let job = current_job
+            .current_job
+            .as_ref()
+            .expect("Worker job is gone");
+        match job.0.group.executions.as_slice() {
+            [exec] => match &exec.command {
+                ExecutionCommand::Builtin(command) => Some(command.clone()),
+                _ => None,
+            },
+            _ => None,
+        }
+    };
+    if let Some(command) = builtin {
+        return execute_builtin_job(current_job, sender, sandbox_path, command);
+    }
     let (job, sandboxes, fifo_dir, server_asked_files) = {
         let mut current_job = current_job.lock().unwrap();
         let job = current_job
@@ -324,7 +353,8 @@ fn execute_job(
             Some(fifo_dir)
         };
         let keep_sandboxes = group.config().keep_sandboxes;
-        for exec in &group.executions {
+        let cores = cpu_affinity.cores_for(&group.executions);
+        for (exec, cores) in group.executions.iter().zip(cores) {
             let mut sandbox = Sandbox::new(
                 sandbox_path,
                 exec,
@@ -334,6 +364,9 @@ fn execute_job(
             if keep_sandboxes {
                 sandbox.keep();
             }
+            if let Some(cores) = cores {
+                sandbox.pin_to_cores(cores);
+            }
             boxes.push(sandbox);
         }
         let job = job.0.clone();
@@ -362,6 +395,53 @@ fn execute_job(
     Ok(join_handle)
 }
 
+/// Spawn a new thread that will run the builtin command of the job, without any sandbox, and will
+/// send the results back to the server.
+fn execute_builtin_job(
+    current_job: Arc<Mutex<WorkerCurrentJob>>,
+    sender: &ChannelSender<WorkerClientMessage>,
+    sandbox_path: &Path,
+    command: BuiltinCommand,
+) -> Result<JoinHandle<()>, Error> {
+    // the directory is kept until the output files are sent to the server
+    let output_dir = TempDir::new_in(sandbox_path, "builtin")?;
+    let (job, files, server_asked_files) = {
+        let mut current_job = current_job.lock().unwrap();
+        let (job, files) = current_job
+            .current_job
+            .as_ref()
+            .expect("Worker job is gone");
+        let (job, files) = (job.clone(), files.clone());
+        let (sender, receiver) = channel();
+        current_job.server_asked_files = Some(sender);
+        (job, files, receiver)
+    };
+    let sender = sender.clone();
+    let join_handle = std::thread::Builder::new()
+        .name(format!("Builtin command of {}", job.group.description))
+        .spawn(move || {
+            let (result, output_paths) = run_builtin(
+                &command,
+                &job.group.executions[0],
+                &files,
+                output_dir.path(),
+            );
+            let outputs = output_paths
+                .iter()
+                .map(|(uuid, path)| (*uuid, FileStoreKey::from_file(path).unwrap()))
+                .collect();
+            finish_job(
+                &current_job,
+                &sender,
+                server_asked_files,
+                vec![result],
+                outputs,
+                output_paths,
+            );
+        })?;
+    Ok(join_handle)
+}
+
 /// The sandbox group manager spawns the threads of the sandbox of all the executions in the group.
 /// Then waits for their outcome and eventually stops the sandboxes if a process fails. When all the
 /// sandboxes complete, this manager collects their results and send them back to the server.
@@ -452,12 +532,33 @@ fn sandbox_group_manager(
             handle.join().expect("Sandbox thread failed");
         }
     }
+    finish_job(
+        &current_job,
+        &sender,
+        server_asked_files_receiver,
+        results.into_iter().map(Option::unwrap).collect(),
+        outputs,
+        output_paths,
+    );
+    // The sandbox may chmod -r the directory, revert it to allow deletion on drop
+    if let Some(fifo_dir) = fifo_dir {
+        let _ = std::fs::set_permissions(fifo_dir.path(), Permissions::from_mode(0o755));
+    }
+}
+
+/// Send the results of the job to the server, then send the files it asks for. When done reset the
+/// worker and ask for more work.
+fn finish_job(
+    current_job: &Mutex<WorkerCurrentJob>,
+    sender: &ChannelSender<WorkerClientMessage>,
+    server_asked_files_receiver: Receiver<Vec<FileUuid>>,
+    results: Vec<ExecutionResult>,
+    outputs: HashMap<FileUuid, FileStoreKey>,
+    output_paths: HashMap<FileUuid, PathBuf>,
+) {
     // tell the server the results and the list of produced files
     sender
-        .send(WorkerClientMessage::WorkerDone(
-            results.into_iter().map(Option::unwrap).collect(),
-            outputs.clone(),
-        ))
+        .send(WorkerClientMessage::WorkerDone(results, outputs.clone()))
         .unwrap();
     // wait for the list of files to send
     match server_asked_files_receiver.recv() {
@@ -495,10 +596,6 @@ fn sandbox_group_manager(
     job.current_job = None;
     job.current_sandboxes = None;
     let _ = sender.send(WorkerClientMessage::GetWork);
-    // The sandbox may chmod -r the directory, revert it to allow deletion on drop
-    if let Some(fifo_dir) = fifo_dir {
-        let _ = std::fs::set_permissions(fifo_dir.path(), Permissions::from_mode(0o755));
-    }
 }
 
 /// Spawn the sandbox of an execution in a different thread and send to the group manager the
@@ -543,6 +640,7 @@ fn compute_execution_result(
             was_killed,
             was_cached: false,
             stderr: capture_stream(&sandbox.stderr_path(), execution.capture_stderr)?,
+            samples: vec![],
         }),
         SandboxResult::Failed { error } => Ok(ExecutionResult {
             status: ExecutionStatus::InternalError(error),
@@ -551,6 +649,7 @@ fn compute_execution_result(
             was_killed: false,
             was_cached: false,
             stderr: None,
+            samples: vec![],
         }),
     }
 }
@@ -0,0 +1,232 @@
+use std::path::Path;
+use std::sync::Arc;
+
+use failure::{bail, Error};
+use itertools::Itertools;
+use serde::{Deserialize, Serialize};
+use typescript_definitions::TypeScriptify;
+
+use task_maker_lang::{CompilerSettings, Language, LanguageManager};
+
+/// A rule of the `compilers` field of the task.yaml, setting the compiler settings of the source
+/// files of a language, optionally only the ones matching a glob pattern.
+///
+/// ```yaml
+/// compilers:
+///   - language: cpp
+///     std: c++17
+///   - language: cpp
+///     files: sol/*_debug.cpp
+///     flags: [-DDEBUG]
+/// ```
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, TypeScriptify)]
+pub struct CompilerSettingsRule {
+    /// The language of the source files: either its name (e.g. `C++`) or one of its extensions
+    /// (e.g. `cpp`). Only the languages supporting the compiler settings (C, C++ and Pascal) are
+    /// accepted.
+    pub language: String,
+    /// The glob pattern, relative to the task directory, of the source files this rule applies
+    /// to. If not set the rule applies to all the source files of the language.
+    #[serde(default)]
+    pub files: Option<String>,
+    /// The version of the standard (or the dialect) of the language to use.
+    #[serde(default)]
+    pub std: Option<String>,
+    /// The extra flags to pass to the compiler.
+    #[serde(default)]
+    pub flags: Vec<String>,
+}
+
+/// The compiler settings of the source files of a task. All the rules matching a source file are
+/// applied in order: the standard of the last one is used, while the flags of all of them are
+/// passed to the compiler.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize, TypeScriptify)]
+pub struct TaskCompilerSettings {
+    /// The rules, in the order they are applied.
+    pub rules: Vec<CompilerSettingsRule>,
+}
+
+impl CompilerSettingsRule {
+    /// Whether this rule applies to the specified language.
+    fn matches_language(&self, language: &dyn Language) -> bool {
+        let name = language.name();
+        let short_name = name.split(" / ").next().unwrap_or(name);
+        let rule = self.language.to_lowercase();
+        rule == name.to_lowercase()
+            || rule == short_name.to_lowercase()
+            || language.extensions().iter().any(|ext| *ext == rule)
+    }
+
+    /// The known language named by this rule, either by name or by extension.
+    fn known_language(&self) -> Option<Arc<dyn Language>> {
+        let rule = self.language.to_lowercase();
+        LanguageManager::language_names()
+            .into_iter()
+            .find(|name| {
+                let short_name = name.split(" / ").next().unwrap_or(name);
+                rule == name.to_lowercase() || rule == short_name.to_lowercase()
+            })
+            .and_then(LanguageManager::from_name)
+            .or_else(|| LanguageManager::detect_language(Path::new("source").with_extension(&rule)))
+    }
+
+    /// Whether this rule applies to the source file at the specified path, relative to the task
+    /// directory.
+    fn matches_path(&self, path: &Path) -> bool {
+        match &self.files {
+            Some(files) => glob::Pattern::new(files)
+                .map(|pattern| pattern.matches_path(path))
+                .unwrap_or(false),
+            None => true,
+        }
+    }
+}
+
+impl TaskCompilerSettings {
+    /// Make the settings from the rules, checking that they are valid.
+    pub fn new(rules: Vec<CompilerSettingsRule>) -> Result<TaskCompilerSettings, Error> {
+        for rule in &rules {
+            let language = match rule.known_language() {
+                Some(language) => language,
+                None => bail!(
+                    "Unknown language {:?} in the compiler settings, the known languages are: {}",
+                    rule.language,
+                    LanguageManager::language_names().iter().join(", ")
+                ),
+            };
+            if language
+                .with_compiler_settings(&CompilerSettings::default())
+                .is_none()
+            {
+                bail!(
+                    "The compiler settings are not supported in {}, remove the rule for {:?}",
+                    language.name(),
+                    rule.language
+                );
+            }
+            if let Some(files) = &rule.files {
+                if let Err(e) = glob::Pattern::new(files) {
+                    bail!(
+                        "Invalid pattern {:?} of the compiler settings: {}",
+                        files,
+                        e
+                    );
+                }
+            }
+        }
+        Ok(TaskCompilerSettings { rules })
+    }
+
+    /// The compiler settings for the specified source file of the task, `None` if no rules apply
+    /// to it.
+    pub fn settings_for(
+        &self,
+        task_dir: &Path,
+        path: &Path,
+        language: &dyn Language,
+    ) -> Option<CompilerSettings> {
+        let path = path.strip_prefix(task_dir).unwrap_or(path);
+        let mut settings: Option<CompilerSettings> = None;
+        for rule in &self.rules {
+            if !rule.matches_language(language) || !rule.matches_path(path) {
+                continue;
+            }
+            let settings = settings.get_or_insert_with(CompilerSettings::default);
+            if let Some(std) = &rule.std {
+                settings.std_version = Some(std.clone());
+            }
+            settings.extra_flags.extend(rule.flags.iter().cloned());
+        }
+        settings
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use task_maker_lang::LanguageManager;
+
+    use super::*;
+
+    fn rule(
+        language: &str,
+        files: Option<&str>,
+        std: Option<&str>,
+        flags: &[&str],
+    ) -> CompilerSettingsRule {
+        CompilerSettingsRule {
+            language: language.to_string(),
+            files: files.map(String::from),
+            std: std.map(String::from),
+            flags: flags.iter().map(|s| s.to_string()).collect(),
+        }
+    }
+
+    #[test]
+    fn test_settings_for() {
+        let settings = TaskCompilerSettings::new(vec![
+            rule("cpp", None, Some("c++17"), &["-DTASK"]),
+            rule("C++", Some("sol/*_debug.cpp"), Some("c++11"), &["-DDEBUG"]),
+            rule("c", None, Some("c99"), &[]),
+        ])
+        .unwrap();
+        let task_dir = Path::new("/task");
+        let cpp = LanguageManager::detect_language("sol.cpp").unwrap();
+        let c = LanguageManager::detect_language("sol.c").unwrap();
+        let py = LanguageManager::detect_language("sol.py").unwrap();
+
+        let sol = settings
+            .settings_for(task_dir, Path::new("/task/sol/sol.cpp"), cpp.as_ref())
+            .unwrap();
+        assert_eq!(sol.std_version, Some("c++17".to_string()));
+        assert_eq!(sol.extra_flags, vec!["-DTASK".to_string()]);
+
+        let debug = settings
+            .settings_for(task_dir, Path::new("/task/sol/sol_debug.cpp"), cpp.as_ref())
+            .unwrap();
+        assert_eq!(debug.std_version, Some("c++11".to_string()));
+        assert_eq!(
+            debug.extra_flags,
+            vec!["-DTASK".to_string(), "-DDEBUG".to_string()]
+        );
+
+        let sol_c = settings
+            .settings_for(task_dir, Path::new("/task/sol/sol.c"), c.as_ref())
+            .unwrap();
+        assert_eq!(sol_c.std_version, Some("c99".to_string()));
+        assert!(sol_c.extra_flags.is_empty());
+
+        assert!(settings
+            .settings_for(task_dir, Path::new("/task/sol/sol.py"), py.as_ref())
+            .is_none());
+    }
+
+    #[test]
+    fn test_invalid_pattern() {
+        assert!(
+            TaskCompilerSettings::new(vec![rule("cpp", Some("sol/[*.cpp"), None, &[])]).is_err()
+        );
+    }
+
+    #[test]
+    fn test_unknown_language() {
+        let err = TaskCompilerSettings::new(vec![rule("cobol", None, None, &[])]).unwrap_err();
+        assert!(err.to_string().contains("Unknown language \"cobol\""));
+        assert!(TaskCompilerSettings::new(vec![rule("c++", None, None, &[])]).is_ok());
+        assert!(TaskCompilerSettings::new(vec![rule("PAS", None, None, &[])]).is_ok());
+    }
+
+    #[test]
+    fn test_unsupported_language() {
+        for language in &["rust", "go", "java", "py"] {
+            let err =
+                TaskCompilerSettings::new(vec![rule(language, None, None, &["-O2"])]).unwrap_err();
+            assert!(
+                err.to_string()
+                    .contains("compiler settings are not supported"),
+                "{}: {}",
+                language,
+                err
+            );
+        }
+    }
+}
@@ -168,7 +168,7 @@ fn ui_execution_status_text(status: &UIExecutionStatus, loading: char) -> Text {
 
 /// Draw the content of the generation box.
 fn draw_generations(frame: &mut FrameType, rect: Rect, state: &UIState, loading: char) {
-    let text: Vec<Text> = state
+    let mut text: Vec<Text> = state
         .generations
         .iter()
         .sorted_by_key(|(k, _)| *k)
@@ -185,6 +185,26 @@ fn draw_generations(frame: &mut FrameType, rect: Rect, state: &UIState, loading:
             res
         })
         .collect();
+    // the failed testcases are listed by name, so they can be found without counting them
+    let failed: Vec<String> = state
+        .generations
+        .values()
+        .flat_map(|subtask| subtask.testcases.iter())
+        .filter(|(_, tc)| tc.status == TestcaseGenerationStatus::Failed)
+        .map(|(id, _)| *id)
+        .sorted()
+        .dedup()
+        .map(|id| match state.task.testcase_name(id) {
+            Some(name) => name.to_string(),
+            None => format!("#{}", id),
+        })
+        .collect();
+    if !failed.is_empty() {
+        text.push(Text::styled(
+            format!(" Failed: {}", failed.join(", ")),
+            Style::default().fg(Color::Red).modifier(Modifier::BOLD),
+        ));
+    }
     Paragraph::new(text.iter()).wrap(false).render(frame, rect);
 }
 
@@ -378,5 +398,9 @@ fn testcase_evaluation_status_text(status: &TestcaseEvaluationStatus, loading: c
                 .modifier(Modifier::BOLD),
         ),
         TestcaseEvaluationStatus::Skipped => Text::raw("X"),
+        TestcaseEvaluationStatus::MissingOutput => Text::styled(
+            "-",
+            Style::default().fg(Color::Red).modifier(Modifier::BOLD),
+        ),
     }
 }
@@ -1,11 +1,14 @@
 use std::path::PathBuf;
 use std::sync::Arc;
 
-use failure::{format_err, Error};
+use failure::{bail, format_err, Error};
 use serde::{Deserialize, Serialize};
 use typescript_definitions::TypeScriptify;
 
-use task_maker_dag::{Execution, ExecutionCommand, ExecutionStatus, FileUuid, Priority};
+use task_maker_dag::{
+    BuiltinCommand, Execution, ExecutionCommand, ExecutionResult, ExecutionStatus, FileUuid,
+    Priority,
+};
 
 use crate::bind_exec_callbacks;
 use crate::ioi::{SubtaskId, TestcaseId, EVALUATION_PRIORITY};
@@ -17,7 +20,8 @@ use crate::{EvaluationData, SourceFile, Tag};
 #[derive(Debug, Clone, Serialize, Deserialize, TypeScriptify)]
 pub enum Checker {
     /// Use a built-in white diff checker that scores 1.0 if the two output files are identical
-    /// except for white spaces. It internally uses `diff --ignore-all-spaces`
+    /// except for white spaces and blank lines. The comparison is done directly by the workers,
+    /// without spawning any process.
     WhiteDiff,
     /// Use a custom checker based on an executable that can output a score (from 0.0 to 1.0) to
     /// stdout as well as a custom message on stderr.
@@ -25,9 +29,52 @@ pub enum Checker {
     /// The arguments are the paths of (input, correct_output, test_output). The checker should
     /// output to stdout the score and to stderr a message for the user.
     Custom(Arc<SourceFile>),
+    /// Use a custom checker written with testlib, it communicates the outcome using the exit code
+    /// and writes a message for the user to stderr.
+    ///
+    /// The arguments are the paths of (input, test_output, correct_output). The exit code 0 means
+    /// that the output is correct, 1, 2, 4 and 8 that it is wrong, 3 that the checker failed and 7
+    /// that the score is written to stderr in the form `points <score> <message>`.
+    Testlib(Arc<SourceFile>),
+    /// Use a built-in checker that compares the output files token by token, where the tokens are
+    /// separated by white spaces. The message tells which token differed.
+    Tokens,
+    /// Use a built-in checker that compares the output files token by token ignoring the case of
+    /// the letters.
+    CaseInsensitive,
+    /// Use a built-in checker that compares the output files token by token, the numbers are
+    /// considered equal if their absolute or relative difference is within the epsilons. `nan` and
+    /// the infinities match only themselves.
+    Float {
+        /// The maximum absolute difference between the numbers.
+        absolute_epsilon: f64,
+        /// The maximum difference between the numbers, relative to the correct one.
+        relative_epsilon: f64,
+    },
 }
 
+/// Exit code of a testlib checker when the output is correct.
+const TESTLIB_OK: u32 = 0;
+/// Exit code of a testlib checker when the output is wrong.
+const TESTLIB_WRONG_ANSWER: u32 = 1;
+/// Exit code of a testlib checker when the output is not well formatted.
+const TESTLIB_PRESENTATION_ERROR: u32 = 2;
+/// Exit code of a testlib checker when the checker itself failed.
+const TESTLIB_FAIL: u32 = 3;
+/// Exit code of a testlib checker when the output has extra content at the end.
+const TESTLIB_DIRT: u32 = 4;
+/// Exit code of a testlib checker when the score is written to stderr.
+const TESTLIB_POINTS: u32 = 7;
+/// Exit code of a testlib checker when the output ended unexpectedly.
+const TESTLIB_UNEXPECTED_EOF: u32 = 8;
+
 impl Checker {
+    /// The maximum absolute difference of the float checker when the task doesn't set one, enough
+    /// for the numbers printed with 6 decimal digits.
+    pub const DEFAULT_FLOAT_ABSOLUTE_EPSILON: f64 = 1e-6;
+    /// The maximum relative difference of the float checker when the task doesn't set one.
+    pub const DEFAULT_FLOAT_RELATIVE_EPSILON: f64 = 1e-6;
+
     /// Build the execution of the checker for the specified files, the callback will be called when
     /// the result is ready. The execution does not send UI messages yet and it's not added to the
     /// DAG.
@@ -46,33 +93,6 @@ impl Checker {
         F: FnOnce(f64, String) -> Result<(), Error> + Send + Sync + 'static,
     {
         match self {
-            Checker::WhiteDiff => {
-                let mut exec = Execution::new(description, ExecutionCommand::system("diff"));
-                exec.args(vec![
-                    "--ignore-blank-lines",
-                    "--ignore-space-change",
-                    "correct",
-                    "test",
-                ])
-                .input(correct_output, "correct", false)
-                .input(test_output, "test", false)
-                .tag(Tag::Checking.into())
-                .priority(EVALUATION_PRIORITY - testcase_id as Priority);
-
-                eval.dag.on_execution_done(&exec.uuid, move |result| {
-                    match result.status {
-                        // diff exits with 0 if the files are equal
-                        ExecutionStatus::Success => callback(1.0, "Output is correct".into())?,
-                        // return code 1 means the files are different
-                        ExecutionStatus::ReturnCode(1) => {
-                            callback(0.0, "Output is incorrect".into())?
-                        }
-                        _ => unreachable!("diff died badly? {:?}", result),
-                    };
-                    Ok(())
-                });
-                Ok(exec)
-            }
             Checker::Custom(source_file) => {
                 let mut exec = source_file.execute(
                     eval,
@@ -87,22 +107,126 @@ impl Checker {
                     .capture_stderr(1024)
                     .priority(EVALUATION_PRIORITY - testcase_id as Priority);
                 eval.dag.on_execution_done(&exec.uuid, move |res| {
-                    let stdout = res
-                        .stdout
-                        .ok_or_else(|| format_err!("Checker stdout not captured"))?;
+                    let (score, message) = Checker::parse_custom_outcome(res)?;
+                    callback(score, message)
+                });
+                Ok(exec)
+            }
+            Checker::Testlib(source_file) => {
+                let mut exec = source_file.execute(
+                    eval,
+                    description,
+                    vec!["input", "test_output", "correct_output"],
+                )?;
+                exec.input(input, "input", false)
+                    .input(correct_output, "correct_output", false)
+                    .input(test_output, "test_output", false)
+                    .tag(Tag::Checking.into())
+                    .capture_stderr(1024)
+                    .priority(EVALUATION_PRIORITY - testcase_id as Priority);
+                eval.dag.on_execution_done(&exec.uuid, move |res| {
                     let stderr = res
                         .stderr
                         .ok_or_else(|| format_err!("Checker stderr not captured"))?;
-                    let score = String::from_utf8_lossy(&stdout);
-                    let score: f64 = score
-                        .trim()
-                        .parse()
-                        .map_err(|e| format_err!("Invalid score from checker: {:?}", e))?;
                     let message = String::from_utf8_lossy(&stderr).trim().to_string();
+                    let (score, message) = Checker::parse_testlib_outcome(&res.status, message)?;
                     callback(score, message)
                 });
                 Ok(exec)
             }
+            Checker::WhiteDiff
+            | Checker::Tokens
+            | Checker::CaseInsensitive
+            | Checker::Float { .. } => {
+                let command = match self {
+                    Checker::WhiteDiff => BuiltinCommand::CompareWhiteDiff,
+                    Checker::Tokens => BuiltinCommand::CompareTokens,
+                    Checker::CaseInsensitive => BuiltinCommand::CompareTokensCaseInsensitive,
+                    Checker::Float {
+                        absolute_epsilon,
+                        relative_epsilon,
+                    } => BuiltinCommand::CompareFloats {
+                        absolute_epsilon: *absolute_epsilon,
+                        relative_epsilon: *relative_epsilon,
+                    },
+                    _ => unreachable!("Not a builtin checker"),
+                };
+                let mut exec = Execution::new(description, ExecutionCommand::builtin(command));
+                exec.args(vec!["correct", "test"])
+                    .input(correct_output, "correct", false)
+                    .input(test_output, "test", false)
+                    .tag(Tag::Checking.into())
+                    .capture_stdout(128)
+                    .capture_stderr(1024)
+                    .priority(EVALUATION_PRIORITY - testcase_id as Priority);
+                // the builtin checkers speak the same protocol of the custom ones
+                eval.dag.on_execution_done(&exec.uuid, move |res| {
+                    let (score, message) = Checker::parse_custom_outcome(res)?;
+                    callback(score, message)
+                });
+                Ok(exec)
+            }
+        }
+    }
+
+    /// Compute the score and the message of a custom checker given its result: the score is
+    /// printed to stdout and the message to stderr.
+    fn parse_custom_outcome(result: ExecutionResult) -> Result<(f64, String), Error> {
+        let stdout = result
+            .stdout
+            .ok_or_else(|| format_err!("Checker stdout not captured"))?;
+        let stderr = result
+            .stderr
+            .ok_or_else(|| format_err!("Checker stderr not captured"))?;
+        let score = String::from_utf8_lossy(&stdout);
+        let score: f64 = score
+            .trim()
+            .parse()
+            .map_err(|e| format_err!("Invalid score from checker: {:?}", e))?;
+        let message = String::from_utf8_lossy(&stderr).trim().to_string();
+        Ok((score, message))
+    }
+
+    /// Compute the score and the message of a testlib checker given its exit status and its
+    /// stderr. Testlib interactors use the same protocol.
+    pub(crate) fn parse_testlib_outcome(
+        status: &ExecutionStatus,
+        message: String,
+    ) -> Result<(f64, String), Error> {
+        let exit_code = match status {
+            ExecutionStatus::Success => TESTLIB_OK,
+            ExecutionStatus::ReturnCode(code) => *code,
+            _ => bail!("Checker failed: {:?}", status),
+        };
+        match exit_code {
+            TESTLIB_OK => Ok((1.0, message)),
+            TESTLIB_WRONG_ANSWER
+            | TESTLIB_PRESENTATION_ERROR
+            | TESTLIB_DIRT
+            | TESTLIB_UNEXPECTED_EOF => Ok((0.0, message)),
+            TESTLIB_FAIL => bail!("Checker failed: {}", message),
+            TESTLIB_POINTS => {
+                let mut parts = message.splitn(3, char::is_whitespace);
+                if parts.next() != Some("points") {
+                    bail!("Invalid points from checker: {}", message);
+                }
+                let score: f64 = parts
+                    .next()
+                    .unwrap_or("")
+                    .parse()
+                    .map_err(|e| format_err!("Invalid score from checker: {:?}", e))?;
+                // this also rejects NaN
+                if !(0.0..=1.0).contains(&score) {
+                    bail!("Invalid score from checker: {} is not in [0, 1]", score);
+                }
+                let message = parts.next().unwrap_or("").trim().to_string();
+                Ok((score, message))
+            }
+            _ => bail!(
+                "Checker exited with unknown code {}: {}",
+                exit_code,
+                message
+            ),
         }
     }
 
@@ -57,12 +57,13 @@ impl InputGenerator {
     }
 
     /// Add the generation of the input file to the DAG and the callbacks to the UI, returning the
-    /// handle to the input file.
+    /// handle to the input file. The file is written to `input/<file_name>` in the task directory.
     pub(crate) fn generate_and_bind(
         &self,
         eval: &mut EvaluationData,
         subtask_id: SubtaskId,
         testcase_id: TestcaseId,
+        file_name: &str,
     ) -> Result<FileUuid, Error> {
         let (input, gen) = self.generate(
             eval,
@@ -73,13 +74,8 @@ impl InputGenerator {
             subtask_id,
             testcase_id,
         )?;
-        eval.dag.write_file_to(
-            input,
-            eval.task_root
-                .join("input")
-                .join(format!("input{}.txt", testcase_id)),
-            false,
-        );
+        eval.dag
+            .write_file_to(input, eval.task_root.join("input").join(file_name), false);
         // if there is an execution, bind its callbacks and store the input file
         if let Some(mut gen) = gen {
             gen.capture_stderr(STDERR_CONTENT_LENGTH);
@@ -1,3 +1,5 @@
+use std::collections::HashMap;
+
 use serde::{Deserialize, Serialize};
 use typescript_definitions::TypeScriptify;
 
@@ -6,7 +8,12 @@ pub use input_generator::InputGenerator;
 pub use input_validator::{InputValidator, TM_VALIDATION_FILE_NAME};
 pub use output_generator::OutputGenerator;
 use task_maker_dag::Priority;
-pub use task_type::{BatchTypeData, CommunicationTypeData, TaskType};
+pub use task_type::{
+    BatchTypeData, CommunicationTypeData, ManagerType, OutputOnlySubmission, OutputOnlyTypeData,
+    TaskType, TwoStepsTypeData, UserIo,
+};
+
+use crate::ioi::SubtaskId;
 
 mod checker;
 mod input_generator;
@@ -32,7 +39,22 @@ pub enum TestcaseScoreAggregator {
     /// Sum the score of all the testcases, formally:
     ///
     /// `st_score = st_max_score * sum(*testcase_scores) / len(*testcase_scores)`
+    ///
+    /// When the testcases have a weight, the weighted average is used instead:
+    ///
+    /// `st_score = st_max_score * sum(w * score for w, score) / sum(*testcase_weights)`
     Sum,
+    /// Multiply the score of all the testcases, formally:
+    ///
+    /// `st_score = st_max_score * prod(*testcase_scores)`
+    Mul,
+    /// Give all the points of the subtask only if the score of all the testcases is positive and
+    /// not greater than the threshold of the subtask, formally:
+    ///
+    /// `st_score = st_max_score * all(0 < score <= st_threshold for score in testcase_scores)`
+    ///
+    /// The map contains the threshold of each subtask.
+    Threshold(HashMap<SubtaskId, f64>),
 }
 
 /// Bind the input/output of an execution to the input and output file of a testcase. It correctly
@@ -64,41 +86,83 @@ macro_rules! bind_exec_io {
 }
 
 impl TestcaseScoreAggregator {
-    /// Aggregate the scores of a subtask from an iterator with the scores of the testcases.
-    pub(crate) fn aggregate<I: IntoIterator<Item = f64>>(&self, iter: I) -> f64 {
+    /// Aggregate the scores of a subtask from an iterator with the scores of the testcases and
+    /// their weights. Only the `Sum` aggregator takes the weights into account.
+    pub(crate) fn aggregate<I: IntoIterator<Item = (f64, f64)>>(
+        &self,
+        subtask_id: SubtaskId,
+        iter: I,
+    ) -> f64 {
+        let mut iter = iter.into_iter();
         match self {
             TestcaseScoreAggregator::Min => iter
-                .into_iter()
+                .map(|(score, _)| score)
                 .min_by(|a, b| a.partial_cmp(b).unwrap())
                 .unwrap_or(1.0),
             TestcaseScoreAggregator::Sum => {
-                let sum_count = iter
-                    .into_iter()
-                    .fold((0.0, 0), |prev, cur| (prev.0 + cur, prev.1 + 1));
-                if sum_count.1 == 0 {
+                let (sum, total_weight) = iter.fold((0.0, 0.0), |prev, (score, weight)| {
+                    (prev.0 + score * weight, prev.1 + weight)
+                });
+                if total_weight == 0.0 {
                     return 1.0;
                 }
-                sum_count.0 / (f64::from(sum_count.1))
+                sum / total_weight
             }
+            TestcaseScoreAggregator::Mul => iter.map(|(score, _)| score).product(),
+            TestcaseScoreAggregator::Threshold(thresholds) => {
+                let threshold = thresholds.get(&subtask_id).cloned().unwrap_or(1.0);
+                if iter.all(|(score, _)| score > 0.0 && score <= threshold) {
+                    1.0
+                } else {
+                    0.0
+                }
+            }
+        }
+    }
+
+    /// The name of the score type in CMS that uses this aggregator.
+    pub fn cms_name(&self) -> &'static str {
+        match self {
+            TestcaseScoreAggregator::Min => "GroupMin",
+            TestcaseScoreAggregator::Sum => "Sum",
+            TestcaseScoreAggregator::Mul => "GroupMul",
+            TestcaseScoreAggregator::Threshold(_) => "GroupThreshold",
+        }
+    }
+
+    /// The parameter of the aggregator relative to the specified subtask, if any.
+    pub fn subtask_parameter(&self, subtask_id: SubtaskId) -> Option<f64> {
+        match self {
+            TestcaseScoreAggregator::Threshold(thresholds) => thresholds.get(&subtask_id).cloned(),
+            _ => None,
         }
     }
 }
 
 #[cfg(test)]
-mod tests {
-    use std::path::PathBuf;
+pub(crate) mod tests {
+    use std::collections::HashMap;
+    use std::io::Write;
+    use std::path::{Path, PathBuf};
     use std::sync::atomic::{AtomicBool, Ordering};
-    use std::sync::Arc;
+    use std::sync::{Arc, Mutex};
+
+    use failure::Error;
 
-    use task_maker_dag::{ExecutionResourcesUsage, ExecutionResult, ExecutionStatus, File};
+    use task_maker_dag::{
+        BuiltinCommand, ExecutionCommand, ExecutionResourcesUsage, ExecutionResult,
+        ExecutionStatus, File, ProvidedFile,
+    };
     use task_maker_lang::GraderMap;
 
-    use crate::ioi::IOITask;
+    use crate::ioi::{IOITask, LimitsMultipliers, ScoreManager, SubtaskInfo, TestcaseInfo};
+    use crate::ui::UIMessage;
     use crate::{EvaluationData, SourceFile, Tag};
 
     use super::*;
 
-    fn make_task<P: Into<PathBuf>>(path: P) -> IOITask {
+    /// An empty batch task, shared by the tests of the IOI format.
+    pub(crate) fn make_task<P: Into<PathBuf>>(path: P) -> IOITask {
         IOITask {
             path: path.into(),
             task_type: TaskType::Batch(BatchTypeData {
@@ -109,6 +173,7 @@ mod tests {
             title: "".to_string(),
             time_limit: None,
             memory_limit: None,
+            limits_multipliers: HashMap::new(),
             infile: None,
             outfile: None,
             subtasks: Default::default(),
@@ -119,37 +184,115 @@ mod tests {
             difficulty: None,
             syllabus_level: None,
             sanity_checks: Default::default(),
+            compiler_settings: Default::default(),
         }
     }
 
+    /// A batch task with two subtasks of 50 points, each with two testcases.
+    pub(crate) fn make_task_with_subtasks<P: Into<PathBuf>>(path: P) -> IOITask {
+        let mut task = make_task(path);
+        for st in 0..2 {
+            let testcases: HashMap<_, _> = (2 * st..2 * st + 2)
+                .map(|tc| {
+                    (
+                        tc,
+                        TestcaseInfo {
+                            id: tc,
+                            name: None,
+                            weight: 1.0,
+                            input_generator: InputGenerator::StaticFile("/input".into()),
+                            input_validator: InputValidator::AssumeValid,
+                            output_generator: OutputGenerator::StaticFile("/output".into()),
+                        },
+                    )
+                })
+                .collect();
+            task.subtasks.insert(
+                st,
+                SubtaskInfo {
+                    id: st,
+                    description: None,
+                    max_score: 50.0,
+                    time_limit: None,
+                    memory_limit: None,
+                    testcases,
+                },
+            );
+        }
+        task
+    }
+
     #[test]
     fn test_aggregate_min() {
         let aggregator = TestcaseScoreAggregator::Min;
-        let min = aggregator.aggregate(vec![1.0, 0.1, 0.5]);
+        let min = aggregator.aggregate(0, vec![(1.0, 1.0), (0.1, 1.0), (0.5, 1.0)]);
         assert_abs_diff_eq!(0.1, min);
     }
 
     #[test]
     fn test_aggregate_min_empty() {
         let aggregator = TestcaseScoreAggregator::Min;
-        let min = aggregator.aggregate(vec![]);
+        let min = aggregator.aggregate(0, vec![]);
         assert_abs_diff_eq!(1.0, min);
     }
 
     #[test]
     fn test_aggregate_sum() {
         let aggregator = TestcaseScoreAggregator::Sum;
-        let sum = aggregator.aggregate(vec![1.0, 0.1, 0.7]);
+        let sum = aggregator.aggregate(0, vec![(1.0, 1.0), (0.1, 1.0), (0.7, 1.0)]);
         assert_abs_diff_eq!(0.6, sum);
     }
 
     #[test]
     fn test_aggregate_sum_empty() {
         let aggregator = TestcaseScoreAggregator::Sum;
-        let sum = aggregator.aggregate(vec![]);
+        let sum = aggregator.aggregate(0, vec![]);
         assert_abs_diff_eq!(1.0, sum);
     }
 
+    #[test]
+    fn test_aggregate_sum_weighted() {
+        let aggregator = TestcaseScoreAggregator::Sum;
+        let sum = aggregator.aggregate(0, vec![(1.0, 3.0), (0.0, 1.0)]);
+        assert_abs_diff_eq!(0.75, sum);
+        let sum = aggregator.aggregate(0, vec![(1.0, 0.5), (0.5, 0.5), (0.0, 1.0)]);
+        assert_abs_diff_eq!(0.375, sum);
+    }
+
+    #[test]
+    fn test_aggregate_min_weighted() {
+        let aggregator = TestcaseScoreAggregator::Min;
+        let min = aggregator.aggregate(0, vec![(1.0, 3.0), (0.5, 1.0)]);
+        assert_abs_diff_eq!(0.5, min);
+    }
+
+    #[test]
+    fn test_aggregate_mul() {
+        let aggregator = TestcaseScoreAggregator::Mul;
+        let mul = aggregator.aggregate(0, vec![(1.0, 1.0), (0.5, 1.0), (0.5, 1.0)]);
+        assert_abs_diff_eq!(0.25, mul);
+    }
+
+    #[test]
+    fn test_aggregate_mul_empty() {
+        let aggregator = TestcaseScoreAggregator::Mul;
+        let mul = aggregator.aggregate(0, vec![]);
+        assert_abs_diff_eq!(1.0, mul);
+    }
+
+    #[test]
+    fn test_aggregate_threshold() {
+        let mut thresholds = HashMap::new();
+        thresholds.insert(0, 0.5);
+        thresholds.insert(1, 2.0);
+        let aggregator = TestcaseScoreAggregator::Threshold(thresholds);
+        assert_abs_diff_eq!(1.0, aggregator.aggregate(0, vec![(0.1, 1.0), (0.5, 1.0)]));
+        assert_abs_diff_eq!(0.0, aggregator.aggregate(0, vec![(0.1, 1.0), (0.6, 1.0)]));
+        assert_abs_diff_eq!(0.0, aggregator.aggregate(0, vec![(0.1, 1.0), (0.0, 1.0)]));
+        assert_abs_diff_eq!(1.0, aggregator.aggregate(1, vec![(1.5, 1.0), (2.0, 1.0)]));
+        assert_abs_diff_eq!(1.0, aggregator.aggregate(1, vec![]));
+    }
+
     #[test]
     fn test_input_generator_static() {
         let tmpdir = tempdir::TempDir::new("tm-test").unwrap();
@@ -157,7 +300,9 @@ mod tests {
         std::fs::write(&path, "x").unwrap();
         let generator = InputGenerator::StaticFile(path);
         let (mut eval, _) = EvaluationData::new(tmpdir.path());
-        let out = generator.generate_and_bind(&mut eval, 0, 0).unwrap();
+        let out = generator
+            .generate_and_bind(&mut eval, 0, 0, "input0.txt")
+            .unwrap();
         assert!(eval.dag.data.provided_files.contains_key(&out));
         assert!(eval
             .dag
@@ -174,7 +319,7 @@ mod tests {
         let path = tmpdir.path().join("input.txt");
         let generator = InputGenerator::StaticFile(path.clone());
         let (mut eval, _) = EvaluationData::new(tmpdir.path());
-        let gen = generator.generate_and_bind(&mut eval, 0, 0);
+        let gen = generator.generate_and_bind(&mut eval, 0, 0, "input0.txt");
         assert!(gen.is_err());
         let err = gen.unwrap_err().to_string();
         assert!(err.contains("COPY"));
@@ -189,7 +334,9 @@ mod tests {
         let source = SourceFile::new(&path, "", None, None::<PathBuf>).unwrap();
         let generator = InputGenerator::Custom(Arc::new(source), vec![]);
         let (mut eval, _recv) = EvaluationData::new(tmpdir.path());
-        let out = generator.generate_and_bind(&mut eval, 0, 0).unwrap();
+        let out = generator
+            .generate_and_bind(&mut eval, 0, 0, "input0.txt")
+            .unwrap();
         assert_eq!(eval.dag.data.provided_files.len(), 1);
         assert_eq!(eval.dag.data.execution_groups.len(), 1);
         let group = eval.dag.data.execution_groups.values().next().unwrap();
@@ -251,7 +398,7 @@ mod tests {
         let task = make_task(tmpdir.path());
         let (mut eval, _) = EvaluationData::new(tmpdir.path());
         let out = generator
-            .generate_and_bind(&task, &mut eval, 0, 0, file.uuid, None)
+            .generate_and_bind(&task, &mut eval, 0, 0, "output0.txt", file.uuid, None)
             .unwrap()
             .unwrap();
         assert!(eval.dag.data.provided_files.contains_key(&out));
@@ -272,7 +419,8 @@ mod tests {
         let file = File::new("input");
         let task = make_task(tmpdir.path());
         let (mut eval, _) = EvaluationData::new(tmpdir.path());
-        let gen = generator.generate_and_bind(&task, &mut eval, 0, 0, file.uuid, None);
+        let gen =
+            generator.generate_and_bind(&task, &mut eval, 0, 0, "output0.txt", file.uuid, None);
         assert!(gen.is_err());
         let err = gen.unwrap_err().to_string();
         assert!(err.contains("Static output file not found"));
@@ -291,7 +439,15 @@ mod tests {
         let task = make_task(tmpdir.path());
         let (mut eval, _recv) = EvaluationData::new(tmpdir.path());
         let out = generator
-            .generate_and_bind(&task, &mut eval, 0, 0, file.uuid, Some(val.uuid))
+            .generate_and_bind(
+                &task,
+                &mut eval,
+                0,
+                0,
+                "output0.txt",
+                file.uuid,
+                Some(val.uuid),
+            )
             .unwrap()
             .unwrap();
         assert_eq!(eval.dag.data.provided_files.len(), 1);
@@ -310,6 +466,41 @@ mod tests {
             .is_some());
     }
 
+    #[test]
+    fn test_output_generator_two_steps() {
+        let tmpdir = tempdir::TempDir::new("tm-test").unwrap();
+        let path = tmpdir.path().join("sol.py");
+        std::fs::write(&path, "x").unwrap();
+        let source = SourceFile::new(&path, "", None, None::<PathBuf>).unwrap();
+        let generator = OutputGenerator::TwoSteps(Arc::new(source));
+        let file = File::new("input");
+        let task = make_task(tmpdir.path());
+        let (mut eval, _recv) = EvaluationData::new(tmpdir.path());
+        let out = generator
+            .generate_and_bind(&task, &mut eval, 0, 0, "output0.txt", file.uuid, None)
+            .unwrap()
+            .unwrap();
+        assert_eq!(eval.dag.data.execution_groups.len(), 1);
+        let group = eval.dag.data.execution_groups.values().next().unwrap();
+        assert_eq!(group.tag().as_ref().unwrap(), &Tag::Generation.into());
+        assert_eq!(group.executions.len(), 2);
+        assert_eq!(group.fifo.len(), 1);
+        let first = group
+            .executions
+            .iter()
+            .find(|e| e.args == vec!["0"])
+            .unwrap();
+        let second = group
+            .executions
+            .iter()
+            .find(|e| e.args == vec!["1"])
+            .unwrap();
+        assert_eq!(first.stdin, Some(file.uuid));
+        assert_eq!(first.stdout_fifo.unwrap().uuid, group.fifo[0].uuid);
+        assert_eq!(second.stdin_fifo.unwrap().uuid, group.fifo[0].uuid);
+        assert_eq!(second.stdout.as_ref().unwrap().uuid, out);
+    }
+
     #[test]
     fn test_checker_whitediff() {
         let checker = Checker::WhiteDiff;
@@ -326,12 +517,10 @@ mod tests {
         assert_eq!(eval.dag.data.execution_groups.len(), 1);
         let group = eval.dag.data.execution_groups.values().next().unwrap();
         assert_eq!(group.tag().as_ref().unwrap(), &Tag::Checking.into());
-        assert!(group.executions[0]
-            .args
-            .contains(&"--ignore-blank-lines".into()));
-        assert!(group.executions[0]
-            .args
-            .contains(&"--ignore-space-change".into()));
+        assert_eq!(
+            group.executions[0].command,
+            ExecutionCommand::Builtin(BuiltinCommand::CompareWhiteDiff)
+        );
         assert!(group.executions[0].dependencies().contains(&output));
         assert!(group.executions[0].dependencies().contains(&test));
     }
@@ -366,8 +555,9 @@ mod tests {
                     wall_time: 0.0,
                     memory: 0,
                 },
-                stdout: None,
-                stderr: None,
+                stdout: Some("1\n".into()),
+                stderr: Some("Output is correct\n".into()),
+                samples: vec![],
             })
             .unwrap();
         });
@@ -395,7 +585,7 @@ mod tests {
         let callbacks = eval.dag.execution_callbacks.into_iter().next().unwrap().1;
         callbacks.on_done.into_iter().for_each(|cb| {
             cb.call(ExecutionResult {
-                status: ExecutionStatus::ReturnCode(1),
+                status: ExecutionStatus::Success,
                 was_killed: false,
                 was_cached: false,
                 resources: ExecutionResourcesUsage {
@@ -404,8 +594,9 @@ mod tests {
                     wall_time: 0.0,
                     memory: 0,
                 },
-                stdout: None,
-                stderr: None,
+                stdout: Some("0\n".into()),
+                stderr: Some("Output is incorrect\n".into()),
+                samples: vec![],
             })
             .unwrap();
         });
@@ -472,6 +663,7 @@ mod tests {
                 resources: Default::default(),
                 stdout: Some("1.0\n\n".into()),
                 stderr: Some("Ok!\n\n".into()),
+                samples: vec![],
             })
             .unwrap();
 
@@ -513,6 +705,7 @@ mod tests {
                 resources: Default::default(),
                 stdout: Some("0.0\n\n".into()),
                 stderr: Some("Ko!\n\n".into()),
+                samples: vec![],
             })
             .unwrap();
 
@@ -547,10 +740,647 @@ mod tests {
                 resources: Default::default(),
                 stdout: Some(":<\n\n".into()),
                 stderr: Some("Ko!\n\n".into()),
+                samples: vec![],
             })
             .unwrap_err()
             .to_string();
 
         assert!(err.contains("Invalid score from checker"));
     }
+
+    fn call_testlib_checker<F>(status: ExecutionStatus, stderr: &str, cb: F) -> Result<(), Error>
+    where
+        F: FnOnce(f64, String) -> Result<(), Error> + Send + Sync + 'static,
+    {
+        let tmpdir = tempdir::TempDir::new("tm-test").unwrap();
+        let path = tmpdir.path().join("check.py");
+        std::fs::write(&path, "x").unwrap();
+        let source = SourceFile::new(&path, "", None, None::<PathBuf>).unwrap();
+        let checker = Checker::Testlib(Arc::new(source));
+        let (mut eval, _recv) = EvaluationData::new(tmpdir.path());
+        let input = File::new("input").uuid;
+        let output = File::new("output").uuid;
+        let test = File::new("test").uuid;
+        checker
+            .check_and_bind(&mut eval, 0, 0, "sol", input, output, test, cb)
+            .unwrap();
+        let group = eval.dag.data.execution_groups.values().next().unwrap();
+        let exec = &group.executions[0];
+        let args = ["input", "test_output", "correct_output"];
+        assert!(exec
+            .args
+            .ends_with(&args.iter().map(|a| a.to_string()).collect::<Vec<_>>()));
+        let on_done = eval.dag.execution_callbacks.get_mut(&exec.uuid).unwrap();
+        on_done.on_done.remove(0).call(ExecutionResult {
+            status,
+            was_killed: false,
+            was_cached: false,
+            resources: Default::default(),
+            stdout: None,
+            stderr: Some(stderr.into()),
+            samples: vec![],
+        })
+    }
+
+    #[test]
+    fn test_checker_testlib_correct() {
+        let cb_called = Arc::new(AtomicBool::new(false));
+        let cb_called2 = cb_called.clone();
+        let cb = move |score, mex| {
+            assert_abs_diff_eq!(score, 1.0);
+            assert_eq!(mex, "ok 3 numbers");
+            cb_called2.store(true, Ordering::Relaxed);
+            Ok(())
+        };
+        call_testlib_checker(ExecutionStatus::Success, "ok 3 numbers\n", cb).unwrap();
+        assert!(cb_called.load(Ordering::Relaxed));
+    }
+
+    #[test]
+    fn test_checker_testlib_incorrect() {
+        let cb_called = Arc::new(AtomicBool::new(false));
+        let cb_called2 = cb_called.clone();
+        let cb = move |score, mex| {
+            assert_abs_diff_eq!(score, 0.0);
+            assert_eq!(mex, "wrong answer expected 3, found 4");
+            cb_called2.store(true, Ordering::Relaxed);
+            Ok(())
+        };
+        let stderr = "wrong answer expected 3, found 4\n";
+        call_testlib_checker(ExecutionStatus::ReturnCode(1), stderr, cb).unwrap();
+        assert!(cb_called.load(Ordering::Relaxed));
+    }
+
+    #[test]
+    fn test_checker_testlib_points() {
+        let cb_called = Arc::new(AtomicBool::new(false));
+        let cb_called2 = cb_called.clone();
+        let cb = move |score, mex| {
+            assert_abs_diff_eq!(score, 0.25);
+            assert_eq!(mex, "almost there");
+            cb_called2.store(true, Ordering::Relaxed);
+            Ok(())
+        };
+        let stderr = "points 0.25 almost there\n";
+        call_testlib_checker(ExecutionStatus::ReturnCode(7), stderr, cb).unwrap();
+        assert!(cb_called.load(Ordering::Relaxed));
+    }
+
+    #[test]
+    fn test_checker_testlib_points_out_of_range() {
+        for points in &["1.5", "-0.1", "NaN"] {
+            let cb = move |_, _| panic!("the callback should not be called here");
+            let stderr = format!("points {} too many points\n", points);
+            let err = call_testlib_checker(ExecutionStatus::ReturnCode(7), &stderr, cb)
+                .unwrap_err()
+                .to_string();
+            assert!(err.contains("Invalid score from checker"));
+        }
+    }
+
+    #[test]
+    fn test_checker_testlib_fail() {
+        let cb = move |_, _| panic!("the callback should not be called here");
+        let err = call_testlib_checker(ExecutionStatus::ReturnCode(3), "FAIL bad answer", cb)
+            .unwrap_err()
+            .to_string();
+        assert!(err.contains("Checker failed"));
+        assert!(err.contains("FAIL bad answer"));
+    }
+
+    #[test]
+    fn test_checker_float() {
+        let checker = Checker::Float {
+            absolute_epsilon: 1e-6,
+            relative_epsilon: 1e-9,
+        };
+        let (mut eval, _recv) = EvaluationData::new("");
+        let input = File::new("input").uuid;
+        let output = File::new("output").uuid;
+        let test = File::new("test").uuid;
+        checker
+            .check_and_bind(&mut eval, 0, 0, "sol", input, output, test, |_, _| {
+                panic!("the callback should not be called here")
+            })
+            .unwrap();
+        let group = eval.dag.data.execution_groups.values().next().unwrap();
+        let exec = &group.executions[0];
+        assert_eq!(group.tag().as_ref().unwrap(), &Tag::Checking.into());
+        assert_eq!(
+            exec.command,
+            ExecutionCommand::Builtin(BuiltinCommand::CompareFloats {
+                absolute_epsilon: 1e-6,
+                relative_epsilon: 1e-9,
+            })
+        );
+        assert_eq!(exec.args, vec!["correct".to_string(), "test".to_string()]);
+        assert!(exec.dependencies().contains(&output));
+        assert!(exec.dependencies().contains(&test));
+        assert!(!exec.dependencies().contains(&input));
+    }
+
+    #[test]
+    fn test_checker_tokens_incorrect() {
+        let checker = Checker::Tokens;
+        let (mut eval, _recv) = EvaluationData::new("");
+        let input = File::new("input").uuid;
+        let output = File::new("output").uuid;
+        let test = File::new("test").uuid;
+        let cb_called = Arc::new(AtomicBool::new(false));
+        let cb_called2 = cb_called.clone();
+        let cb = move |score, mex| {
+            assert_abs_diff_eq!(score, 0.0);
+            assert_eq!(
+                mex,
+                "Output is incorrect: token 2 differs, expected '2', found '3'"
+            );
+            cb_called2.store(true, Ordering::Relaxed);
+            Ok(())
+        };
+        checker
+            .check_and_bind(&mut eval, 0, 0, "sol", input, output, test, cb)
+            .unwrap();
+        let callbacks = eval.dag.execution_callbacks.into_iter().next().unwrap().1;
+        callbacks.on_done.into_iter().for_each(|cb| {
+            cb.call(ExecutionResult {
+                status: ExecutionStatus::Success,
+                was_killed: false,
+                was_cached: false,
+                resources: Default::default(),
+                stdout: Some("0\n".into()),
+                stderr: Some(
+                    "Output is incorrect: token 2 differs, expected '2', found '3'\n".into(),
+                ),
+                samples: vec![],
+            })
+            .unwrap();
+        });
+        assert!(cb_called.load(Ordering::Relaxed));
+    }
+
+    fn make_output_only_task<P: Into<PathBuf>>(path: P) -> IOITask {
+        let mut task = make_task(path);
+        task.task_type = TaskType::OutputOnly(OutputOnlyTypeData {
+            checker: Checker::WhiteDiff,
+        });
+        let mut testcases = HashMap::new();
+        testcases.insert(
+            0,
+            TestcaseInfo {
+                id: 0,
+                name: None,
+                weight: 1.0,
+                input_generator: InputGenerator::StaticFile("input".into()),
+                input_validator: InputValidator::AssumeValid,
+                output_generator: OutputGenerator::StaticFile("output".into()),
+            },
+        );
+        task.subtasks.insert(
+            0,
+            SubtaskInfo {
+                id: 0,
+                description: None,
+                max_score: 100.0,
+                time_limit: None,
+                memory_limit: None,
+                testcases,
+            },
+        );
+        task
+    }
+
+    #[test]
+    fn test_output_only_directory() {
+        let tmpdir = tempdir::TempDir::new("tm-test").unwrap();
+        let sub_dir = tmpdir.path().join("sol").join("sub");
+        std::fs::create_dir_all(&sub_dir).unwrap();
+        std::fs::write(sub_dir.join("output_0.txt"), "x").unwrap();
+        let task = make_output_only_task(tmpdir.path());
+        let (mut eval, _recv) = EvaluationData::new(tmpdir.path());
+        let submissions = OutputOnlySubmission::find(tmpdir.path(), &Default::default());
+        assert_eq!(submissions.len(), 1);
+        assert_eq!(submissions[0].path, sub_dir);
+        let input = File::new("input").uuid;
+        let output = File::new("output").uuid;
+        let score_manager = Arc::new(Mutex::new(ScoreManager::new(&task)));
+        task.task_type
+            .evaluate_submission(
+                &mut eval,
+                0,
+                0,
+                &submissions[0],
+                input,
+                Some(output),
+                score_manager,
+            )
+            .unwrap();
+        // the output file is not read while building the DAG
+        assert_eq!(eval.dag.data.provided_files.len(), 1);
+        let provided = eval.dag.data.provided_files.values().next().unwrap();
+        match provided {
+            ProvidedFile::LocalFile { local_path, .. } => {
+                assert_eq!(local_path, &sub_dir.join("output_0.txt"))
+            }
+            _ => panic!("Expecting the path of the output file"),
+        }
+        assert_eq!(eval.dag.data.execution_groups.len(), 1);
+        let group = eval.dag.data.execution_groups.values().next().unwrap();
+        assert_eq!(group.tag().as_ref().unwrap(), &Tag::Checking.into());
+    }
+
+    #[test]
+    fn test_output_only_directory_missing_file() {
+        let tmpdir = tempdir::TempDir::new("tm-test").unwrap();
+        std::fs::create_dir_all(tmpdir.path().join("sol").join("sub")).unwrap();
+        let task = make_output_only_task(tmpdir.path());
+        let (mut eval, recv) = EvaluationData::new(tmpdir.path());
+        let submissions = OutputOnlySubmission::find(tmpdir.path(), &Default::default());
+        let input = File::new("input").uuid;
+        let output = File::new("output").uuid;
+        let score_manager = Arc::new(Mutex::new(ScoreManager::new(&task)));
+        task.task_type
+            .evaluate_submission(
+                &mut eval,
+                0,
+                0,
+                &submissions[0],
+                input,
+                Some(output),
+                score_manager,
+            )
+            .unwrap();
+        assert_eq!(eval.dag.data.execution_groups.len(), 0);
+        let mut scored = false;
+        while let Ok(message) = recv.try_recv() {
+            if let UIMessage::IOITestcaseScore { score, .. } = message {
+                assert_abs_diff_eq!(score, 0.0);
+                scored = true;
+            }
+        }
+        assert!(scored);
+    }
+
+    /// Write a zip archive with the specified files.
+    fn write_zip(path: &Path, files: &[(&str, &str)]) {
+        let mut zip = zip::ZipWriter::new(std::fs::File::create(path).unwrap());
+        let options =
+            zip::write::FileOptions::default().compression_method(zip::CompressionMethod::Stored);
+        for (name, content) in files {
+            zip.start_file(*name, options).unwrap();
+            zip.write_all(content.as_bytes()).unwrap();
+        }
+        zip.finish().unwrap();
+    }
+
+    #[test]
+    fn test_output_only_zip() {
+        let tmpdir = tempdir::TempDir::new("tm-test").unwrap();
+        std::fs::create_dir_all(tmpdir.path().join("sol")).unwrap();
+        write_zip(
+            &tmpdir.path().join("sol").join("sub.zip"),
+            &[("output_0.txt", "x")],
+        );
+        std::fs::write(tmpdir.path().join("sol").join("sol.cpp"), "x").unwrap();
+        let task = make_output_only_task(tmpdir.path());
+        let (mut eval, _recv) = EvaluationData::new(tmpdir.path());
+        let submissions = OutputOnlySubmission::find(tmpdir.path(), &Default::default());
+        assert_eq!(submissions.len(), 1);
+        let input = File::new("input").uuid;
+        let output = File::new("output").uuid;
+        let score_manager = Arc::new(Mutex::new(ScoreManager::new(&task)));
+        task.task_type
+            .evaluate_submission(
+                &mut eval,
+                0,
+                0,
+                &submissions[0],
+                input,
+                Some(output),
+                score_manager,
+            )
+            .unwrap();
+        // the output file is extracted while building the DAG, only the checker is executed
+        assert_eq!(eval.dag.data.provided_files.len(), 1);
+        let provided = eval.dag.data.provided_files.values().next().unwrap();
+        match provided {
+            ProvidedFile::Content { content, .. } => assert_eq!(content, b"x"),
+            _ => panic!("Expecting the content of the output file"),
+        }
+        assert_eq!(eval.dag.data.execution_groups.len(), 1);
+        let group = eval.dag.data.execution_groups.values().next().unwrap();
+        assert_eq!(group.tag().as_ref().unwrap(), &Tag::Checking.into());
+    }
+
+    #[test]
+    fn test_output_only_zip_missing_file() {
+        let tmpdir = tempdir::TempDir::new("tm-test").unwrap();
+        std::fs::create_dir_all(tmpdir.path().join("sol")).unwrap();
+        write_zip(
+            &tmpdir.path().join("sol").join("sub.zip"),
+            &[("output_1.txt", "x")],
+        );
+        let task = make_output_only_task(tmpdir.path());
+        let (mut eval, recv) = EvaluationData::new(tmpdir.path());
+        let submissions = OutputOnlySubmission::find(tmpdir.path(), &Default::default());
+        let input = File::new("input").uuid;
+        let output = File::new("output").uuid;
+        let score_manager = Arc::new(Mutex::new(ScoreManager::new(&task)));
+        task.task_type
+            .evaluate_submission(
+                &mut eval,
+                0,
+                0,
+                &submissions[0],
+                input,
+                Some(output),
+                score_manager,
+            )
+            .unwrap();
+        assert_eq!(eval.dag.data.execution_groups.len(), 0);
+        let mut missing = false;
+        while let Ok(message) = recv.try_recv() {
+            if let UIMessage::IOIMissingOutput { testcase, .. } = message {
+                assert_eq!(testcase, 0);
+                missing = true;
+            }
+        }
+        assert!(missing);
+    }
+
+    #[test]
+    fn test_output_only_invalid_zip() {
+        let tmpdir = tempdir::TempDir::new("tm-test").unwrap();
+        std::fs::create_dir_all(tmpdir.path().join("sol")).unwrap();
+        std::fs::write(tmpdir.path().join("sol").join("sub.zip"), "x").unwrap();
+        let task = make_output_only_task(tmpdir.path());
+        let (mut eval, _recv) = EvaluationData::new(tmpdir.path());
+        let submissions = OutputOnlySubmission::find(tmpdir.path(), &Default::default());
+        let input = File::new("input").uuid;
+        let output = File::new("output").uuid;
+        let score_manager = Arc::new(Mutex::new(ScoreManager::new(&task)));
+        let res = task.task_type.evaluate_submission(
+            &mut eval,
+            0,
+            0,
+            &submissions[0],
+            input,
+            Some(output),
+            score_manager,
+        );
+        assert!(res.is_err());
+    }
+
+    #[test]
+    fn test_score_sender_once() {
+        // any task with a testcase works here
+        let task = make_output_only_task("");
+        let (eval, recv) = EvaluationData::new("");
+        let score_manager = Arc::new(Mutex::new(ScoreManager::new(&task)));
+        let sender =
+            task_type::ScoreSender::new(0, 0, eval.sender.clone(), "sol".into(), score_manager);
+        // a process of the solution fails, then the checker runs on its (partial) output
+        sender.send(0.0, "Signal(11)".into()).unwrap();
+        sender.send(1.0, "Output is correct".into()).unwrap();
+        let mut scores = Vec::new();
+        while let Ok(message) = recv.try_recv() {
+            if let UIMessage::IOITestcaseScore { score, .. } = message {
+                scores.push(score);
+            }
+        }
+        assert_eq!(scores, vec![0.0]);
+    }
+
+    #[test]
+    fn test_two_steps_pipe() {
+        let tmpdir = tempdir::TempDir::new("tm-test").unwrap();
+        let path = tmpdir.path().join("sol.py");
+        std::fs::write(&path, "x").unwrap();
+        let source = SourceFile::new(&path, "", None, None::<PathBuf>).unwrap();
+        let mut task = make_task(tmpdir.path());
+        task.task_type = TaskType::TwoSteps(TwoStepsTypeData {
+            output_generator: None,
+            checker: Checker::WhiteDiff,
+        });
+        let (mut eval, _recv) = EvaluationData::new(tmpdir.path());
+        let input = File::new("input").uuid;
+        let output = File::new("output").uuid;
+        let score_manager = Arc::new(Mutex::new(ScoreManager::new(&task)));
+        task.task_type
+            .evaluate(
+                &task,
+                &mut eval,
+                0,
+                0,
+                &source,
+                1,
+                input,
+                None,
+                Some(output),
+                score_manager,
+            )
+            .unwrap();
+        // the two steps and the checker
+        assert_eq!(eval.dag.data.execution_groups.len(), 2);
+        let group = eval
+            .dag
+            .data
+            .execution_groups
+            .values()
+            .find(|group| group.tag().as_ref().unwrap() == &Tag::Evaluation.into())
+            .unwrap();
+        assert_eq!(group.executions.len(), 2);
+        assert_eq!(group.fifo.len(), 1);
+        let first = group
+            .executions
+            .iter()
+            .find(|e| e.args == vec!["0"])
+            .unwrap();
+        let second = group
+            .executions
+            .iter()
+            .find(|e| e.args == vec!["1"])
+            .unwrap();
+        assert_eq!(first.stdin, Some(input));
+        assert_eq!(first.stdout_fifo.unwrap().uuid, group.fifo[0].uuid);
+        assert!(first.stdout.is_none());
+        assert_eq!(second.stdin_fifo.unwrap().uuid, group.fifo[0].uuid);
+        assert!(second.stdin.is_none());
+        assert!(second.stdout.is_some());
+    }
+
+    #[test]
+    fn test_communication_std_io() {
+        let tmpdir = tempdir::TempDir::new("tm-test").unwrap();
+        let path = tmpdir.path().join("sol.py");
+        std::fs::write(&path, "x").unwrap();
+        let source = SourceFile::new(&path, "", None, None::<PathBuf>).unwrap();
+        let manager_path = tmpdir.path().join("manager.py");
+        std::fs::write(&manager_path, "x").unwrap();
+        let manager = SourceFile::new(&manager_path, "", None, None::<PathBuf>).unwrap();
+        let mut task = make_task(tmpdir.path());
+        task.task_type = TaskType::Communication(CommunicationTypeData {
+            manager: Arc::new(manager),
+            num_processes: 1,
+            user_io: UserIo::StdIo,
+            manager_type: ManagerType::Cms,
+        });
+        let (mut eval, _recv) = EvaluationData::new(tmpdir.path());
+        let input = File::new("input").uuid;
+        let score_manager = Arc::new(Mutex::new(ScoreManager::new(&task)));
+        task.task_type
+            .evaluate(
+                &task,
+                &mut eval,
+                0,
+                0,
+                &source,
+                1,
+                input,
+                None,
+                None,
+                score_manager,
+            )
+            .unwrap();
+        let group = eval.dag.data.execution_groups.values().next().unwrap();
+        assert_eq!(group.executions.len(), 2);
+        assert_eq!(group.fifo.len(), 2);
+        let solution = &group.executions[0];
+        let manager = &group.executions[1];
+        assert!(solution.args.is_empty());
+        let stdin = solution.stdin_fifo.unwrap();
+        let stdout = solution.stdout_fifo.unwrap();
+        assert_ne!(stdin.uuid, stdout.uuid);
+        assert_eq!(
+            manager.args,
+            vec![
+                stdin.sandbox_path().to_string_lossy().to_string(),
+                stdout.sandbox_path().to_string_lossy().to_string()
+            ]
+        );
+    }
+
+    #[test]
+    fn test_communication_testlib() {
+        let tmpdir = tempdir::TempDir::new("tm-test").unwrap();
+        let path = tmpdir.path().join("sol.py");
+        std::fs::write(&path, "x").unwrap();
+        let source = SourceFile::new(&path, "", None, None::<PathBuf>).unwrap();
+        let manager_path = tmpdir.path().join("manager.py");
+        std::fs::write(&manager_path, "x").unwrap();
+        let manager = SourceFile::new(&manager_path, "", None, None::<PathBuf>).unwrap();
+        let mut task = make_task(tmpdir.path());
+        task.task_type = TaskType::Communication(CommunicationTypeData {
+            manager: Arc::new(manager),
+            num_processes: 1,
+            user_io: UserIo::StdIo,
+            manager_type: ManagerType::Testlib,
+        });
+        let (mut eval, _recv) = EvaluationData::new(tmpdir.path());
+        let input = File::new("input").uuid;
+        let score_manager = Arc::new(Mutex::new(ScoreManager::new(&task)));
+        task.task_type
+            .evaluate(
+                &task,
+                &mut eval,
+                0,
+                0,
+                &source,
+                1,
+                input,
+                None,
+                None,
+                score_manager,
+            )
+            .unwrap();
+        let group = eval.dag.data.execution_groups.values().next().unwrap();
+        assert_eq!(group.executions.len(), 2);
+        let solution = &group.executions[0];
+        let manager = &group.executions[1];
+        assert_eq!(manager.args, vec!["input.txt", "output.txt"]);
+        assert_eq!(
+            manager.stdin_fifo.unwrap().uuid,
+            solution.stdout_fifo.unwrap().uuid
+        );
+        assert_eq!(
+            manager.stdout_fifo.unwrap().uuid,
+            solution.stdin_fifo.unwrap().uuid
+        );
+        assert!(manager.stdin.is_none());
+        assert!(manager.stdout.is_none());
+        assert!(manager.inputs.contains_key(Path::new("input.txt")));
+    }
+
+    #[test]
+    fn test_batch_limits() {
+        let tmpdir = tempdir::TempDir::new("tm-test").unwrap();
+        let path = tmpdir.path().join("sol.py");
+        std::fs::write(&path, "x").unwrap();
+        let source = SourceFile::new(&path, "", None, None::<PathBuf>).unwrap();
+        let mut task = make_output_only_task(tmpdir.path());
+        task.task_type = TaskType::Batch(BatchTypeData {
+            output_generator: None,
+            checker: Checker::WhiteDiff,
+        });
+        task.time_limit = Some(1.0);
+        task.memory_limit = Some(256);
+        task.subtasks.get_mut(&0).unwrap().memory_limit = Some(1024);
+        task.limits_multipliers.insert(
+            "Python".into(),
+            LimitsMultipliers {
+                time: 3.0,
+                memory: 1.0,
+            },
+        );
+        let (mut eval, _recv) = EvaluationData::new(tmpdir.path());
+        let input = File::new("input").uuid;
+        let output = File::new("output").uuid;
+        let score_manager = Arc::new(Mutex::new(ScoreManager::new(&task)));
+        task.task_type
+            .evaluate(
+                &task,
+                &mut eval,
+                0,
+                0,
+                &source,
+                1,
+                input,
+                None,
+                Some(output),
+                score_manager,
+            )
+            .unwrap();
+        let group = eval
+            .dag
+            .data
+            .execution_groups
+            .values()
+            .find(|group| group.tag().as_ref().unwrap() == &Tag::Evaluation.into())
+            .unwrap();
+        let limits = &group.executions[0].limits;
+        assert_abs_diff_eq!(limits.cpu_time.unwrap(), 3.0);
+        assert_abs_diff_eq!(limits.wall_time.unwrap(), 5.5);
+        assert_eq!(limits.memory, Some(1024 * 1024));
+    }
+
+    #[test]
+    fn test_solution_limits() {
+        let mut task = make_output_only_task("");
+        task.time_limit = Some(2.0);
+        task.memory_limit = Some(100);
+        task.subtasks.get_mut(&0).unwrap().time_limit = Some(4.0);
+        task.limits_multipliers.insert(
+            "C++".into(),
+            LimitsMultipliers {
+                time: 0.5,
+                memory: 1.5,
+            },
+        );
+        assert_abs_diff_eq!(task.solution_time_limit(0, "C++").unwrap(), 2.0);
+        assert_abs_diff_eq!(task.solution_time_limit(0, "C").unwrap(), 4.0);
+        assert_abs_diff_eq!(task.solution_time_limit(1, "C").unwrap(), 2.0);
+        assert_eq!(task.solution_memory_limit(0, "C++"), Some(150));
+        assert_eq!(task.solution_memory_limit(0, "C"), Some(100));
+        task.memory_limit = None;
+        assert_eq!(task.solution_memory_limit(0, "C++"), None);
+    }
 }
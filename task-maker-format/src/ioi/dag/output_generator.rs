@@ -5,8 +5,9 @@ use failure::{bail, Error};
 use serde::{Deserialize, Serialize};
 use typescript_definitions::TypeScriptify;
 
-use task_maker_dag::{Execution, File, FileUuid, Priority};
+use task_maker_dag::{ExecutionGroup, File, FileUuid, Priority};
 
+use crate::ioi::dag::task_type::build_two_steps_pipeline;
 use crate::ioi::{IOITask, SubtaskId, TestcaseId, GENERATION_PRIORITY};
 use crate::ui::UIMessage;
 use crate::{bind_exec_callbacks, bind_exec_io};
@@ -23,12 +24,15 @@ pub enum OutputGenerator {
     /// Use a custom command to generate the output file. The task specification for input/output
     /// files are used.
     Custom(Arc<SourceFile>, Vec<String>),
+    /// Use the official solution of a TwoSteps task to generate the output file, running it with
+    /// the same two processes used for the evaluation of the solutions.
+    TwoSteps(Arc<SourceFile>),
 }
 
 impl OutputGenerator {
-    /// Build the execution for the generation of the output file. Return the handle to the output
-    /// file and the `Execution` if any. The execution does not send UI messages yet and it's not
-    /// added to the DAG.
+    /// Build the executions for the generation of the output file. Return the handle to the output
+    /// file and the `ExecutionGroup` if any. The executions do not send UI messages yet and they are
+    /// not added to the DAG.
     #[allow(clippy::too_many_arguments)]
     pub(crate) fn generate(
         &self,
@@ -39,7 +43,7 @@ impl OutputGenerator {
         testcase_id: TestcaseId,
         input: FileUuid,
         validation_handle: Option<FileUuid>,
-    ) -> Result<(Option<FileUuid>, Option<Execution>), Error> {
+    ) -> Result<(Option<FileUuid>, Option<ExecutionGroup>), Error> {
         match self {
             OutputGenerator::NotAvailable => Ok((None, None)),
             OutputGenerator::StaticFile(path) => {
@@ -59,19 +63,37 @@ impl OutputGenerator {
                 exec.tag(Tag::Generation.into());
                 exec.priority(GENERATION_PRIORITY - testcase_id as Priority);
                 let output = bind_exec_io!(exec, task, input, validation_handle);
-                Ok((Some(output.uuid), Some(exec)))
+                Ok((Some(output.uuid), Some(exec.into())))
+            }
+            OutputGenerator::TwoSteps(source_file) => {
+                let (mut group, output) = build_two_steps_pipeline(
+                    task,
+                    eval,
+                    description,
+                    source_file,
+                    input,
+                    validation_handle,
+                )?;
+                for exec in group.executions.iter_mut() {
+                    exec.tag(Tag::Generation.into());
+                    exec.priority(GENERATION_PRIORITY - testcase_id as Priority);
+                }
+                Ok((Some(output.uuid), Some(group)))
             }
         }
     }
 
     /// Add the generation of the output file to the DAG and the callbacks to the UI, returning the
-    /// handle to the output file.
+    /// handle to the output file. The file is written to `output/<file_name>` in the task
+    /// directory.
+    #[allow(clippy::too_many_arguments)]
     pub(crate) fn generate_and_bind(
         &self,
         task: &IOITask,
         eval: &mut EvaluationData,
         subtask_id: SubtaskId,
         testcase_id: TestcaseId,
+        file_name: &str,
         input: FileUuid,
         validation_handle: Option<FileUuid>,
     ) -> Result<Option<FileUuid>, Error> {
@@ -88,21 +110,18 @@ impl OutputGenerator {
             validation_handle,
         )?;
         if let Some(sol) = sol {
-            bind_exec_callbacks!(eval, sol.uuid, |status| UIMessage::IOISolution {
-                subtask: subtask_id,
-                testcase: testcase_id,
-                status
-            })?;
-            eval.dag.add_execution(sol);
+            for exec in &sol.executions {
+                bind_exec_callbacks!(eval, exec.uuid, |status| UIMessage::IOISolution {
+                    subtask: subtask_id,
+                    testcase: testcase_id,
+                    status
+                })?;
+            }
+            eval.dag.add_execution_group(sol);
         }
         if let Some(output) = output {
-            eval.dag.write_file_to(
-                output,
-                task.path
-                    .join("output")
-                    .join(format!("output{}.txt", testcase_id)),
-                false,
-            );
+            eval.dag
+                .write_file_to(output, task.path.join("output").join(file_name), false);
         }
         Ok(output)
     }
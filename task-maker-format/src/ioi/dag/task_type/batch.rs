@@ -6,6 +6,7 @@ use typescript_definitions::TypeScriptify;
 
 use task_maker_dag::{ExecutionStatus, FileUuid, Priority};
 
+use crate::ioi::dag::task_type::set_solution_limits;
 use crate::ioi::{
     Checker, IOITask, OutputGenerator, ScoreManager, SubtaskId, TestcaseId, EVALUATION_PRIORITY,
 };
@@ -31,6 +32,7 @@ pub fn evaluate(
     subtask_id: SubtaskId,
     testcase_id: TestcaseId,
     source_file: &SourceFile,
+    repetitions: usize,
     input: FileUuid,
     validation_handle: Option<FileUuid>,
     correct_output: Option<FileUuid>,
@@ -52,14 +54,8 @@ pub fn evaluate(
     exec.priority(EVALUATION_PRIORITY - testcase_id as Priority);
     let output = bind_exec_io!(exec, task, input, validation_handle);
     let path = source_file.path.clone();
-    let limits = exec.limits_mut();
-    if let Some(time_limit) = task.time_limit {
-        limits.cpu_time(time_limit);
-        limits.wall_time(time_limit * 1.5 + 1.0); // some margin
-    }
-    if let Some(memory_limit) = task.memory_limit {
-        limits.memory(memory_limit * 1024); // MiB -> KiB
-    }
+    set_solution_limits(task, subtask_id, source_file, &mut exec);
+    exec.repetitions(repetitions);
     bind_exec_callbacks!(
         eval,
         exec.uuid,
@@ -76,10 +72,13 @@ pub fn evaluate(
     let sender = eval.sender.clone();
     let path = source_file.path.clone();
     let score_manager_err = score_manager.clone();
-    eval.dag
-        .on_execution_done(&exec.uuid, move |result| match result.status {
+    eval.dag.on_execution_done(&exec.uuid, move |result| {
+        let mut score_manager = score_manager_err.lock().unwrap();
+        score_manager.execution_status(testcase_id, &result.status);
+        score_manager.execution_resources(testcase_id, &result.resources);
+        match result.status {
             ExecutionStatus::Success => Ok(()),
-            _ => score_manager_err.lock().unwrap().score(
+            _ => score_manager.score(
                 subtask_id,
                 testcase_id,
                 0.0,
@@ -87,7 +86,8 @@ pub fn evaluate(
                 sender,
                 path,
             ),
-        });
+        }
+    });
     eval.dag.add_execution(exec);
 
     let sender = eval.sender.clone();
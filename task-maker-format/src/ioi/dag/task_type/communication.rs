@@ -1,52 +1,56 @@
-use std::path::PathBuf;
 use std::sync::{Arc, Mutex};
 
-use failure::{format_err, Error};
+use failure::{bail, format_err, Error};
 use serde::{Deserialize, Serialize};
 use typescript_definitions::TypeScriptify;
 
-use task_maker_dag::{ExecutionGroup, FileUuid, Priority};
+use task_maker_dag::{ExecutionGroup, ExecutionStatus, Fifo, FileUuid, Priority};
 
-use crate::ioi::{IOITask, ScoreManager, SubtaskId, TestcaseId, EVALUATION_PRIORITY};
-use crate::ui::{UIMessage, UIMessageSender};
+use crate::ioi::dag::task_type::{set_solution_limits, ScoreSender};
+use crate::ioi::{Checker, IOITask, ScoreManager, SubtaskId, TestcaseId, EVALUATION_PRIORITY};
+use crate::ui::UIMessage;
 use crate::{bind_exec_callbacks, bind_exec_io};
 use crate::{EvaluationData, SourceFile, Tag};
 
-/// The internal data of a task of type `Batch`.
+/// The name of the input file of a testlib interactor inside the sandbox.
+const TESTLIB_INPUT: &str = "input.txt";
+/// The name of the output file of a testlib interactor inside the sandbox.
+const TESTLIB_OUTPUT: &str = "output.txt";
+
+/// The internal data of a task of type `Communication`.
 #[derive(Debug, Clone, Serialize, Deserialize, TypeScriptify)]
 pub struct CommunicationTypeData {
     /// The source file of the manager that communicates with the solutions.
     pub manager: Arc<SourceFile>,
     /// Number of solution processes to spawn in parallel in a communication task.
     pub num_processes: u8,
+    /// How the solution communicates with the manager.
+    pub user_io: UserIo,
+    /// How the manager tells the outcome of the solution.
+    pub manager_type: ManagerType,
 }
 
-/// Internal data of `ScoreSender`.
-#[derive(Debug, Clone)]
-struct ScoreSenderData {
-    /// The id of the current subtask.
-    subtask_id: SubtaskId,
-    /// The id of the current testcase.
-    testcase_id: TestcaseId,
-    /// The sender to use for with the `ScoreManager`.
-    sender: Arc<Mutex<UIMessageSender>>,
-    /// The path of the current solution.
-    path: PathBuf,
-    /// The score manager to use for sending the score.
-    score_manager: Arc<Mutex<ScoreManager>>,
-    /// Whether the score has already been sent. This avoids sending the score more than once.
-    done: bool,
+/// The protocol used by the manager of a communication task.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, TypeScriptify)]
+pub enum ManagerType {
+    /// The manager receives the paths of the FIFO pipes as arguments and the input file in the
+    /// task's input, it writes the score to stdout and the message to stderr.
+    Cms,
+    /// The manager is a testlib interactor: it's started as `interactor input output`, it talks
+    /// with the only process of the solution using its standard input and output and it tells the
+    /// outcome using the exit code, like a testlib checker.
+    Testlib,
 }
 
-/// Utility structure for sending the score only once. Since there are many points where the score
-/// can be generated (the manager, but also each process if it fails), it's easier to centralize the
-/// control of the sending.
-/// It's important that, in case of a failure, the first process that fails is marked as the cause
-/// because it will stop the entire group, maybe letting the other executions fail.
-#[derive(Debug, Clone)]
-struct ScoreSender {
-    /// Interior mutability allowing this struct to be Clone, Send and Sync.
-    data: Arc<Mutex<ScoreSenderData>>,
+/// The way the solutions of a communication task are connected to the manager.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, TypeScriptify)]
+pub enum UserIo {
+    /// The paths of the FIFO pipes are passed to the solution as command line arguments, usually a
+    /// grader opens them.
+    FifoIo,
+    /// The standard input and the standard output of the solution are connected directly to the
+    /// FIFO pipes of the manager.
+    StdIo,
 }
 
 /// Evaluate a solution in a task of Batch type.
@@ -57,6 +61,7 @@ pub fn evaluate(
     subtask_id: SubtaskId,
     testcase_id: TestcaseId,
     source_file: &SourceFile,
+    repetitions: usize,
     input: FileUuid,
     validation_handle: Option<FileUuid>,
     _correct_output: Option<FileUuid>,
@@ -73,11 +78,10 @@ pub fn evaluate(
     let mut fifo_man2sol = Vec::new();
     let mut fifo_sol2man = Vec::new();
     for _ in 0..data.num_processes {
-        let fifo1 = group.new_fifo().sandbox_path();
-        fifo_man2sol.push(fifo1.to_str().unwrap().to_string());
-        let fifo2 = group.new_fifo().sandbox_path();
-        fifo_sol2man.push(fifo2.to_str().unwrap().to_string());
+        fifo_man2sol.push(group.new_fifo());
+        fifo_sol2man.push(group.new_fifo());
     }
+    let fifo_path = |fifo: &Fifo| fifo.sandbox_path().to_str().unwrap().to_string();
 
     let path = source_file.path.clone();
     let num_processes = data.num_processes as usize;
@@ -89,10 +93,13 @@ pub fn evaluate(
         score_manager,
     );
     for process_index in 0..num_processes {
-        let mut args = vec![
-            fifo_sol2man[process_index].clone(),
-            fifo_man2sol[process_index].clone(),
-        ];
+        let mut args = match data.user_io {
+            UserIo::FifoIo => vec![
+                fifo_path(&fifo_sol2man[process_index]),
+                fifo_path(&fifo_man2sol[process_index]),
+            ],
+            UserIo::StdIo => vec![],
+        };
         if num_processes > 1 {
             args.push(process_index.to_string());
         }
@@ -110,14 +117,12 @@ pub fn evaluate(
         )?;
         exec.tag(Tag::Evaluation.into());
         exec.priority(EVALUATION_PRIORITY - testcase_id as Priority);
-        let limits = exec.limits_mut();
-        if let Some(time_limit) = task.time_limit {
-            limits.cpu_time(time_limit);
-            limits.wall_time(time_limit * 1.5 + 1.0); // some margin
-        }
-        if let Some(memory_limit) = task.memory_limit {
-            limits.memory(memory_limit * 1024); // MiB -> KiB
+        if data.user_io == UserIo::StdIo {
+            exec.stdin_fifo(fifo_man2sol[process_index])
+                .stdout_fifo(fifo_sol2man[process_index]);
         }
+        set_solution_limits(task, subtask_id, source_file, &mut exec);
+        exec.repetitions(repetitions);
         bind_exec_callbacks!(
             eval,
             exec.uuid,
@@ -133,6 +138,8 @@ pub fn evaluate(
         )?;
         let score_sender = score_sender.clone();
         eval.dag.on_execution_done(&exec.uuid, move |result| {
+            score_sender.execution_status(&result.status);
+            score_sender.execution_resources(&result.resources);
             if !result.status.is_success() {
                 score_sender.send(0.0, format!("{:?}", result.status))?;
             }
@@ -141,11 +148,22 @@ pub fn evaluate(
         group.add_execution(exec);
     }
 
-    let mut args = Vec::new();
-    for process_index in 0..num_processes {
-        args.push(&fifo_man2sol[process_index]);
-        args.push(&fifo_sol2man[process_index]);
-    }
+    let args = match data.manager_type {
+        ManagerType::Cms => {
+            let mut args = Vec::new();
+            for process_index in 0..num_processes {
+                args.push(fifo_path(&fifo_man2sol[process_index]));
+                args.push(fifo_path(&fifo_sol2man[process_index]));
+            }
+            args
+        }
+        ManagerType::Testlib => {
+            if num_processes != 1 {
+                bail!("A testlib interactor supports only one process of the solution");
+            }
+            vec![TESTLIB_INPUT.to_string(), TESTLIB_OUTPUT.to_string()]
+        }
+    };
     let mut exec = data.manager.execute(
         eval,
         format!(
@@ -158,11 +176,27 @@ pub fn evaluate(
     )?;
     exec.tag(Tag::Evaluation.into())
         .priority(EVALUATION_PRIORITY - testcase_id as Priority)
-        .capture_stdout(128)
         .capture_stderr(1024);
-    bind_exec_io!(exec, task, input, validation_handle);
+    match data.manager_type {
+        ManagerType::Cms => {
+            exec.capture_stdout(128);
+            bind_exec_io!(exec, task, input, validation_handle);
+        }
+        ManagerType::Testlib => {
+            exec.input(input, TESTLIB_INPUT, false)
+                .stdin_fifo(fifo_sol2man[0])
+                .stdout_fifo(fifo_man2sol[0]);
+            if let Some(validation_handle) = validation_handle {
+                exec.input(validation_handle, "wait_for_validation", false);
+            }
+            // the interactor may write some data for the checker, it's not used
+            exec.output(TESTLIB_OUTPUT);
+        }
+    }
+    let language = source_file.language().name();
     let limits = exec.limits_mut();
-    if let Some(time_limit) = task.time_limit {
+    // the manager runs for as long as the solutions do
+    if let Some(time_limit) = task.solution_time_limit(subtask_id, language) {
         limits.cpu_time((time_limit + 1.0) * num_processes as f64);
         limits.wall_time(limits.cpu_time.unwrap() * 1.5 + 1.0); // some margin
     }
@@ -180,7 +214,21 @@ pub fn evaluate(
         },
         path
     )?;
+    let manager_type = data.manager_type;
     eval.dag.on_execution_done(&exec.uuid, move |result| {
+        if manager_type == ManagerType::Testlib {
+            if let ExecutionStatus::Success | ExecutionStatus::ReturnCode(_) = result.status {
+                let stderr = result
+                    .stderr
+                    .ok_or_else(|| format_err!("Checker stderr not captured"))?;
+                let message = String::from_utf8_lossy(&stderr).trim().to_string();
+                let (score, message) = Checker::parse_testlib_outcome(&result.status, message)?;
+                score_sender.send(score, message)?;
+            } else {
+                score_sender.send(0.0, "Checker failed".to_string())?;
+            }
+            return Ok(());
+        }
         if !result.status.is_success() {
             score_sender.send(0.0, "Checker failed".to_string())?;
             return Ok(());
@@ -204,43 +252,3 @@ pub fn evaluate(
     eval.dag.add_execution_group(group);
     Ok(())
 }
-
-impl ScoreSender {
-    /// Make a new `ScoreSender` for a testcase of a solution.
-    fn new(
-        subtask_id: SubtaskId,
-        testcase_id: TestcaseId,
-        sender: Arc<Mutex<UIMessageSender>>,
-        path: PathBuf,
-        score_manager: Arc<Mutex<ScoreManager>>,
-    ) -> ScoreSender {
-        ScoreSender {
-            data: Arc::new(Mutex::new(ScoreSenderData {
-                subtask_id,
-                testcase_id,
-                sender,
-                path,
-                score_manager,
-                done: false,
-            })),
-        }
-    }
-
-    /// Send the score to the `ScoreManager`, if not already sent.
-    fn send(&self, score: f64, message: String) -> Result<(), Error> {
-        let data = self.data.lock().unwrap();
-        // do not send the score twice
-        if data.done {
-            return Ok(());
-        }
-        data.score_manager.lock().unwrap().score(
-            data.subtask_id,
-            data.testcase_id,
-            score,
-            message,
-            data.sender.clone(),
-            data.path.clone(),
-        )?;
-        Ok(())
-    }
-}
@@ -1,18 +1,25 @@
+use std::path::PathBuf;
 use std::sync::{Arc, Mutex};
 
-use failure::Error;
+use failure::{bail, Error};
 use serde::{Deserialize, Serialize};
 use typescript_definitions::TypeScriptify;
 
 pub use batch::BatchTypeData;
-pub use communication::CommunicationTypeData;
-use task_maker_dag::FileUuid;
+pub use communication::{CommunicationTypeData, ManagerType, UserIo};
+pub use output_only::{OutputOnlySubmission, OutputOnlyTypeData};
+use task_maker_dag::{Execution, ExecutionResourcesUsage, ExecutionStatus, FileUuid};
+pub(crate) use two_steps::build_pipeline as build_two_steps_pipeline;
+pub use two_steps::TwoStepsTypeData;
 
 use crate::ioi::{IOITask, ScoreManager, SubtaskId, TestcaseId};
+use crate::ui::UIMessageSender;
 use crate::{EvaluationData, SourceFile};
 
 mod batch;
 mod communication;
+mod output_only;
+mod two_steps;
 
 /// The type of the task. This changes the behavior of the solutions.
 #[derive(Debug, Clone, Serialize, Deserialize, TypeScriptify)]
@@ -24,11 +31,19 @@ pub enum TaskType {
     /// The solution is executed in parallel with a manager and communicate using FIFO pipes. There
     /// are only input files since the manager computes the score of the solution.
     Communication(CommunicationTypeData),
+    /// The solution is not a program but a set of output files, one per testcase, provided by the
+    /// contestant. The output files are checked with the same checker of `Batch` tasks.
+    OutputOnly(OutputOnlyTypeData),
+    /// The solution is executed twice in parallel, the output of the first process is sent to the
+    /// input of the second one using a FIFO pipe. The first process reads the input file and the
+    /// second writes the output file, which is then checked like in `Batch` tasks.
+    TwoSteps(TwoStepsTypeData),
 }
 
 impl TaskType {
     /// Evaluate a solution on a testcase, eventually adding to the `ScoreManager` the result of the
-    /// evaluation. This will add both the execution as well as the checking to the DAG.
+    /// evaluation. This will add both the execution as well as the checking to the DAG. The
+    /// solution is run `repetitions` times for having more reliable timings.
     #[allow(clippy::too_many_arguments)]
     pub(crate) fn evaluate(
         &self,
@@ -37,6 +52,7 @@ impl TaskType {
         subtask_id: SubtaskId,
         testcase_id: TestcaseId,
         source_file: &SourceFile,
+        repetitions: usize,
         input: FileUuid,
         validation_handle: Option<FileUuid>,
         correct_output: Option<FileUuid>,
@@ -49,6 +65,7 @@ impl TaskType {
                 subtask_id,
                 testcase_id,
                 source_file,
+                repetitions,
                 input,
                 validation_handle,
                 correct_output,
@@ -61,12 +78,166 @@ impl TaskType {
                 subtask_id,
                 testcase_id,
                 source_file,
+                repetitions,
                 input,
                 validation_handle,
                 correct_output,
                 score_manager,
                 data,
             ),
+            TaskType::TwoSteps(data) => two_steps::evaluate(
+                task,
+                eval,
+                subtask_id,
+                testcase_id,
+                source_file,
+                repetitions,
+                input,
+                validation_handle,
+                correct_output,
+                score_manager,
+                data,
+            ),
+            TaskType::OutputOnly(_) => bail!(
+                "Cannot evaluate {} in an output only task",
+                source_file.name()
+            ),
+        }
+    }
+
+    /// Evaluate a submission of an output-only task on a testcase, eventually adding to the
+    /// `ScoreManager` the result of the evaluation. Fails if the task is not output-only.
+    #[allow(clippy::too_many_arguments)]
+    pub(crate) fn evaluate_submission(
+        &self,
+        eval: &mut EvaluationData,
+        subtask_id: SubtaskId,
+        testcase_id: TestcaseId,
+        submission: &OutputOnlySubmission,
+        input: FileUuid,
+        correct_output: Option<FileUuid>,
+        score_manager: Arc<Mutex<ScoreManager>>,
+    ) -> Result<(), Error> {
+        match self {
+            TaskType::OutputOnly(data) => output_only::evaluate(
+                eval,
+                subtask_id,
+                testcase_id,
+                submission,
+                input,
+                correct_output,
+                score_manager,
+                data,
+            ),
+            _ => bail!("Submissions can be evaluated only in output only tasks"),
+        }
+    }
+}
+
+/// Set the time and memory limits of an execution of a solution on a testcase of the subtask. The
+/// wall time limit is a bit larger than the time limit, for giving some margin to the solution.
+/// The language of the solution can then adapt the execution to its limits.
+fn set_solution_limits(
+    task: &IOITask,
+    subtask_id: SubtaskId,
+    source_file: &SourceFile,
+    exec: &mut Execution,
+) {
+    let language = source_file.language().name();
+    let limits = exec.limits_mut();
+    if let Some(time_limit) = task.solution_time_limit(subtask_id, language) {
+        limits.cpu_time(time_limit);
+        limits.wall_time(time_limit * 1.5 + 1.0); // some margin
+    }
+    if let Some(memory_limit) = task.solution_memory_limit(subtask_id, language) {
+        limits.memory(memory_limit * 1024); // MiB -> KiB
+    }
+    source_file.language().adapt_to_limits(exec);
+}
+
+/// Internal data of `ScoreSender`.
+#[derive(Debug, Clone)]
+struct ScoreSenderData {
+    /// The id of the current subtask.
+    subtask_id: SubtaskId,
+    /// The id of the current testcase.
+    testcase_id: TestcaseId,
+    /// The sender to use for with the `ScoreManager`.
+    sender: Arc<Mutex<UIMessageSender>>,
+    /// The path of the current solution.
+    path: PathBuf,
+    /// The score manager to use for sending the score.
+    score_manager: Arc<Mutex<ScoreManager>>,
+    /// Whether the score has already been sent. This avoids sending the score more than once.
+    done: bool,
+}
+
+/// Utility structure for sending the score only once. Since there are many points where the score
+/// can be generated (e.g. the checker, but also each process if it fails), it's easier to centralize the
+/// control of the sending.
+/// It's important that, in case of a failure, the first process that fails is marked as the cause
+/// because it will stop the entire group, maybe letting the other executions fail.
+#[derive(Debug, Clone)]
+pub(crate) struct ScoreSender {
+    /// Interior mutability allowing this struct to be Clone, Send and Sync.
+    data: Arc<Mutex<ScoreSenderData>>,
+}
+
+impl ScoreSender {
+    /// Make a new `ScoreSender` for a testcase of a solution.
+    pub(crate) fn new(
+        subtask_id: SubtaskId,
+        testcase_id: TestcaseId,
+        sender: Arc<Mutex<UIMessageSender>>,
+        path: PathBuf,
+        score_manager: Arc<Mutex<ScoreManager>>,
+    ) -> ScoreSender {
+        ScoreSender {
+            data: Arc::new(Mutex::new(ScoreSenderData {
+                subtask_id,
+                testcase_id,
+                sender,
+                path,
+                score_manager,
+                done: false,
+            })),
+        }
+    }
+
+    /// Store the status of an execution of the solution in the `ScoreManager`.
+    pub(crate) fn execution_status(&self, status: &ExecutionStatus) {
+        let data = self.data.lock().unwrap();
+        data.score_manager
+            .lock()
+            .unwrap()
+            .execution_status(data.testcase_id, status);
+    }
+
+    /// Store the resources used by an execution of the solution in the `ScoreManager`.
+    pub(crate) fn execution_resources(&self, resources: &ExecutionResourcesUsage) {
+        let data = self.data.lock().unwrap();
+        data.score_manager
+            .lock()
+            .unwrap()
+            .execution_resources(data.testcase_id, resources);
+    }
+
+    /// Send the score to the `ScoreManager`, if not already sent.
+    pub(crate) fn send(&self, score: f64, message: String) -> Result<(), Error> {
+        let mut data = self.data.lock().unwrap();
+        // do not send the score twice
+        if data.done {
+            return Ok(());
         }
+        data.done = true;
+        data.score_manager.lock().unwrap().score(
+            data.subtask_id,
+            data.testcase_id,
+            score,
+            message,
+            data.sender.clone(),
+            data.path.clone(),
+        )?;
+        Ok(())
     }
 }
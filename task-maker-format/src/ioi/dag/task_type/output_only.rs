@@ -0,0 +1,184 @@
+use std::io::Read;
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex};
+
+use failure::{format_err, Error};
+use serde::{Deserialize, Serialize};
+use typescript_definitions::TypeScriptify;
+use zip::result::ZipError;
+use zip::ZipArchive;
+
+use task_maker_dag::{File, FileUuid};
+
+use crate::ioi::{Checker, ScoreManager, SubtaskId, TestcaseId};
+use crate::ui::UIMessage;
+use crate::{EvaluationConfig, EvaluationData, UISender};
+
+/// The message of the testcases whose output file is not present in the submission.
+const MISSING_OUTPUT_MESSAGE: &str = "Missing output file";
+
+/// The internal data of a task of type `OutputOnly`.
+#[derive(Debug, Clone, Serialize, Deserialize, TypeScriptify)]
+pub struct OutputOnlyTypeData {
+    /// The checker to use for this task.
+    pub checker: Checker,
+}
+
+/// A set of output files submitted for an output-only task. The output files are named
+/// `output_<n>.txt`, where `<n>` is the id of the testcase, and they are either inside a directory
+/// (`sol/<name>/`) or inside a zip archive (`sol/<name>.zip`).
+#[derive(Debug, Clone)]
+pub struct OutputOnlySubmission {
+    /// The path to the directory or to the zip archive with the output files.
+    pub path: PathBuf,
+    /// Whether the submission is a zip archive.
+    is_archive: bool,
+    /// The zip archive of the submission, opened the first time an output file is extracted and
+    /// then shared between all the testcases.
+    archive: Arc<Mutex<Option<ZipArchive<std::fs::File>>>>,
+}
+
+impl OutputOnlySubmission {
+    /// Search all the submissions inside the `sol/` folder of the task, applying the filters of
+    /// the configuration.
+    pub fn find(task_dir: &Path, config: &EvaluationConfig) -> Vec<OutputOnlySubmission> {
+        let filter = config.solution_filters();
+        let mut submissions = Vec::new();
+        for path in config.solution_paths(task_dir, vec!["sol/*"]) {
+            let name = path.file_name().unwrap().to_string_lossy();
+            if !config.solution_filter.is_empty()
+                && !filter
+                    .iter()
+                    .any(|filter| name.starts_with(filter.as_str()))
+            {
+                continue;
+            }
+            if path.is_dir() {
+                submissions.push(OutputOnlySubmission {
+                    path,
+                    is_archive: false,
+                    archive: Default::default(),
+                });
+            } else if path.extension().map(|ext| ext == "zip").unwrap_or(false) {
+                submissions.push(OutputOnlySubmission {
+                    path,
+                    is_archive: true,
+                    archive: Default::default(),
+                });
+            }
+        }
+        submissions
+    }
+
+    /// Extract the content of a file from the zip archive of this submission, returning `None` if
+    /// the archive does not contain that file.
+    fn extract(&self, file_name: &str) -> Result<Option<Vec<u8>>, Error> {
+        let invalid_archive =
+            |e: ZipError| format_err!("Invalid zip archive {}: {}", self.path.display(), e);
+        let mut archive = self.archive.lock().unwrap();
+        if archive.is_none() {
+            let file = std::fs::File::open(&self.path)
+                .map_err(|e| format_err!("Failed to open {}: {}", self.path.display(), e))?;
+            *archive = Some(ZipArchive::new(file).map_err(invalid_archive)?);
+        }
+        let archive = archive.as_mut().unwrap();
+        let mut file = match archive.by_name(file_name) {
+            Ok(file) => file,
+            Err(ZipError::FileNotFound) => return Ok(None),
+            Err(e) => return Err(invalid_archive(e)),
+        };
+        let mut content = Vec::new();
+        file.read_to_end(&mut content)
+            .map_err(|e| format_err!("Failed to extract {}: {}", file_name, e))?;
+        Ok(Some(content))
+    }
+
+    /// The name of the file with the output of the specified testcase.
+    pub fn output_file_name(testcase_id: TestcaseId) -> String {
+        format!("output_{}.txt", testcase_id)
+    }
+
+    /// The name of this submission.
+    pub fn name(&self) -> String {
+        self.path.file_name().unwrap().to_string_lossy().to_string()
+    }
+}
+
+/// Evaluate a submission in a task of OutputOnly type: the output file of the testcase is taken
+/// from the submission and checked using the checker of the task.
+#[allow(clippy::too_many_arguments)]
+pub fn evaluate(
+    eval: &mut EvaluationData,
+    subtask_id: SubtaskId,
+    testcase_id: TestcaseId,
+    submission: &OutputOnlySubmission,
+    input: FileUuid,
+    correct_output: Option<FileUuid>,
+    score_manager: Arc<Mutex<ScoreManager>>,
+    data: &OutputOnlyTypeData,
+) -> Result<(), Error> {
+    let correct_output = correct_output.ok_or_else(|| format_err!("Missing official solution"))?;
+    let file_name = OutputOnlySubmission::output_file_name(testcase_id);
+    let output = File::new(format!(
+        "Output file {} of {}",
+        file_name,
+        submission.name()
+    ));
+    let output_uuid = output.uuid;
+    let found = if submission.is_archive {
+        match submission.extract(&file_name)? {
+            Some(content) => {
+                eval.dag.provide_content(output, content);
+                true
+            }
+            None => false,
+        }
+    } else {
+        let output_path = submission.path.join(&file_name);
+        if output_path.exists() {
+            eval.dag.provide_file(output, &output_path)?;
+            true
+        } else {
+            false
+        }
+    };
+    if !found {
+        eval.sender.send(UIMessage::IOIMissingOutput {
+            subtask: subtask_id,
+            testcase: testcase_id,
+            solution: submission.path.clone(),
+        })?;
+        return score_manager.lock().unwrap().score(
+            subtask_id,
+            testcase_id,
+            0.0,
+            MISSING_OUTPUT_MESSAGE.into(),
+            eval.sender.clone(),
+            submission.path.clone(),
+        );
+    }
+    let output = output_uuid;
+
+    let sender = eval.sender.clone();
+    let path = submission.path.clone();
+    data.checker.check_and_bind(
+        eval,
+        subtask_id,
+        testcase_id,
+        submission.path.clone(),
+        input,
+        correct_output,
+        output,
+        move |score, message| {
+            score_manager.lock().unwrap().score(
+                subtask_id,
+                testcase_id,
+                score,
+                message,
+                sender,
+                path,
+            )
+        },
+    )?;
+    Ok(())
+}
@@ -0,0 +1,155 @@
+use std::sync::{Arc, Mutex};
+
+use failure::{format_err, Error};
+use serde::{Deserialize, Serialize};
+use typescript_definitions::TypeScriptify;
+
+use task_maker_dag::{ExecutionGroup, File, FileUuid, Priority};
+
+use crate::bind_exec_callbacks;
+use crate::ioi::dag::task_type::{set_solution_limits, ScoreSender};
+use crate::ioi::{
+    Checker, IOITask, OutputGenerator, ScoreManager, SubtaskId, TestcaseId, EVALUATION_PRIORITY,
+};
+use crate::ui::UIMessage;
+use crate::{EvaluationData, SourceFile, Tag};
+
+/// The internal data of a task of type `TwoSteps`.
+#[derive(Debug, Clone, Serialize, Deserialize, TypeScriptify)]
+pub struct TwoStepsTypeData {
+    /// The default output generator for this task, if any.
+    #[serde(skip_serializing)]
+    pub output_generator: Option<OutputGenerator>,
+    /// The checker to use for this task.
+    pub checker: Checker,
+}
+
+/// Build the two processes of a solution of a TwoSteps task, returning the group with the
+/// executions and the output file of the second step.
+///
+/// The solution is executed twice, concurrently: the first process is started with `0` as argument
+/// and it reads the input file, the second one is started with `1` as argument and it writes the
+/// output file. The standard output of the first process is connected to the standard input of the
+/// second using a FIFO pipe. The group is not added to the DAG.
+pub(crate) fn build_pipeline(
+    task: &IOITask,
+    eval: &mut EvaluationData,
+    description: String,
+    source_file: &SourceFile,
+    input: FileUuid,
+    validation_handle: Option<FileUuid>,
+) -> Result<(ExecutionGroup, File), Error> {
+    let mut group = ExecutionGroup::new(description.clone());
+    let pipe = group.new_fifo();
+    let mut output = None;
+    for step in 0..2 {
+        let mut exec = source_file.execute(
+            eval,
+            format!("{} (step {}/2)", description, step + 1),
+            vec![step.to_string()],
+        )?;
+        if step == 0 {
+            match &task.infile {
+                None => exec.stdin(input),
+                Some(infile) => exec.input(input, infile, false),
+            };
+            if let Some(validation_handle) = validation_handle {
+                exec.input(validation_handle, "wait_for_validation", false);
+            }
+            exec.stdout_fifo(pipe);
+        } else {
+            exec.stdin_fifo(pipe);
+            output = Some(match &task.outfile {
+                None => exec.stdout(),
+                Some(outfile) => exec.output(outfile),
+            });
+        }
+        group.add_execution(exec);
+    }
+    let output = output.expect("The second step has no output");
+    Ok((group, output))
+}
+
+/// Evaluate a solution in a task of TwoSteps type.
+///
+/// The two processes of the solution are built by `build_pipeline` and the output of the second
+/// process is then checked using the checker.
+#[allow(clippy::too_many_arguments)]
+pub fn evaluate(
+    task: &IOITask,
+    eval: &mut EvaluationData,
+    subtask_id: SubtaskId,
+    testcase_id: TestcaseId,
+    source_file: &SourceFile,
+    repetitions: usize,
+    input: FileUuid,
+    validation_handle: Option<FileUuid>,
+    correct_output: Option<FileUuid>,
+    score_manager: Arc<Mutex<ScoreManager>>,
+    data: &TwoStepsTypeData,
+) -> Result<(), Error> {
+    let correct_output = correct_output.ok_or_else(|| format_err!("Missing official solution"))?;
+    let (mut group, output) = build_pipeline(
+        task,
+        eval,
+        format!(
+            "Evaluation of {} on testcase {}, subtask {}",
+            source_file.name(),
+            testcase_id,
+            subtask_id
+        ),
+        source_file,
+        input,
+        validation_handle,
+    )?;
+
+    let path = source_file.path.clone();
+    let score_sender = ScoreSender::new(
+        subtask_id,
+        testcase_id,
+        eval.sender.clone(),
+        path.clone(),
+        score_manager,
+    );
+    for (step, exec) in group.executions.iter_mut().enumerate() {
+        exec.tag(Tag::Evaluation.into());
+        exec.priority(EVALUATION_PRIORITY - testcase_id as Priority);
+        set_solution_limits(task, subtask_id, source_file, exec);
+        exec.repetitions(repetitions);
+        bind_exec_callbacks!(
+            eval,
+            exec.uuid,
+            |status, solution| UIMessage::IOIEvaluation {
+                subtask: subtask_id,
+                testcase: testcase_id,
+                solution,
+                status,
+                part: step,
+                num_parts: 2,
+            },
+            path
+        )?;
+        let score_sender = score_sender.clone();
+        eval.dag.on_execution_done(&exec.uuid, move |result| {
+            score_sender.execution_status(&result.status);
+            score_sender.execution_resources(&result.resources);
+            if !result.status.is_success() {
+                score_sender.send(0.0, format!("{:?}", result.status))?;
+            }
+            Ok(())
+        });
+    }
+    eval.dag.add_execution_group(group);
+
+    data.checker.check_and_bind(
+        eval,
+        subtask_id,
+        testcase_id,
+        source_file.path.clone(),
+        input,
+        correct_output,
+        output.uuid,
+        move |score, message| score_sender.send(score, message),
+    )?;
+    Ok(())
+}
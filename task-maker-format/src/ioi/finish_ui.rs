@@ -6,7 +6,9 @@ use termcolor::{Color, ColorChoice, ColorSpec, StandardStream};
 use task_maker_dag::ExecutionStatus;
 
 use crate::ioi::ui_state::{SolutionEvaluationState, TestcaseEvaluationStatus, UIState};
-use crate::ioi::{SolutionTestcaseEvaluationState, TestcaseId};
+use crate::ioi::{
+    OutputOnlySubmission, SolutionTestcaseEvaluationState, SolutionTiming, TestcaseId,
+};
 use crate::ui::{
     FinishUI as FinishUITrait, FinishUIUtils, UIExecutionStatus, BLUE, BOLD, GREEN, RED, YELLOW,
 };
@@ -42,7 +44,9 @@ impl FinishUITrait<UIState> for FinishUI {
         println!();
         ui.print_evaluations(state);
         ui.print_summary(state);
+        ui.print_limit_suggestion(state);
         FinishUIUtils::new(&mut ui.stream).print_messages(&state.warnings);
+        FinishUIUtils::new(&mut ui.stream).print_errors(&state.errors);
     }
 }
 
@@ -111,6 +115,10 @@ impl FinishUI {
             println!(": {} points", state.task.subtasks[&st_num].max_score);
             for (tc_num, testcase) in subtask.testcases.iter().sorted_by_key(|(n, _)| *n) {
                 print!("#{:<3} ", tc_num);
+                if let Some(name) = state.task.testcase_name(*tc_num) {
+                    cwrite!(self, BOLD, "{}", name);
+                    print!(" ");
+                }
 
                 let mut first = true;
                 let mut gen_failed = false;
@@ -242,6 +250,10 @@ impl FinishUI {
         state: &UIState,
     ) {
         print!("{:3}) ", tc_num);
+        if let Some(testcase_name) = state.task.testcase_name(tc_num) {
+            cwrite!(self, BOLD, "{}", testcase_name);
+            print!(" ");
+        }
         let score = testcase.score.unwrap_or(0.0);
         if abs_diff_eq!(score, 1.0) {
             cwrite!(self, GREEN, "[{:.2}]", score);
@@ -250,6 +262,16 @@ impl FinishUI {
         } else {
             cwrite!(self, YELLOW, "[{:.2}]", score);
         }
+        // in output only tasks the resources are meaningless, print the output file instead
+        if state.task.is_output_only() {
+            print!(" [{}]", OutputOnlySubmission::output_file_name(tc_num));
+            print!(" {}", testcase.status.message());
+            if FinishUI::is_ansi() {
+                self.print_right(format!("[{}]", name));
+            }
+            println!();
+            return;
+        }
         // print the time and memory info
         for result in &testcase.results {
             if let Some(result) = result {
@@ -267,6 +289,12 @@ impl FinishUI {
                         * YELLOW_RESOURCE_THRESHOLD,
                 );
                 cwrite!(self, time_color, "{:2.3}s", result.resources.cpu_time);
+                if let Some(stats) = result.timing_stats() {
+                    print!(
+                        " (min {:.3}s, median {:.3}s, ±{:.3}s)",
+                        stats.min, stats.median, stats.spread
+                    );
+                }
                 print!(" | ");
                 cwrite!(
                     self,
@@ -356,6 +384,7 @@ impl FinishUI {
                         RuntimeError => cwrite!(self, RED, "R"),
                         Failed => cwrite!(self, BOLD, "F"),
                         Skipped => cwrite!(self, BOLD, "S"),
+                        MissingOutput => cwrite!(self, RED, "-"),
                         _ => cwrite!(self, BOLD, "X"),
                     }
                 }
@@ -366,6 +395,52 @@ impl FinishUI {
         println!();
     }
 
+    /// Print the limits suggested for the task, if any.
+    fn print_limit_suggestion(&mut self, state: &UIState) {
+        let suggestion = match &state.limit_suggestion {
+            Some(suggestion) => suggestion,
+            None => return,
+        };
+        let name = |timing: &SolutionTiming| {
+            timing
+                .solution
+                .file_name()
+                .map(|name| name.to_string_lossy().to_string())
+                .unwrap_or_default()
+        };
+        cwriteln!(self, BLUE, "Suggested limits");
+        cwrite!(self, BOLD, "Time limit:   ");
+        println!("{:.2}s", suggestion.time_limit);
+        print!(
+            "    slowest correct solution:  {} {:.3}s",
+            name(&suggestion.slowest_correct),
+            suggestion.slowest_correct.time
+        );
+        match suggestion.time_margin() {
+            Some(margin) => println!(" ({:.2}x below the limit)", margin),
+            None => println!(),
+        }
+        if let Some(too_slow) = &suggestion.fastest_too_slow {
+            let margin = suggestion.too_slow_margin().unwrap_or(0.0);
+            let color: &ColorSpec = if margin > 1.0 { &GREEN } else { &RED };
+            print!(
+                "    fastest too slow solution: {} {}{:.3}s ",
+                name(too_slow),
+                if too_slow.killed { "at least " } else { "" },
+                too_slow.time
+            );
+            cwriteln!(self, color, "({:.2}x above the limit)", margin);
+        }
+        cwrite!(self, BOLD, "Memory limit: ");
+        println!("{}MiB", suggestion.memory_limit);
+        print!("    correct solutions: {:.1}MiB", suggestion.correct_memory);
+        match suggestion.memory_margin() {
+            Some(margin) => println!(" ({:.2}x below the limit)", margin),
+            None => println!(),
+        }
+        println!();
+    }
+
     /// Print the score fraction of a solution using colors.
     fn print_score_frac(&mut self, score: f64, max_score: f64) {
         if max_score == 0.0 {
@@ -1,4 +1,6 @@
-use std::collections::HashMap;
+use std::cmp::Ordering;
+use std::collections::{HashMap, HashSet};
+use std::convert::TryFrom;
 use std::fmt::Debug;
 use std::path::{Path, PathBuf};
 use std::str::FromStr;
@@ -6,6 +8,7 @@ use std::sync::Arc;
 
 use failure::_core::fmt::Formatter;
 use failure::{bail, format_err, Error};
+use pest::prec_climber::{Assoc, Operator, PrecClimber};
 use pest::Parser;
 
 use crate::ioi::format::italian_yaml::TaskInputEntry;
@@ -19,6 +22,9 @@ use std::io::Read;
 /// String placed in the auto-generated gen/GEN marking it as safely deletable.
 pub(crate) const TM_ALLOW_DELETE_COOKIE: &str = "tm-allow-delete";
 
+/// The maximum number of iterations of a single `: FOR` loop.
+const MAX_LOOP_ITERATIONS: i64 = 10_000;
+
 /// This module exists because of a `pest`'s bug: https://github.com/pest-parser/pest/issues/326
 #[allow(missing_docs)]
 mod parser {
@@ -31,6 +37,20 @@ mod parser {
 /// Helper type for lightening the types.
 type Pair<'a> = pest::iterators::Pair<'a, parser::Rule>;
 
+lazy_static! {
+    /// The precedence climber for the arithmetic expressions: `*`, `/` and `%` have higher
+    /// precedence than `+` and `-`, all the operators are left-associative.
+    static ref PREC_CLIMBER: PrecClimber<parser::Rule> = {
+        use parser::Rule::*;
+        PrecClimber::new(vec![
+            Operator::new(add, Assoc::Left) | Operator::new(sub, Assoc::Left),
+            Operator::new(mul, Assoc::Left)
+                | Operator::new(div, Assoc::Left)
+                | Operator::new(rem, Assoc::Left),
+        ])
+    };
+}
+
 /// A manager is either a generator or a validator, since they have the same internal structure they
 /// are abstracted as a `Manager`.
 #[derive(Debug)]
@@ -42,15 +62,59 @@ struct Manager {
     args: Vec<String>,
 }
 
-/// Operand of a constraint. It is either a constant integer value or a symbolic variable to
-/// substitute.
-#[derive(Debug, PartialEq, Eq)]
-enum ConstraintOperand {
-    /// This operand is a constant integer value.
+/// An arithmetic expression between constants and symbolic variables. It is used as operand of the
+/// constraints and it is evaluated by `: SET` and by the `$(...)` substitutions.
+#[derive(Debug, PartialEq)]
+enum Expression {
+    /// This expression is a constant integer value.
     Constant(i64),
-    /// This operand is a symbolic variable. It is stored the variable name, without the dollar
+    /// This expression is a constant floating point value.
+    Float(f64),
+    /// This expression is a symbolic variable. It is stored the variable name, without the dollar
     /// sign.
     Variable(String),
+    /// The opposite of the inner expression.
+    Negation(Box<Expression>),
+    /// A binary operation between two expressions.
+    Operation(Box<Expression>, ArithmeticOperator, Box<Expression>),
+    /// The application of a function to the list of its arguments.
+    Function(ExpressionFunction, Vec<Expression>),
+}
+
+/// A binary arithmetic operator of an `Expression`.
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+enum ArithmeticOperator {
+    /// Operator `+`.
+    Add,
+    /// Operator `-`.
+    Sub,
+    /// Operator `*`.
+    Mul,
+    /// Operator `/`, between integers it's the integer division.
+    Div,
+    /// Operator `%`.
+    Rem,
+    /// Operator `^`.
+    Power,
+}
+
+/// A function that can be used inside an `Expression`.
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+enum ExpressionFunction {
+    /// `min(a, b, ...)`: the smallest of the arguments.
+    Min,
+    /// `max(a, b, ...)`: the largest of the arguments.
+    Max,
+}
+
+/// The value of an evaluated `Expression`. The operations between integers are done using integer
+/// arithmetic, as soon as a floating point value is involved the result is a floating point value.
+#[derive(Debug, Clone, Copy)]
+enum Value {
+    /// An integer value.
+    Integer(i64),
+    /// A floating point value.
+    Float(f64),
 }
 
 /// The operator of a constraint.
@@ -70,13 +134,14 @@ enum ConstraintOperator {
 
 /// A constraint between the variables. It is in the following format:
 ///     operand (operator operand)+
+/// Where each operand is an arithmetic expression.
 /// Note that the number of operands is one more than the operators.
 /// All the operators must be _in the same direction_: in the same constraint there cannot be both
 /// a _less_ operator and a _greater_ one.
 #[derive(Default)]
 struct Constraint {
     /// List of the operands of the constraint.
-    operands: Vec<ConstraintOperand>,
+    operands: Vec<Expression>,
     /// List of the operators of the contraint.
     operators: Vec<ConstraintOperator>,
 }
@@ -87,10 +152,16 @@ struct Constraint {
 #[derivative(Debug)]
 pub(crate) struct CasesGen<O>
 where
-    O: Fn(TestcaseId) -> OutputGenerator,
+    O: Fn(TestcaseId, Option<&str>) -> OutputGenerator,
 {
     /// The base directory of the task.
     task_dir: PathBuf,
+    /// The path, relative to the task directory, of the file being parsed. Used for pointing at
+    /// the lines in the error messages.
+    current_file: PathBuf,
+    /// The canonical paths of the files being processed: the `cases.gen` file and the chain of
+    /// `: INCLUDE`s leading to the current file. Used for detecting the include cycles.
+    include_stack: Vec<PathBuf>,
     /// The function to call for getting the `OutputGenerator` for a given testcase.
     #[derivative(Debug = "ignore")]
     get_output_gen: O,
@@ -101,6 +172,8 @@ where
     constraints: Vec<Constraint>,
     /// The list of additional constraints for the current subtask.
     subtask_constraints: Vec<Vec<Constraint>>,
+    /// The testcases of each subtask, including the ones included from other subtasks.
+    subtask_testcases: Vec<Vec<TestcaseInfo>>,
     /// The list of all the generators found, indexed by generator name.
     generators: HashMap<String, Manager>,
     /// The list of all the validators found, indexed by validator name.
@@ -121,11 +194,19 @@ where
     subtask_description: Option<String>,
     /// The identifier of the next testcase to process.
     testcase_id: TestcaseId,
+    /// The current values of the variables of the `: FOR` loops being processed.
+    loop_variables: HashMap<String, String>,
+    /// The values of the variables defined with `: SET`.
+    set_variables: HashMap<String, String>,
+    /// The name set by `: TESTCASE` for the next testcase.
+    testcase_name: Option<String>,
+    /// The weight set by `: WEIGHT` for the next testcase.
+    testcase_weight: Option<f64>,
 }
 
 impl<O> CasesGen<O>
 where
-    O: Fn(TestcaseId) -> OutputGenerator,
+    O: Fn(TestcaseId, Option<&str>) -> OutputGenerator,
 {
     /// Parse the `cases.gen` file pointed at the specified path.
     pub(crate) fn new<P: AsRef<Path>>(path: P, output_gen: O) -> Result<CasesGen<O>, Error> {
@@ -135,16 +216,16 @@ where
             .expect("Invalid gen/cases.gen path")
             .parent()
             .expect("Invalid gen/cases.gen path");
-        let content = std::fs::read_to_string(&path)?;
-        let mut file = parser::CasesGenParser::parse(parser::Rule::file, &content)?;
-        let file = file.next().ok_or_else(|| format_err!("Corrupted parser"))?; // extract the real file
 
         let mut cases = CasesGen {
             task_dir: task_dir.into(),
+            current_file: PathBuf::new(),
+            include_stack: vec![],
             get_output_gen: output_gen,
             result: vec![],
             constraints: vec![],
             subtask_constraints: vec![],
+            subtask_testcases: vec![],
             generators: Default::default(),
             validators: Default::default(),
             default_generator: None,
@@ -154,36 +235,119 @@ where
             subtask_id: 0,
             subtask_description: None,
             testcase_id: 0,
+            loop_variables: HashMap::new(),
+            set_variables: HashMap::new(),
+            testcase_name: None,
+            testcase_weight: None,
         };
+        cases.process_file(path.as_ref())?;
+        cases.check_no_pending_testcase()?;
+        Ok(cases)
+    }
+
+    /// Parse and process the file at the specified path, the files it includes are processed
+    /// recursively. The errors are located in the file being processed.
+    fn process_file(&mut self, path: &Path) -> Result<(), Error> {
+        let relative_path = self.relative_path(path);
+        let content = std::fs::read_to_string(path)
+            .map_err(|e| format_err!("Cannot read {}: {}", relative_path.display(), e))?;
+        let mut file = parser::CasesGenParser::parse(parser::Rule::file, &content)
+            .map_err(|e| format_err!("Cannot parse {}: {}", relative_path.display(), e))?;
+        let file = file.next().ok_or_else(|| format_err!("Corrupted parser"))?; // extract the real file
+        let lines: Vec<_> = file
+            .into_inner()
+            .filter(|line| line.as_rule() == parser::Rule::line)
+            .collect();
 
-        for line in file.into_inner() {
+        let previous_file = std::mem::replace(&mut self.current_file, relative_path);
+        self.include_stack.push(path.canonicalize()?);
+        let result = self.process_lines(&lines);
+        self.include_stack.pop();
+        self.current_file = previous_file;
+        result
+    }
+
+    /// Process the lines of the file in order, repeating the body of the `: FOR` loops. The errors
+    /// are prefixed with the position of the line that caused them.
+    fn process_lines(&mut self, lines: &[Pair]) -> Result<(), Error> {
+        let mut index = 0;
+        while index < lines.len() {
+            let line = lines[index]
+                .clone()
+                .into_inner()
+                .next()
+                .ok_or_else(|| format_err!("Corrupted parser"))?;
             match line.as_rule() {
-                parser::Rule::line => {
-                    let line = line
+                parser::Rule::command => {
+                    let command = line
                         .into_inner()
                         .next()
                         .ok_or_else(|| format_err!("Corrupted parser"))?;
-                    match line.as_rule() {
-                        parser::Rule::command => {
-                            let command = line
-                                .into_inner()
-                                .next()
-                                .ok_or_else(|| format_err!("Corrupted parser"))?;
-                            cases.parse_command(command)?;
+                    match command.as_rule() {
+                        parser::Rule::FOR => {
+                            // the errors of the body are already located by the inner call
+                            let end = find_loop_end(lines, index)
+                                .map_err(|e| self.locate_error(&lines[index], e))?;
+                            let (variable, from, to) = self
+                                .parse_for(command)
+                                .map_err(|e| self.locate_error(&lines[index], e))?;
+                            self.process_loop(variable, from, to, &lines[index + 1..end])?;
+                            index = end;
+                        }
+                        parser::Rule::INCLUDE => {
+                            // the errors of the included file are located by the inner call
+                            let path = self
+                                .parse_include(command)
+                                .map_err(|e| self.locate_error(&lines[index], e))?;
+                            self.process_file(&path)?;
                         }
-                        parser::Rule::testcase => {
-                            cases.parse_testcase(line.as_str(), cases.current_generator.clone())?;
+                        parser::Rule::END => {
+                            return Err(self.locate_error(
+                                &lines[index],
+                                format_err!("Found : END outside a loop"),
+                            ))
                         }
-                        parser::Rule::comment => {}
-                        parser::Rule::empty => {}
-                        _ => unreachable!(),
+                        // the definitions would be repeated at each iteration
+                        parser::Rule::GEN
+                        | parser::Rule::VAL
+                        | parser::Rule::CONSTRAINT
+                        | parser::Rule::SUBTASK
+                            if !self.loop_variables.is_empty() =>
+                        {
+                            return Err(self.locate_error(
+                                &lines[index],
+                                format_err!("Cannot use : {:?} inside a loop", command.as_rule()),
+                            ))
+                        }
+                        _ => self
+                            .parse_command(command)
+                            .map_err(|e| self.locate_error(&lines[index], e))?,
                     }
                 }
-                parser::Rule::EOI => {}
+                parser::Rule::testcase => {
+                    self.parse_testcase(line.as_str(), self.current_generator.clone())
+                        .map_err(|e| self.locate_error(&lines[index], e))?;
+                }
+                parser::Rule::comment => {}
+                parser::Rule::empty => {}
                 _ => unreachable!(),
             }
+            index += 1;
         }
-        Ok(cases)
+        Ok(())
+    }
+
+    /// Return the path relative to the task directory, or the path itself if it's outside of it.
+    fn relative_path(&self, path: &Path) -> PathBuf {
+        path.strip_prefix(&self.task_dir)
+            .unwrap_or(path)
+            .to_path_buf()
+    }
+
+    /// Prefix the error with the file and the line number of the line that caused it.
+    fn locate_error(&self, line: &Pair, error: Error) -> Error {
+        let (line_number, _) = line.as_span().start_pos().line_col();
+        format_err!("{}:{}: {}", self.current_file.display(), line_number, error)
     }
 
     /// Return the list of `TaskInputEntry` from the `cases.gen` file.
@@ -210,6 +374,7 @@ where
                 gen += &format!("# {:?}\n", constr);
             }
         }
+        let mut written = HashSet::new();
         for entry in &self.result {
             match entry {
                 TaskInputEntry::Subtask(subtask) => {
@@ -226,7 +391,16 @@ where
                     }
                 }
                 TaskInputEntry::Testcase(testcase) => {
-                    gen += &format!("# Testcase {}\n", testcase.id);
+                    // gen/GEN cannot include the testcases of other subtasks, the included
+                    // testcases are generated again in this subtask with the same command
+                    if written.insert(testcase.id) {
+                        gen += &format!("# Testcase {}\n", testcase.id);
+                        if let Some(name) = &testcase.name {
+                            gen += &format!("#TESTCASE: {}\n", name);
+                        }
+                    } else {
+                        gen += &format!("# Testcase {} (included)\n", testcase.id);
+                    }
                     match &testcase.input_generator {
                         InputGenerator::StaticFile(path) => {
                             if let Ok(path) = path.strip_prefix(&self.task_dir) {
@@ -267,6 +441,21 @@ where
             parser::Rule::RUN => {
                 self.parse_run(line)?;
             }
+            parser::Rule::SET => {
+                self.parse_set(line)?;
+            }
+            parser::Rule::TESTCASE => {
+                self.parse_testcase_name(line)?;
+            }
+            parser::Rule::WEIGHT => {
+                self.parse_weight(line)?;
+            }
+            parser::Rule::TIME_LIMIT => {
+                self.parse_time_limit(line)?;
+            }
+            parser::Rule::MEMORY_LIMIT => {
+                self.parse_memory_limit(line)?;
+            }
             _ => unreachable!(),
         }
         Ok(())
@@ -281,6 +470,7 @@ where
         if self.subtask_id == 0 {
             bail!("Cannot add a testcase outside a subtask");
         }
+        let line = &self.substitute_variables(line)?;
         let current_generator = if let Some(gen) = current_generator {
             gen
         } else {
@@ -298,7 +488,15 @@ where
             .iter()
             .chain(self.subtask_constraints.last().unwrap())
         {
-            if let Some(false) = constr.is_valid(&variables) {
+            let valid = constr.is_valid(&variables).map_err(|e| {
+                format_err!(
+                    "Cannot check constraint {:?} for testcase '{}': {}",
+                    constr,
+                    line,
+                    e
+                )
+            })?;
+            if let Some(false) = valid {
                 let mut error = format!(
                     "Testcase '{}' violates constraint {:?}\nWith:",
                     line, constr
@@ -310,16 +508,33 @@ where
             }
         }
         let generator = InputGenerator::Custom(generator.source.clone(), args);
-        self.result.push(TaskInputEntry::Testcase(TestcaseInfo {
+        self.add_testcase(TestcaseInfo {
             id: self.testcase_id,
+            name: None,
+            weight: 1.0,
             input_generator: generator,
             input_validator: self.get_validator(&variables)?,
-            output_generator: (self.get_output_gen)(self.testcase_id),
-        }));
-        self.testcase_id += 1;
+            output_generator: (self.get_output_gen)(
+                self.testcase_id,
+                self.testcase_name.as_deref(),
+            ),
+        });
         Ok(())
     }
 
+    /// Add a new testcase to the current subtask, and increment the testcase counter. The testcase
+    /// takes the name and the weight set by the previous `: TESTCASE` and `: WEIGHT`, if any.
+    fn add_testcase(&mut self, mut testcase: TestcaseInfo) {
+        testcase.name = self.testcase_name.take();
+        testcase.weight = self.testcase_weight.take().unwrap_or(1.0);
+        self.subtask_testcases
+            .last_mut()
+            .expect("Testcase outside a subtask")
+            .push(testcase.clone());
+        self.result.push(TaskInputEntry::Testcase(testcase));
+        self.testcase_id += 1;
+    }
+
     /// Parse a `GEN` or a `VAL`, since they have the same internal format their parsing function is
     /// abstracted in this.
     fn process_gen_val(
@@ -429,22 +644,12 @@ where
         let mut direction = None;
         for item in line {
             match item.as_rule() {
-                parser::Rule::number => {
-                    constraint.operands.push(ConstraintOperand::Constant(
-                        i64::from_str(item.as_str()).map_err(|e| {
-                            format_err!(
-                                "Invalid integer constant '{}' in constraint '{}': {}",
-                                item.as_str(),
-                                line_str,
-                                e
-                            )
-                        })?,
-                    ));
-                }
-                parser::Rule::variable => {
+                parser::Rule::expr => {
                     constraint
                         .operands
-                        .push(ConstraintOperand::Variable(item.as_str()[1..].to_string()));
+                        .push(build_expression(item).map_err(|e| {
+                            format_err!("Invalid constraint '{}': {}", line_str, e)
+                        })?);
                 }
                 parser::Rule::comp_operator => {
                     let operator = ConstraintOperator::from_str(item.as_str())?;
@@ -486,6 +691,7 @@ where
 
     /// Parse a `:SUBTASK` command.
     fn parse_subtask(&mut self, line: Pair) -> Result<(), Error> {
+        self.check_no_pending_testcase()?;
         let line: Vec<_> = line.into_inner().collect();
         self.current_generator = self.default_generator.clone();
         self.current_validator = self.default_validator.clone();
@@ -497,18 +703,53 @@ where
                 e
             )
         })?;
-        let description = if line.len() >= 2 {
-            Some(line[1].as_str().to_string())
-        } else {
-            None
-        };
+        let mut includes = Vec::new();
+        let mut description = None;
+        for item in &line[1..] {
+            match item.as_rule() {
+                parser::Rule::subtask_includes => {
+                    for id in item.clone().into_inner() {
+                        let id = SubtaskId::from_str(id.as_str()).map_err(|e| {
+                            format_err!("Invalid subtask id '{}': {}", id.as_str(), e)
+                        })?;
+                        if id >= self.subtask_id {
+                            bail!(
+                                "Subtask {} cannot include subtask {}: only the previous subtasks \
+                                 can be included",
+                                self.subtask_id,
+                                id
+                            );
+                        }
+                        includes.push(id);
+                    }
+                }
+                parser::Rule::rest => description = Some(item.as_str().to_string()),
+                _ => unreachable!(),
+            }
+        }
         self.subtask_description = description.clone();
         self.result.push(TaskInputEntry::Subtask(SubtaskInfo {
             id: self.subtask_id,
             description,
             max_score: score,
+            time_limit: None,
+            memory_limit: None,
             testcases: HashMap::new(),
         }));
+        // the testcases of the included subtasks are added to this subtask without generating them
+        // again
+        let mut testcases: Vec<TestcaseInfo> = Vec::new();
+        for id in includes {
+            for testcase in &self.subtask_testcases[id as usize] {
+                if testcases.iter().all(|tc| tc.id != testcase.id) {
+                    testcases.push(testcase.clone());
+                }
+            }
+        }
+        for testcase in &testcases {
+            self.result.push(TaskInputEntry::Testcase(testcase.clone()));
+        }
+        self.subtask_testcases.push(testcases);
         self.subtask_id += 1;
         Ok(())
     }
@@ -519,23 +760,273 @@ where
             bail!("Cannot add a COPY testcase outside a subtask");
         }
         let path = line.into_inner().next().expect("corrupted parser").as_str();
-        let path = self.task_dir.join(path);
+        let path = self.task_dir.join(self.substitute_variables(path)?);
         if !path.exists() {
             bail!(
                 "Cannot copy testcase from '{}': file not found",
                 path.display()
             );
         }
-        self.result.push(TaskInputEntry::Testcase(TestcaseInfo {
+        self.add_testcase(TestcaseInfo {
             id: self.testcase_id,
+            name: None,
+            weight: 1.0,
             input_generator: InputGenerator::StaticFile(path),
             input_validator: self.get_validator(&self.get_auto_variables())?,
-            output_generator: (self.get_output_gen)(self.testcase_id),
-        }));
-        self.testcase_id += 1;
+            output_generator: (self.get_output_gen)(
+                self.testcase_id,
+                self.testcase_name.as_deref(),
+            ),
+        });
+        Ok(())
+    }
+
+    /// Parse a `:TESTCASE` command, setting the name of the next testcase.
+    fn parse_testcase_name(&mut self, line: Pair) -> Result<(), Error> {
+        let name = line.into_inner().next().expect("corrupted parser").as_str();
+        let name = self.substitute_variables(name)?;
+        if let Some(previous) = &self.testcase_name {
+            bail!(
+                "Cannot name the next testcase '{}': it is already named '{}'",
+                name,
+                previous
+            );
+        }
+        self.testcase_name = Some(name);
+        Ok(())
+    }
+
+    /// Parse a `:WEIGHT` command, setting the weight of the next testcase.
+    fn parse_weight(&mut self, line: Pair) -> Result<(), Error> {
+        let expr = line.into_inner().next().expect("corrupted parser").as_str();
+        let weight = evaluate_expression(expr, &self.user_variables())?.as_f64();
+        if weight <= 0.0 {
+            bail!("Invalid testcase weight {}: it must be positive", weight);
+        }
+        if let Some(previous) = self.testcase_weight {
+            bail!(
+                "Cannot set the weight of the next testcase to {}: it is already {}",
+                weight,
+                previous
+            );
+        }
+        self.testcase_weight = Some(weight);
+        Ok(())
+    }
+
+    /// Parse a `:TIME_LIMIT` command, overriding the time limit of the task in the current subtask.
+    fn parse_time_limit(&mut self, line: Pair) -> Result<(), Error> {
+        let expr = line.into_inner().next().expect("corrupted parser").as_str();
+        let time_limit = evaluate_expression(expr, &self.user_variables())?.as_f64();
+        if time_limit <= 0.0 {
+            bail!("Invalid time limit {}: it must be positive", time_limit);
+        }
+        let subtask = self.current_subtask("time limit")?;
+        subtask.time_limit = Some(time_limit);
+        Ok(())
+    }
+
+    /// Parse a `:MEMORY_LIMIT` command, overriding the memory limit of the task in the current
+    /// subtask.
+    fn parse_memory_limit(&mut self, line: Pair) -> Result<(), Error> {
+        let expr = line.into_inner().next().expect("corrupted parser").as_str();
+        let memory_limit = match evaluate_expression(expr, &self.user_variables())? {
+            Value::Integer(value) if value > 0 => value as u64,
+            value => bail!(
+                "Invalid memory limit {}: it must be a positive integer",
+                value
+            ),
+        };
+        let subtask = self.current_subtask("memory limit")?;
+        subtask.memory_limit = Some(memory_limit);
+        Ok(())
+    }
+
+    /// The subtask being defined, for changing its properties. `what` is the name of the property,
+    /// used in the error message when there is no subtask.
+    fn current_subtask(&mut self, what: &str) -> Result<&mut SubtaskInfo, Error> {
+        self.result
+            .iter_mut()
+            .rev()
+            .find_map(|entry| match entry {
+                TaskInputEntry::Subtask(subtask) => Some(subtask),
+                _ => None,
+            })
+            .ok_or_else(|| format_err!("Cannot set the {} outside a subtask", what))
+    }
+
+    /// Check that the name and the weight of the next testcase are not set, since there are no more
+    /// testcases to apply them to.
+    fn check_no_pending_testcase(&self) -> Result<(), Error> {
+        if let Some(name) = &self.testcase_name {
+            bail!("Testcase name '{}' is not followed by a testcase", name);
+        }
+        if let Some(weight) = self.testcase_weight {
+            bail!("Testcase weight {} is not followed by a testcase", weight);
+        }
+        Ok(())
+    }
+
+    /// Parse a `:FOR` command, returning the name of the loop variable and the bounds of the range.
+    fn parse_for(&self, line: Pair) -> Result<(String, i64, i64), Error> {
+        let line_str = line.as_str().trim().to_string();
+        let line: Vec<_> = line.into_inner().collect();
+        let variable = line[0].as_str().to_string();
+        let parse_bound = |item: &Pair| {
+            i64::from_str(item.as_str()).map_err(|e| {
+                format_err!(
+                    "Invalid integer '{}' in loop '{}': {}",
+                    item.as_str(),
+                    line_str,
+                    e
+                )
+            })
+        };
+        let from = parse_bound(&line[1])?;
+        let to = parse_bound(&line[2])?;
+        if from > to {
+            bail!(
+                "Invalid range in loop '{}': {} is greater than {}",
+                line_str,
+                from,
+                to
+            );
+        }
+        if to
+            .checked_sub(from)
+            .map_or(true, |n| n >= MAX_LOOP_ITERATIONS)
+        {
+            bail!(
+                "Too many iterations in loop '{}': at most {} are allowed",
+                line_str,
+                MAX_LOOP_ITERATIONS
+            );
+        }
+        if self.loop_variables.contains_key(&variable) || self.set_variables.contains_key(&variable)
+        {
+            bail!("Loop variable ${} is already defined", variable);
+        }
+        Ok((variable, from, to))
+    }
+
+    /// Process the lines in the body of a loop once for each value in the range.
+    fn process_loop(
+        &mut self,
+        variable: String,
+        from: i64,
+        to: i64,
+        body: &[Pair],
+    ) -> Result<(), Error> {
+        for value in from..=to {
+            self.loop_variables
+                .insert(variable.clone(), value.to_string());
+            self.process_lines(body)?;
+        }
+        self.loop_variables.remove(&variable);
+        Ok(())
+    }
+
+    /// Parse a `:INCLUDE` command, returning the path of the file to include. The path is
+    /// relative to the directory of the file containing the command.
+    fn parse_include(&self, line: Pair) -> Result<PathBuf, Error> {
+        let path = line
+            .into_inner()
+            .next()
+            .expect("corrupted parser")
+            .as_str()
+            .trim();
+        let path = self
+            .task_dir
+            .join(&self.current_file)
+            .parent()
+            .expect("Invalid file path")
+            .join(path);
+        if !path.is_file() {
+            bail!("Cannot include '{}': file not found", path.display());
+        }
+        if self.include_stack.contains(&path.canonicalize()?) {
+            bail!(
+                "Cannot include '{}': include cycle detected",
+                self.relative_path(&path).display()
+            );
+        }
+        Ok(path)
+    }
+
+    /// Parse a `:SET` command, evaluating the expression and storing its value.
+    fn parse_set(&mut self, line: Pair) -> Result<(), Error> {
+        let line: Vec<_> = line.into_inner().collect();
+        let name = line[0].as_str();
+        if self.loop_variables.contains_key(name) {
+            bail!("Cannot set ${}: it is a loop variable", name);
+        }
+        let value = evaluate_expression(line[1].as_str(), &self.user_variables())?;
+        self.set_variables
+            .insert(name.to_string(), value.to_string());
         Ok(())
     }
 
+    /// Replace the variables (`$name`) in the line with their current value and the expressions
+    /// (`$(expr)`) with the result of their evaluation. Only the variables defined with `: SET`
+    /// and `: FOR` are replaced, the unknown ones are left untouched.
+    fn substitute_variables(&self, line: &str) -> Result<String, Error> {
+        let mut result = String::with_capacity(line.len());
+        let mut rest = line;
+        while let Some(pos) = rest.find('$') {
+            result += &rest[..pos];
+            rest = &rest[pos + 1..];
+            if rest.starts_with('(') {
+                let mut depth = 0;
+                let end = rest
+                    .char_indices()
+                    .find(|(_, c)| {
+                        match c {
+                            '(' => depth += 1,
+                            ')' => depth -= 1,
+                            _ => {}
+                        }
+                        depth == 0
+                    })
+                    .map(|(index, _)| index)
+                    .ok_or_else(|| format_err!("Unbalanced parenthesis in '{}'", line))?;
+                let value = evaluate_expression(&rest[1..end], &self.user_variables())?;
+                result += &value.to_string();
+                rest = &rest[end + 1..];
+            } else {
+                let name_len = rest
+                    .find(|c: char| !(c.is_ascii_alphanumeric() || c == '_'))
+                    .unwrap_or_else(|| rest.len());
+                let name = &rest[..name_len];
+                match self.user_variable(name) {
+                    Some(value) => result += value,
+                    None => {
+                        result += "$";
+                        result += name;
+                    }
+                }
+                rest = &rest[name_len..];
+            }
+        }
+        result += rest;
+        Ok(result)
+    }
+
+    /// Get the value of a variable defined with `: SET` or by an enclosing `: FOR`.
+    fn user_variable(&self, name: &str) -> Option<&String> {
+        self.loop_variables
+            .get(name)
+            .or_else(|| self.set_variables.get(name))
+    }
+
+    /// Get all the variables defined with `: SET` or by the enclosing `: FOR`s.
+    fn user_variables(&self) -> HashMap<String, String> {
+        self.set_variables
+            .iter()
+            .chain(&self.loop_variables)
+            .map(|(name, value)| (name.clone(), value.clone()))
+            .collect()
+    }
+
     /// Get the current validator for the next testcase.
     fn get_validator(&self, variables: &HashMap<String, String>) -> Result<InputValidator, Error> {
         match &self.current_validator {
@@ -601,10 +1092,157 @@ where
         if let Some(descr) = &self.subtask_description {
             vars.insert("ST_DESCRIPTION".to_string(), descr.clone());
         }
+        vars.extend(self.user_variables());
         vars
     }
 }
 
+/// Evaluate an arithmetic expression, the variables are taken from `vars` and must be numbers.
+fn evaluate_expression(expr: &str, vars: &HashMap<String, String>) -> Result<Value, Error> {
+    let mut parsed = parser::CasesGenParser::parse(parser::Rule::expression, expr)
+        .map_err(|e| format_err!("Invalid expression '{}': {}", expr, e))?;
+    let expression = parsed
+        .next()
+        .ok_or_else(|| format_err!("Corrupted parser"))?;
+    let expr_pair = expression
+        .into_inner()
+        .next()
+        .ok_or_else(|| format_err!("Corrupted parser"))?;
+    let expression = build_expression(expr_pair)
+        .map_err(|e| format_err!("Invalid expression '{}': {}", expr, e))?;
+    expression
+        .evaluate(vars)
+        .map_err(|e| format_err!("Cannot evaluate '{}': {}", expr, e))
+}
+
+/// Build an `Expression` from a parsed `expr`, applying the operators with the correct precedence.
+fn build_expression(expr: Pair) -> Result<Expression, Error> {
+    PREC_CLIMBER.climb(expr.into_inner(), build_term, |lhs, operator, rhs| {
+        let operator = match operator.as_rule() {
+            parser::Rule::add => ArithmeticOperator::Add,
+            parser::Rule::sub => ArithmeticOperator::Sub,
+            parser::Rule::mul => ArithmeticOperator::Mul,
+            parser::Rule::div => ArithmeticOperator::Div,
+            parser::Rule::rem => ArithmeticOperator::Rem,
+            _ => unreachable!(),
+        };
+        Ok(Expression::Operation(
+            Box::new(lhs?),
+            operator,
+            Box::new(rhs?),
+        ))
+    })
+}
+
+/// Build an `Expression` from a parsed `expr_term`: a power, optionally negated.
+fn build_term(term: Pair) -> Result<Expression, Error> {
+    let mut negations = 0;
+    let mut result = None;
+    for item in term.into_inner() {
+        match item.as_rule() {
+            parser::Rule::expr_negation => negations += 1,
+            parser::Rule::expr_power => result = Some(build_power(item)?),
+            _ => unreachable!(),
+        }
+    }
+    let mut result = result.ok_or_else(|| format_err!("Corrupted parser"))?;
+    for _ in 0..negations {
+        result = match result {
+            Expression::Constant(k) if k.checked_neg().is_some() => Expression::Constant(-k),
+            Expression::Float(k) => Expression::Float(-k),
+            result => Expression::Negation(Box::new(result)),
+        };
+    }
+    Ok(result)
+}
+
+/// Build an `Expression` from a parsed `expr_power`: an atom, optionally raised to the power of a
+/// term. The power operator is right-associative.
+fn build_power(power: Pair) -> Result<Expression, Error> {
+    let mut items = power.into_inner();
+    let base = build_atom(
+        items
+            .next()
+            .ok_or_else(|| format_err!("Corrupted parser"))?,
+    )?;
+    match items.next() {
+        Some(exponent) => Ok(Expression::Operation(
+            Box::new(base),
+            ArithmeticOperator::Power,
+            Box::new(build_term(exponent)?),
+        )),
+        None => Ok(base),
+    }
+}
+
+/// Build an `Expression` from a parsed atom: a number, a function call, a variable or a
+/// parenthesized expression.
+fn build_atom(atom: Pair) -> Result<Expression, Error> {
+    match atom.as_rule() {
+        parser::Rule::expr_number => {
+            let number = atom.as_str();
+            if number.contains('.') {
+                Ok(Expression::Float(f64::from_str(number).map_err(|e| {
+                    format_err!("Invalid floating point constant '{}': {}", number, e)
+                })?))
+            } else {
+                Ok(Expression::Constant(i64::from_str(number).map_err(
+                    |e| format_err!("Invalid integer constant '{}': {}", number, e),
+                )?))
+            }
+        }
+        parser::Rule::expr_function => {
+            let mut items = atom.into_inner();
+            let name = items
+                .next()
+                .ok_or_else(|| format_err!("Corrupted parser"))?
+                .as_str();
+            let function = match name {
+                "min" => ExpressionFunction::Min,
+                "max" => ExpressionFunction::Max,
+                _ => bail!("Unknown function '{}'", name),
+            };
+            let args = items.map(build_expression).collect::<Result<_, _>>()?;
+            Ok(Expression::Function(function, args))
+        }
+        parser::Rule::expr_variable => Ok(Expression::Variable(
+            atom.as_str().trim_start_matches('$').to_string(),
+        )),
+        parser::Rule::expr => build_expression(atom),
+        _ => unreachable!(),
+    }
+}
+
+/// Return the rule of the command in the line, or `None` if the line is not a command.
+fn command_rule(line: &Pair) -> Option<parser::Rule> {
+    let line = line.clone().into_inner().next()?;
+    if line.as_rule() != parser::Rule::command {
+        return None;
+    }
+    line.into_inner().next().map(|command| command.as_rule())
+}
+
+/// Find the index of the line with the `: END` matching the `: FOR` at index `start`.
+fn find_loop_end(lines: &[Pair], start: usize) -> Result<usize, Error> {
+    let mut depth = 0;
+    for (index, line) in lines.iter().enumerate().skip(start) {
+        match command_rule(line) {
+            Some(parser::Rule::FOR) => depth += 1,
+            Some(parser::Rule::END) => {
+                depth -= 1;
+                if depth == 0 {
+                    return Ok(index);
+                }
+            }
+            _ => {}
+        }
+    }
+    bail!(
+        "Missing : END for the loop '{}'",
+        lines[start].as_str().trim()
+    )
+}
+
 /// Check if the gen/GEN file is deletable, i.e. it exists and it is autogenerated.
 pub(crate) fn is_gen_gen_deletable(path: &Path) -> Result<bool, Error> {
     if !path.exists() {
@@ -623,7 +1261,7 @@ pub(crate) fn is_gen_gen_deletable(path: &Path) -> Result<bool, Error> {
 
 impl ConstraintOperator {
     /// Apply the operator to the provided values and return the result of the comparison.
-    fn is_valid(&self, lhs: i64, rhs: i64) -> bool {
+    fn is_valid(&self, lhs: Value, rhs: Value) -> bool {
         match self {
             ConstraintOperator::Less => lhs < rhs,
             ConstraintOperator::LessEqual => lhs <= rhs,
@@ -662,33 +1300,194 @@ impl ToString for ConstraintOperator {
     }
 }
 
-impl ConstraintOperand {
-    /// Return the value of this operand which is either the constant or the integer value of the
-    /// variable contained in it. If the variable is not present, or it's not a valid integer `None`
-    /// is returned.
-    fn get_val(&self, vars: &HashMap<String, String>) -> Option<i64> {
+impl Expression {
+    /// Check if all the variables of this expression are present and are valid numbers.
+    fn can_evaluate(&self, vars: &HashMap<String, String>) -> bool {
         match self {
-            ConstraintOperand::Constant(k) => Some(*k),
-            ConstraintOperand::Variable(var) => {
-                if let Some(val) = vars.get(var) {
-                    if let Ok(val) = i64::from_str(val) {
-                        Some(val)
-                    } else {
-                        None
-                    }
-                } else {
-                    None
+            Expression::Constant(_) | Expression::Float(_) => true,
+            Expression::Variable(var) => vars
+                .get(var)
+                .map(|val| Value::from_str(val).is_ok())
+                .unwrap_or(false),
+            Expression::Negation(expr) => expr.can_evaluate(vars),
+            Expression::Operation(lhs, _, rhs) => lhs.can_evaluate(vars) && rhs.can_evaluate(vars),
+            Expression::Function(_, args) => args.iter().all(|arg| arg.can_evaluate(vars)),
+        }
+    }
+
+    /// Compute the value of this expression, taking the values of the variables from `vars`.
+    fn evaluate(&self, vars: &HashMap<String, String>) -> Result<Value, Error> {
+        match self {
+            Expression::Constant(k) => Ok(Value::Integer(*k)),
+            Expression::Float(k) => Ok(Value::Float(*k)),
+            Expression::Variable(var) => {
+                let val = vars
+                    .get(var)
+                    .ok_or_else(|| format_err!("unknown variable ${}", var))?;
+                Value::from_str(val)
+                    .map_err(|_| format_err!("variable ${} is not a number: '{}'", var, val))
+            }
+            Expression::Negation(expr) => expr.evaluate(vars)?.negate(),
+            Expression::Operation(lhs, operator, rhs) => {
+                lhs.evaluate(vars)?.apply(*operator, rhs.evaluate(vars)?)
+            }
+            Expression::Function(function, args) => {
+                let mut result: Option<Value> = None;
+                for arg in args {
+                    let arg = arg.evaluate(vars)?;
+                    result = match (result, function) {
+                        (Some(res), ExpressionFunction::Min) if res <= arg => Some(res),
+                        (Some(res), ExpressionFunction::Max) if res >= arg => Some(res),
+                        _ => Some(arg),
+                    };
                 }
+                result.ok_or_else(|| format_err!("function without arguments"))
             }
         }
     }
 }
 
-impl ToString for ConstraintOperand {
-    fn to_string(&self) -> String {
+impl std::fmt::Display for Expression {
+    fn fmt(&self, f: &mut Formatter<'_>) -> Result<(), std::fmt::Error> {
+        // the nested operations are wrapped in parenthesis to keep the precedence explicit
+        let wrap = |expr: &Expression| match expr {
+            Expression::Operation(..) => format!("({})", expr),
+            _ => expr.to_string(),
+        };
+        match self {
+            Expression::Constant(k) => write!(f, "{}", k),
+            Expression::Float(k) => write!(f, "{:?}", k),
+            Expression::Variable(v) => write!(f, "${}", v),
+            Expression::Negation(expr) => write!(f, "-{}", wrap(expr)),
+            Expression::Operation(lhs, operator, rhs) => {
+                write!(f, "{} {} {}", wrap(lhs), operator, wrap(rhs))
+            }
+            Expression::Function(function, args) => {
+                let name = match function {
+                    ExpressionFunction::Min => "min",
+                    ExpressionFunction::Max => "max",
+                };
+                let args: Vec<_> = args.iter().map(|arg| arg.to_string()).collect();
+                write!(f, "{}({})", name, args.join(", "))
+            }
+        }
+    }
+}
+
+impl std::fmt::Display for ArithmeticOperator {
+    fn fmt(&self, f: &mut Formatter<'_>) -> Result<(), std::fmt::Error> {
+        let operator = match self {
+            ArithmeticOperator::Add => "+",
+            ArithmeticOperator::Sub => "-",
+            ArithmeticOperator::Mul => "*",
+            ArithmeticOperator::Div => "/",
+            ArithmeticOperator::Rem => "%",
+            ArithmeticOperator::Power => "^",
+        };
+        write!(f, "{}", operator)
+    }
+}
+
+impl Value {
+    /// The value converted to a floating point number.
+    fn as_f64(self) -> f64 {
+        match self {
+            Value::Integer(k) => k as f64,
+            Value::Float(k) => k,
+        }
+    }
+
+    /// Compute the opposite of this value.
+    fn negate(self) -> Result<Value, Error> {
+        match self {
+            Value::Integer(k) => k
+                .checked_neg()
+                .map(Value::Integer)
+                .ok_or_else(|| format_err!("integer overflow")),
+            Value::Float(k) => Ok(Value::Float(-k)),
+        }
+    }
+
+    /// Apply the operator between this value and `rhs`. If both the values are integers the
+    /// integer arithmetic is used, unless the result of a power is not an integer.
+    fn apply(self, operator: ArithmeticOperator, rhs: Value) -> Result<Value, Error> {
+        if let (Value::Integer(lhs), Value::Integer(rhs)) = (self, rhs) {
+            let result = match operator {
+                ArithmeticOperator::Add => lhs.checked_add(rhs),
+                ArithmeticOperator::Sub => lhs.checked_sub(rhs),
+                ArithmeticOperator::Mul => lhs.checked_mul(rhs),
+                ArithmeticOperator::Div | ArithmeticOperator::Rem if rhs == 0 => {
+                    bail!("division by zero")
+                }
+                ArithmeticOperator::Div => lhs.checked_div(rhs),
+                ArithmeticOperator::Rem => lhs.checked_rem(rhs),
+                ArithmeticOperator::Power if rhs < 0 => {
+                    return Ok(Value::Float((lhs as f64).powf(rhs as f64)))
+                }
+                ArithmeticOperator::Power => {
+                    u32::try_from(rhs).ok().and_then(|rhs| lhs.checked_pow(rhs))
+                }
+            };
+            return result
+                .map(Value::Integer)
+                .ok_or_else(|| format_err!("integer overflow"));
+        }
+        let (lhs, rhs) = (self.as_f64(), rhs.as_f64());
+        let result = match operator {
+            ArithmeticOperator::Add => lhs + rhs,
+            ArithmeticOperator::Sub => lhs - rhs,
+            ArithmeticOperator::Mul => lhs * rhs,
+            ArithmeticOperator::Div | ArithmeticOperator::Rem if rhs == 0.0 => {
+                bail!("division by zero")
+            }
+            ArithmeticOperator::Div => lhs / rhs,
+            ArithmeticOperator::Rem => lhs % rhs,
+            ArithmeticOperator::Power => lhs.powf(rhs),
+        };
+        if !result.is_finite() {
+            bail!("the result is not a finite number");
+        }
+        Ok(Value::Float(result))
+    }
+}
+
+impl PartialEq for Value {
+    fn eq(&self, other: &Value) -> bool {
+        match (self, other) {
+            (Value::Integer(lhs), Value::Integer(rhs)) => lhs == rhs,
+            _ => self.as_f64() == other.as_f64(),
+        }
+    }
+}
+
+impl PartialOrd for Value {
+    fn partial_cmp(&self, other: &Value) -> Option<Ordering> {
+        match (self, other) {
+            (Value::Integer(lhs), Value::Integer(rhs)) => lhs.partial_cmp(rhs),
+            _ => self.as_f64().partial_cmp(&other.as_f64()),
+        }
+    }
+}
+
+impl FromStr for Value {
+    type Err = Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        if let Ok(k) = i64::from_str(s) {
+            return Ok(Value::Integer(k));
+        }
+        match f64::from_str(s) {
+            Ok(k) if k.is_finite() => Ok(Value::Float(k)),
+            _ => bail!("Invalid number: {}", s),
+        }
+    }
+}
+
+impl std::fmt::Display for Value {
+    fn fmt(&self, f: &mut Formatter<'_>) -> Result<(), std::fmt::Error> {
         match self {
-            ConstraintOperand::Constant(k) => k.to_string(),
-            ConstraintOperand::Variable(v) => format!("${}", v),
+            Value::Integer(k) => write!(f, "{}", k),
+            Value::Float(k) => write!(f, "{}", k),
         }
     }
 }
@@ -696,17 +1495,25 @@ impl ToString for ConstraintOperand {
 impl Constraint {
     /// Check if the variables verify this constraint, returning `Some(res)` if the check was
     /// successful (i.e. all the variables were present and valid). `None` is returned if this
-    /// constraint cannot be fully verified.
-    fn is_valid(&self, vars: &HashMap<String, String>) -> Option<bool> {
-        let mut last = self.operands[0].get_val(vars)?;
+    /// constraint cannot be fully verified. An error is returned if the evaluation of an operand
+    /// fails, for example for a division by zero.
+    fn is_valid(&self, vars: &HashMap<String, String>) -> Result<Option<bool>, Error> {
+        if !self
+            .operands
+            .iter()
+            .all(|operand| operand.can_evaluate(vars))
+        {
+            return Ok(None);
+        }
+        let mut last = self.operands[0].evaluate(vars)?;
         for (operator, operand) in self.operators.iter().zip(&self.operands[1..]) {
-            let this = operand.get_val(vars)?;
+            let this = operand.evaluate(vars)?;
             if !operator.is_valid(last, this) {
-                return Some(false);
+                return Ok(Some(false));
             }
             last = this;
         }
-        Some(true)
+        Ok(Some(true))
     }
 }
 
@@ -730,7 +1537,7 @@ mod tests {
     use tempdir::TempDir;
 
     use crate::ioi::format::italian_yaml::cases_gen::{
-        CasesGen, ConstraintOperand, ConstraintOperator,
+        ArithmeticOperator, CasesGen, ConstraintOperator, Expression,
     };
     use crate::ioi::format::italian_yaml::TaskInputEntry;
     use crate::ioi::{
@@ -752,14 +1559,20 @@ mod tests {
             self
         }
 
+        fn write_file<P: AsRef<Path>, S: AsRef<str>>(&self, path: P, content: S) -> &Self {
+            self.add_file(&path);
+            std::fs::write(self.0.path().join(path), content.as_ref()).unwrap();
+            self
+        }
+
         fn cases_gen<S: AsRef<str>>(
             &self,
             content: S,
-        ) -> Result<CasesGen<impl Fn(TestcaseId) -> OutputGenerator>, Error> {
+        ) -> Result<CasesGen<impl Fn(TestcaseId, Option<&str>) -> OutputGenerator>, Error> {
             std::fs::create_dir_all(self.0.path().join("gen")).unwrap();
             let dest = self.0.path().join("gen/cases.gen");
             std::fs::write(&dest, content.as_ref()).unwrap();
-            CasesGen::new(dest, |_| OutputGenerator::StaticFile("nope".into()))
+            CasesGen::new(dest, |_, _| OutputGenerator::StaticFile("nope".into()))
         }
     }
 
@@ -1072,10 +1885,10 @@ mod tests {
         assert_eq!(
             constr.operands,
             vec![
-                ConstraintOperand::Constant(1),
-                ConstraintOperand::Variable("N".into()),
-                ConstraintOperand::Variable("K".into()),
-                ConstraintOperand::Variable("M".into())
+                Expression::Constant(1),
+                Expression::Variable("N".into()),
+                Expression::Variable("K".into()),
+                Expression::Variable("M".into())
             ]
         );
         assert_eq!(
@@ -1098,10 +1911,10 @@ mod tests {
         assert_eq!(
             constr.operands,
             vec![
-                ConstraintOperand::Variable("K".into()),
-                ConstraintOperand::Constant(1),
-                ConstraintOperand::Variable("N".into()),
-                ConstraintOperand::Variable("M".into())
+                Expression::Variable("K".into()),
+                Expression::Constant(1),
+                Expression::Variable("N".into()),
+                Expression::Variable("M".into())
             ]
         );
         assert_eq!(
@@ -1124,9 +1937,9 @@ mod tests {
         assert_eq!(
             constr.operands,
             vec![
-                ConstraintOperand::Variable("K".into()),
-                ConstraintOperand::Variable("N".into()),
-                ConstraintOperand::Variable("M".into())
+                Expression::Variable("K".into()),
+                Expression::Variable("N".into()),
+                Expression::Variable("M".into())
             ]
         );
         assert_eq!(
@@ -1145,27 +1958,84 @@ mod tests {
 
     #[test]
     fn test_add_constraint_floats() {
-        let gen = TestHelper::new().cases_gen(":CONSTRAINT $N < 10.2");
-        assert!(gen.is_err());
+        let gen = TestHelper::new()
+            .cases_gen(":CONSTRAINT $N < 10.2")
+            .unwrap();
+        assert_eq!(
+            gen.constraints[0].operands,
+            vec![Expression::Variable("N".into()), Expression::Float(10.2)]
+        );
     }
 
     #[test]
-    fn test_add_constraint_invalid_integer() {
-        let gen = TestHelper::new().cases_gen(":CONSTRAINT $N < 100000000000000000000000000000000");
-        assert!(gen.is_err());
-        assert_that!(gen.unwrap_err().to_string()).contains("Invalid integer constant");
+    fn test_add_constraint_arithmetic() {
+        let gen = TestHelper::new()
+            .cases_gen(":CONSTRAINT $N * $M <= 1000000")
+            .unwrap();
+        assert_eq!(
+            gen.constraints[0].operands,
+            vec![
+                Expression::Operation(
+                    Box::new(Expression::Variable("N".into())),
+                    ArithmeticOperator::Mul,
+                    Box::new(Expression::Variable("M".into()))
+                ),
+                Expression::Constant(1000000)
+            ]
+        );
     }
 
-    /**********************
-     * : SUBTASK
-     *********************/
+    #[test]
+    fn test_add_constraint_precedence() {
+        let gen = TestHelper::new()
+            .cases_gen(":CONSTRAINT -2^$K^2 + 3 * max($N, 1.5) % 7 < min(-$M, 4) - 1")
+            .unwrap();
+        assert_eq!(
+            format!("{:?}", gen.constraints[0]),
+            "-(2 ^ ($K ^ 2)) + ((3 * max($N, 1.5)) % 7) < min(-$M, 4) - 1"
+        );
+    }
 
     #[test]
-    fn test_add_subtask() {
-        let gen = TestHelper::new().cases_gen(":SUBTASK 42").unwrap();
-        assert_eq!(gen.subtask_id, 1);
-        assert_eq!(gen.result.len(), 1);
-        let subtask = &gen.result[0];
+    fn test_add_constraint_unary_plus() {
+        let gen = TestHelper::new()
+            .cases_gen(":CONSTRAINT +1 <= $N <= +5\n:CONSTRAINT $M < 2 + +$N")
+            .unwrap();
+        assert_eq!(
+            gen.constraints[0].operands,
+            vec![
+                Expression::Constant(1),
+                Expression::Variable("N".into()),
+                Expression::Constant(5)
+            ]
+        );
+        assert_eq!(format!("{:?}", gen.constraints[1]), "$M < 2 + $N");
+    }
+
+    #[test]
+    fn test_add_constraint_unknown_function() {
+        let gen = TestHelper::new().cases_gen(":CONSTRAINT $N < abs($M)");
+        assert!(gen.is_err());
+        assert_that!(gen.unwrap_err().to_string()).contains("Unknown function 'abs'");
+    }
+
+    #[test]
+    fn test_add_constraint_invalid_integer() {
+        let gen = TestHelper::new().cases_gen(":CONSTRAINT $N < 100000000000000000000000000000000");
+        assert!(gen.is_err());
+        assert_that!(gen.unwrap_err().to_string()).contains("Invalid integer constant");
+    }
+
+    /**********************
+     * : SUBTASK
+     *********************/
+
+    #[test]
+    fn test_add_subtask() {
+        let gen = TestHelper::new().cases_gen(":SUBTASK 42").unwrap();
+        assert_eq!(gen.subtask_id, 1);
+        assert_eq!(gen.result.len(), 1);
+        let subtask = &gen.result[0];
         if let TaskInputEntry::Subtask(subtask) = subtask {
             assert_eq!(subtask.id, 0);
             assert_eq!(subtask.description, None);
@@ -1209,6 +2079,81 @@ mod tests {
         }
     }
 
+    #[test]
+    fn test_add_subtask_includes() {
+        let gen = TestHelper::new()
+            .add_file("gen/generator.py")
+            .cases_gen(
+                ":GEN gen gen/generator.py\n:SUBTASK 10\n1\n2\n:SUBTASK 20\n3\n:SUBTASK 70 INCLUDES 0 1 the description\n4",
+            )
+            .unwrap();
+        assert_eq!(gen.subtask_id, 3);
+        assert_eq!(gen.testcase_id, 4);
+        let entries = gen.get_task_entries();
+        assert_eq!(entries.len(), 10);
+        if let TaskInputEntry::Subtask(subtask) = &entries[5] {
+            assert_eq!(subtask.id, 2);
+            assert_eq!(subtask.description, Some("the description".into()));
+            assert_abs_diff_eq!(subtask.max_score, 70.0);
+        } else {
+            panic!("Expecting a subtask, got: {:?}", entries[5]);
+        }
+        let ids: Vec<_> = entries[6..]
+            .iter()
+            .map(|entry| match entry {
+                TaskInputEntry::Testcase(testcase) => testcase.id,
+                _ => panic!("Expecting a testcase, got: {:?}", entry),
+            })
+            .collect();
+        assert_eq!(ids, vec![0, 1, 2, 3]);
+    }
+
+    #[test]
+    fn test_add_subtask_includes_transitive() {
+        let gen = TestHelper::new()
+            .add_file("gen/generator.py")
+            .cases_gen(
+                ":GEN gen gen/generator.py\n:SUBTASK 10\n1\n:SUBTASK 20 INCLUDES 0\n2\n:SUBTASK 70 INCLUDES 0 1",
+            )
+            .unwrap();
+        assert_eq!(gen.subtask_testcases[2].len(), 2);
+        assert_eq!(gen.subtask_testcases[2][0].id, 0);
+        assert_eq!(gen.subtask_testcases[2][1].id, 1);
+    }
+
+    #[test]
+    fn test_add_subtask_includes_next() {
+        let gen = TestHelper::new().cases_gen(":SUBTASK 10\n:SUBTASK 20 INCLUDES 1");
+        assert!(gen.is_err());
+        assert_that!(gen.unwrap_err().to_string()).contains("cannot include subtask 1");
+    }
+
+    #[test]
+    fn test_gen_gen_includes() {
+        let helper = TestHelper::new();
+        let gen = helper
+            .add_file("gen/generator.py")
+            .cases_gen(
+                ":GEN gen gen/generator.py\n:SUBTASK 42\n12 34\n:SUBTASK 24 INCLUDES 0\n21 21",
+            )
+            .unwrap();
+        gen.write_gen_gen().unwrap();
+        let path = gen.task_dir.join("gen/GEN");
+        let data = std::fs::read_to_string(path).unwrap();
+        let res: Vec<_> = data
+            .split('\n')
+            .filter(|s| !s.is_empty() && !s.starts_with("# ") && !s.starts_with("#COPY"))
+            .collect();
+        assert_eq!(res.len(), 5);
+        assert_eq!(res[0], "#ST: 42");
+        assert!(res[1].contains("12 34"));
+        assert_eq!(res[2], "#ST: 24");
+        // the included testcase is generated again in the second subtask
+        assert_eq!(res[3], res[1]);
+        assert!(res[4].contains("21 21"));
+        assert!(data.contains("# Testcase 0 (included)"));
+    }
+
     /**********************
      * : COPY
      *********************/
@@ -1572,6 +2517,58 @@ mod tests {
         assert!(gen.is_ok());
     }
 
+    #[test]
+    fn test_testcase_arithmetic_constraints() {
+        let gen = TestHelper::new().add_file("gen/generator.py").cases_gen(
+            ":GEN default gen/generator.py N M K\n:CONSTRAINT $N * $M <= 1000000\n:CONSTRAINT $K <= $N / 2\n:SUBTASK 42\n1000 1000 500\n1001 999 1",
+        );
+        assert!(gen.is_ok());
+    }
+
+    #[test]
+    fn test_testcase_invalid_arithmetic_constraints() {
+        let gen = TestHelper::new().add_file("gen/generator.py").cases_gen(
+            ":GEN default gen/generator.py N M K\n:CONSTRAINT $N * $M <= 1000000\n:CONSTRAINT $K <= $N / 2\n:SUBTASK 42\n1000 1000 500\n1000 1000 501",
+        );
+        assert!(gen.is_err());
+        assert_that!(gen.unwrap_err().to_string()).starts_with(
+            "gen/cases.gen:6: Testcase '1000 1000 501' violates constraint $K <= $N / 2",
+        );
+    }
+
+    #[test]
+    fn test_testcase_non_linear_constraints() {
+        let helper = TestHelper::new();
+        helper.add_file("gen/generator.py");
+        let cases = ":GEN default gen/generator.py N K\n:CONSTRAINT 2^$K <= min($N, 1000) < max(100, 20 * $K)\n:SUBTASK 42\n";
+        assert!(helper.cases_gen(format!("{}{}", cases, "50 5")).is_ok());
+        assert!(helper.cases_gen(format!("{}{}", cases, "50 6")).is_err());
+        assert!(helper.cases_gen(format!("{}{}", cases, "200 2")).is_err());
+    }
+
+    #[test]
+    fn test_testcase_float_constraints() {
+        let helper = TestHelper::new();
+        helper.add_file("gen/generator.py");
+        let cases =
+            ":GEN default gen/generator.py X\n:CONSTRAINT 0.5 <= $X * 2 <= 5.0\n:SUBTASK 42\n";
+        assert!(helper.cases_gen(format!("{}{}", cases, "2.5")).is_ok());
+        assert!(helper.cases_gen(format!("{}{}", cases, "1")).is_ok());
+        assert!(helper.cases_gen(format!("{}{}", cases, "2.51")).is_err());
+        assert!(helper.cases_gen(format!("{}{}", cases, "0.2")).is_err());
+    }
+
+    #[test]
+    fn test_testcase_constraint_division_by_zero() {
+        let gen = TestHelper::new().add_file("gen/generator.py").cases_gen(
+            ":GEN default gen/generator.py N M\n:CONSTRAINT $N / $M < 10\n:SUBTASK 42\n10 0",
+        );
+        assert!(gen.is_err());
+        let error = gen.unwrap_err().to_string();
+        assert_that!(error).starts_with("gen/cases.gen:4: Cannot check constraint");
+        assert_that!(error).contains("division by zero");
+    }
+
     #[test]
     fn test_testcase_corrupted_command() {
         let gen = TestHelper::new()
@@ -1596,4 +2593,608 @@ mod tests {
         assert!(gen.is_err());
         assert_that!(gen.unwrap_err().to_string()).contains("outside a subtask");
     }
+
+    /**********************
+     * : FOR
+     *********************/
+
+    fn get_testcase_args(
+        gen: &CasesGen<impl Fn(TestcaseId, Option<&str>) -> OutputGenerator>,
+    ) -> Vec<String> {
+        gen.result
+            .iter()
+            .filter_map(|entry| match entry {
+                TaskInputEntry::Testcase(testcase) => match &testcase.input_generator {
+                    InputGenerator::Custom(_, args) => Some(args.join(" ")),
+                    InputGenerator::StaticFile(path) => Some(path.display().to_string()),
+                },
+                _ => None,
+            })
+            .collect()
+    }
+
+    #[test]
+    fn test_for_loop() {
+        let gen = TestHelper::new()
+            .add_file("gen/gen.py")
+            .cases_gen(
+                ":GEN default gen/gen.py\n:SUBTASK 42\n:FOR seed IN 201..203\n1000 $seed\n: END",
+            )
+            .unwrap();
+        assert_eq!(gen.testcase_id, 3);
+        assert_eq!(
+            get_testcase_args(&gen),
+            vec!["1000 201", "1000 202", "1000 203"]
+        );
+        assert!(gen.loop_variables.is_empty());
+    }
+
+    #[test]
+    fn test_for_loop_nested() {
+        let gen = TestHelper::new()
+            .add_file("gen/gen.py")
+            .cases_gen(":GEN default gen/gen.py\n:SUBTASK 42\n:FOR a IN 1..2\n:FOR b IN -1..0\n: RUN default $a $b $c\n: END\n:END")
+            .unwrap();
+        assert_eq!(
+            get_testcase_args(&gen),
+            vec!["1 -1 $c", "1 0 $c", "2 -1 $c", "2 0 $c"]
+        );
+    }
+
+    #[test]
+    fn test_for_loop_copy() {
+        let helper = TestHelper::new();
+        helper.add_file("gen/input1.txt").add_file("gen/input2.txt");
+        let gen = helper
+            .cases_gen(":SUBTASK 42\n:FOR i IN 1..2\n:COPY gen/input$i.txt\n:END")
+            .unwrap();
+        let args = get_testcase_args(&gen);
+        assert_eq!(args.len(), 2);
+        assert!(args[0].ends_with("gen/input1.txt"));
+        assert!(args[1].ends_with("gen/input2.txt"));
+    }
+
+    #[test]
+    fn test_for_loop_constraints() {
+        let gen = TestHelper::new().add_file("gen/gen.py").cases_gen(
+            ":GEN default gen/gen.py N seed\n:CONSTRAINT $seed < 203\n:SUBTASK 42\n:FOR s IN 201..203\n1000 $s\n:END",
+        );
+        assert!(gen.is_err());
+        assert_that!(gen.unwrap_err().to_string()).contains("'1000 203' violates constraint");
+    }
+
+    #[test]
+    fn test_for_loop_variable_in_constraints() {
+        let gen = TestHelper::new().add_file("gen/gen.py").cases_gen(
+            ":GEN default gen/gen.py\n:CONSTRAINT $s < 203\n:SUBTASK 42\n:FOR s IN 201..203\n1000\n:END",
+        );
+        assert!(gen.is_err());
+        assert_that!(gen.unwrap_err().to_string()).contains("$s = 203");
+    }
+
+    #[test]
+    fn test_for_loop_missing_end() {
+        let gen = TestHelper::new()
+            .add_file("gen/gen.py")
+            .cases_gen(":GEN default gen/gen.py\n:SUBTASK 42\n:FOR s IN 1..3\n1 $s");
+        assert!(gen.is_err());
+        assert_that!(gen.unwrap_err().to_string()).contains("Missing : END");
+    }
+
+    #[test]
+    fn test_for_loop_unexpected_end() {
+        let gen = TestHelper::new()
+            .add_file("gen/gen.py")
+            .cases_gen(":GEN default gen/gen.py\n:SUBTASK 42\n1 2\n:END");
+        assert!(gen.is_err());
+        assert_that!(gen.unwrap_err().to_string()).contains("outside a loop");
+    }
+
+    #[test]
+    fn test_for_loop_error_location() {
+        let gen = TestHelper::new().add_file("gen/gen.py").cases_gen(
+            ":GEN default gen/gen.py N\n:CONSTRAINT $N <= 10\n:SUBTASK 42\n:FOR i IN 1..2\n\n$(i * 10)\n:END",
+        );
+        assert!(gen.is_err());
+        assert_that!(gen.unwrap_err().to_string())
+            .starts_with("gen/cases.gen:6: Testcase '20' violates constraint");
+    }
+
+    #[test]
+    fn test_for_loop_invalid_range() {
+        let gen = TestHelper::new()
+            .add_file("gen/gen.py")
+            .cases_gen(":GEN default gen/gen.py\n:SUBTASK 42\n:FOR s IN 3..1\n1 $s\n:END");
+        assert!(gen.is_err());
+        assert_that!(gen.unwrap_err().to_string()).contains("Invalid range");
+    }
+
+    #[test]
+    fn test_for_loop_too_many_iterations() {
+        let gen = TestHelper::new()
+            .add_file("gen/gen.py")
+            .cases_gen(":GEN default gen/gen.py\n:SUBTASK 42\n:FOR s IN 1..1000000000\n1 $s\n:END");
+        assert!(gen.is_err());
+        assert_that!(gen.unwrap_err().to_string())
+            .starts_with("gen/cases.gen:3: Too many iterations");
+        assert!(TestHelper::new()
+            .add_file("gen/gen.py")
+            .cases_gen(":GEN default gen/gen.py\n:SUBTASK 42\n:FOR s IN 1..10000\n1 $s\n:END")
+            .is_ok());
+    }
+
+    #[test]
+    fn test_for_loop_definitions() {
+        for command in &[
+            "GEN other gen/gen.py",
+            "VAL other gen/gen.py",
+            "CONSTRAINT $s < 10",
+            "SUBTASK 10",
+        ] {
+            let gen = TestHelper::new().add_file("gen/gen.py").cases_gen(format!(
+                ":GEN default gen/gen.py\n:SUBTASK 42\n:FOR s IN 1..2\n1 $s\n:{}\n:END",
+                command
+            ));
+            assert!(gen.is_err());
+            let name = command.split(' ').next().unwrap();
+            assert_that!(gen.unwrap_err().to_string()).is_equal_to(format!(
+                "gen/cases.gen:5: Cannot use : {} inside a loop",
+                name
+            ));
+        }
+    }
+
+    #[test]
+    fn test_for_loop_definitions_in_include() {
+        let gen = TestHelper::new()
+            .add_file("gen/gen.py")
+            .write_file("gen/body.gen", ":CONSTRAINT $i < 10\n1 $i")
+            .cases_gen(
+                ":GEN default gen/gen.py\n:SUBTASK 42\n:FOR i IN 1..2\n:INCLUDE body.gen\n:END",
+            );
+        assert!(gen.is_err());
+        assert_that!(gen.unwrap_err().to_string())
+            .is_equal_to("gen/body.gen:1: Cannot use : CONSTRAINT inside a loop".to_string());
+    }
+
+    #[test]
+    fn test_for_loop_duplicate_variable() {
+        let gen = TestHelper::new().add_file("gen/gen.py").cases_gen(
+            ":GEN default gen/gen.py\n:SUBTASK 42\n:FOR s IN 1..2\n:FOR s IN 1..2\n1 $s\n:END\n:END",
+        );
+        assert!(gen.is_err());
+        assert_that!(gen.unwrap_err().to_string()).contains("already defined");
+    }
+
+    #[test]
+    fn test_gen_gen_for_loop() {
+        let helper = TestHelper::new();
+        let gen = helper
+            .add_file("gen/generator.py")
+            .cases_gen(":GEN gen gen/generator.py\n:SUBTASK 42\n:FOR seed IN 1..2\n12 $seed\n:END")
+            .unwrap();
+        gen.write_gen_gen().unwrap();
+        let path = gen.task_dir.join("gen/GEN");
+        let data = std::fs::read_to_string(path).unwrap();
+        let res: Vec<_> = data
+            .split('\n')
+            .filter(|s| !s.is_empty() && !s.starts_with("# "))
+            .collect();
+        assert_eq!(res.len(), 3);
+        assert_eq!(res[0], "#ST: 42");
+        assert_eq!(res[1], "generator.py 12 1");
+        assert_eq!(res[2], "generator.py 12 2");
+    }
+
+    /**********************
+     * : SET
+     *********************/
+
+    #[test]
+    fn test_set_variable() {
+        let gen = TestHelper::new()
+            .add_file("gen/gen.py")
+            .cases_gen(":SET MAXN 200000\n:GEN default gen/gen.py\n:SUBTASK 42\n$MAXN $(MAXN/2) $($MAXN - 1) 17 $UNKNOWN")
+            .unwrap();
+        assert_eq!(gen.set_variables["MAXN"], "200000");
+        assert_eq!(
+            get_testcase_args(&gen),
+            vec!["200000 100000 199999 17 $UNKNOWN"]
+        );
+    }
+
+    #[test]
+    fn test_set_variable_expression() {
+        let gen = TestHelper::new()
+            .cases_gen(":SET A 2 + 3 * 4\n:SET B (A - 4) * -2 % 7\n:SET C -(-A)/3")
+            .unwrap();
+        assert_eq!(gen.set_variables["A"], "14");
+        assert_eq!(gen.set_variables["B"], "-6");
+        assert_eq!(gen.set_variables["C"], "4");
+    }
+
+    #[test]
+    fn test_set_variable_functions() {
+        let gen = TestHelper::new()
+            .cases_gen(":SET A 2^10\n:SET B max(1, A, 3) - min(2, -A)\n:SET C min(A, 5) / 2.0\n:SET D 2^-1")
+            .unwrap();
+        assert_eq!(gen.set_variables["A"], "1024");
+        assert_eq!(gen.set_variables["B"], "2048");
+        assert_eq!(gen.set_variables["C"], "2.5");
+        assert_eq!(gen.set_variables["D"], "0.5");
+    }
+
+    #[test]
+    fn test_set_variable_in_loop() {
+        let gen = TestHelper::new()
+            .add_file("gen/gen.py")
+            .cases_gen(":GEN default gen/gen.py\n:SUBTASK 42\n:FOR i IN 1..2\n:SET N i * 10\n$N $(i+N)\n:END")
+            .unwrap();
+        assert_eq!(get_testcase_args(&gen), vec!["10 11", "20 22"]);
+    }
+
+    #[test]
+    fn test_set_variable_in_constraints() {
+        let gen = TestHelper::new().add_file("gen/gen.py").cases_gen(
+            ":SET MAXN 100\n:GEN default gen/gen.py N\n:CONSTRAINT $N <= $MAXN\n:SUBTASK 42\n$MAXN\n$(MAXN+1)",
+        );
+        assert!(gen.is_err());
+        assert_that!(gen.unwrap_err().to_string()).contains("'101' violates constraint");
+    }
+
+    #[test]
+    fn test_set_variable_unknown() {
+        let gen = TestHelper::new().cases_gen(":SET A B + 1");
+        assert!(gen.is_err());
+        assert_that!(gen.unwrap_err().to_string()).contains("unknown variable $B");
+    }
+
+    #[test]
+    fn test_set_variable_division_by_zero() {
+        let gen = TestHelper::new().cases_gen(":SET A 1 / (2 - 2)");
+        assert!(gen.is_err());
+        assert_that!(gen.unwrap_err().to_string()).contains("division by zero");
+    }
+
+    #[test]
+    fn test_set_variable_invalid_expression() {
+        let gen = TestHelper::new().cases_gen(":SET A 1 +");
+        assert!(gen.is_err());
+        assert_that!(gen.unwrap_err().to_string()).contains("Invalid expression '1 +'");
+    }
+
+    #[test]
+    fn test_set_loop_variable() {
+        let gen = TestHelper::new().cases_gen(":FOR i IN 1..2\n:SET i 3\n:END");
+        assert!(gen.is_err());
+        assert_that!(gen.unwrap_err().to_string()).contains("loop variable");
+    }
+
+    #[test]
+    fn test_substitution_unbalanced() {
+        let gen = TestHelper::new()
+            .add_file("gen/gen.py")
+            .cases_gen(":GEN default gen/gen.py\n:SUBTASK 42\n$((1+2)");
+        assert!(gen.is_err());
+        assert_that!(gen.unwrap_err().to_string()).contains("Unbalanced parenthesis");
+    }
+
+    /**********************
+     * : INCLUDE
+     *********************/
+
+    #[test]
+    fn test_include() {
+        let gen = TestHelper::new()
+            .add_file("gen/gen.py")
+            .write_file(
+                "gen/common.gen",
+                ":GEN default gen/gen.py N\n:CONSTRAINT $N <= 100",
+            )
+            .write_file("gen/st1.gen", ":SUBTASK 58\n2\n3")
+            .cases_gen(":INCLUDE common.gen\n:SUBTASK 42\n1\n: INCLUDE st1.gen ")
+            .unwrap();
+        assert_eq!(gen.subtask_id, 2);
+        assert_eq!(gen.constraints.len(), 1);
+        assert_eq!(get_testcase_args(&gen), vec!["1", "2", "3"]);
+        assert!(gen.include_stack.is_empty());
+    }
+
+    #[test]
+    fn test_include_missing_file() {
+        let gen = TestHelper::new().cases_gen(":INCLUDE missing.gen");
+        assert!(gen.is_err());
+        assert_that!(gen.unwrap_err().to_string()).contains("missing.gen': file not found");
+    }
+
+    #[test]
+    fn test_include_relative_path() {
+        let gen = TestHelper::new()
+            .add_file("gen/gen.py")
+            .write_file(
+                "gen/common/generators.gen",
+                ":INCLUDE ../validators.gen\n:GEN default gen/gen.py",
+            )
+            .write_file("gen/validators.gen", ":VAL default gen/gen.py")
+            .cases_gen(":INCLUDE common/generators.gen\n:SUBTASK 42\n1")
+            .unwrap();
+        assert_eq!(gen.default_generator, Some("default".into()));
+        assert_eq!(gen.default_validator, Some("default".into()));
+    }
+
+    #[test]
+    fn test_include_in_loop() {
+        let gen = TestHelper::new()
+            .add_file("gen/gen.py")
+            .write_file("gen/seed.gen", "$i 1\n$i 2")
+            .cases_gen(
+                ":GEN default gen/gen.py\n:SUBTASK 42\n:FOR i IN 1..2\n:INCLUDE seed.gen\n:END",
+            )
+            .unwrap();
+        assert_eq!(get_testcase_args(&gen), vec!["1 1", "1 2", "2 1", "2 2"]);
+    }
+
+    #[test]
+    fn test_include_cycle() {
+        let gen = TestHelper::new()
+            .write_file("gen/a.gen", ":INCLUDE b.gen")
+            .write_file("gen/b.gen", "# comment\n:INCLUDE ../gen/a.gen")
+            .cases_gen(":INCLUDE a.gen");
+        assert!(gen.is_err());
+        assert_eq!(
+            gen.unwrap_err().to_string(),
+            "gen/b.gen:2: Cannot include 'gen/../gen/a.gen': include cycle detected"
+        );
+    }
+
+    #[test]
+    fn test_include_itself() {
+        let gen = TestHelper::new().cases_gen("\n:INCLUDE cases.gen");
+        assert!(gen.is_err());
+        assert_that!(gen.unwrap_err().to_string())
+            .starts_with("gen/cases.gen:2: Cannot include 'gen/cases.gen'");
+    }
+
+    #[test]
+    fn test_include_error_location() {
+        let gen = TestHelper::new()
+            .add_file("gen/gen.py")
+            .write_file("gen/st0.gen", ":SUBTASK 42\n\n1000")
+            .cases_gen(":GEN default gen/gen.py N\n:CONSTRAINT $N < 1000\n:INCLUDE st0.gen");
+        assert!(gen.is_err());
+        assert_that!(gen.unwrap_err().to_string())
+            .starts_with("gen/st0.gen:3: Testcase '1000' violates constraint");
+    }
+
+    #[test]
+    fn test_include_invalid_file() {
+        let gen = TestHelper::new()
+            .write_file("gen/broken.gen", ":SUBTASK")
+            .cases_gen(":INCLUDE broken.gen");
+        assert!(gen.is_err());
+        assert_that!(gen.unwrap_err().to_string()).starts_with("Cannot parse gen/broken.gen");
+    }
+
+    /**********************
+     * : TESTCASE
+     *********************/
+
+    fn get_testcase_names(
+        gen: &CasesGen<impl Fn(TestcaseId, Option<&str>) -> OutputGenerator>,
+    ) -> Vec<Option<String>> {
+        gen.result
+            .iter()
+            .filter_map(|entry| match entry {
+                TaskInputEntry::Testcase(testcase) => Some(testcase.name.clone()),
+                _ => None,
+            })
+            .collect()
+    }
+
+    #[test]
+    fn test_testcase_name() {
+        let gen = TestHelper::new()
+            .add_file("gen/gen.py")
+            .add_file("gen/input.txt")
+            .cases_gen(":GEN default gen/gen.py\n:SUBTASK 42\n:TESTCASE small\n1\n2\n: TESTCASE copied \n:COPY gen/input.txt\n:SUBTASK 58 INCLUDES 0")
+            .unwrap();
+        assert_eq!(
+            get_testcase_names(&gen),
+            vec![
+                Some("small".into()),
+                None,
+                Some("copied".into()),
+                Some("small".into()),
+                None,
+                Some("copied".into())
+            ]
+        );
+    }
+
+    #[test]
+    fn test_testcase_name_in_loop() {
+        let gen = TestHelper::new()
+            .add_file("gen/gen.py")
+            .cases_gen(":GEN default gen/gen.py\n:SUBTASK 42\n:FOR i IN 1..2\n:TESTCASE random_$i\n: RUN default $i\n:END")
+            .unwrap();
+        assert_eq!(
+            get_testcase_names(&gen),
+            vec![Some("random_1".into()), Some("random_2".into())]
+        );
+    }
+
+    #[test]
+    fn test_testcase_name_twice() {
+        let gen = TestHelper::new()
+            .add_file("gen/gen.py")
+            .cases_gen(":GEN default gen/gen.py\n:SUBTASK 42\n:TESTCASE a\n:TESTCASE b\n1");
+        assert!(gen.is_err());
+        assert_that!(gen.unwrap_err().to_string()).contains("it is already named 'a'");
+    }
+
+    #[test]
+    fn test_testcase_name_without_testcase() {
+        let gen = TestHelper::new()
+            .add_file("gen/gen.py")
+            .cases_gen(":GEN default gen/gen.py\n:SUBTASK 42\n:TESTCASE a\n:SUBTASK 58\n1");
+        assert!(gen.is_err());
+        assert_that!(gen.unwrap_err().to_string())
+            .contains("gen/cases.gen:4: Testcase name 'a' is not followed by a testcase");
+        let gen = TestHelper::new()
+            .add_file("gen/gen.py")
+            .cases_gen(":GEN default gen/gen.py\n:SUBTASK 42\n1\n:TESTCASE a");
+        assert!(gen.is_err());
+        assert_that!(gen.unwrap_err().to_string())
+            .contains("Testcase name 'a' is not followed by a testcase");
+    }
+
+    #[test]
+    fn test_gen_gen_testcase_name() {
+        let helper = TestHelper::new();
+        let gen = helper
+            .add_file("gen/generator.py")
+            .cases_gen(":GEN gen gen/generator.py\n:SUBTASK 42\n:TESTCASE worst\n12 34\n56 78")
+            .unwrap();
+        gen.write_gen_gen().unwrap();
+        let path = gen.task_dir.join("gen/GEN");
+        let data = std::fs::read_to_string(path).unwrap();
+        let res: Vec<_> = data
+            .split('\n')
+            .filter(|s| !s.is_empty() && !s.starts_with("# "))
+            .collect();
+        assert_eq!(
+            res,
+            vec![
+                "#ST: 42",
+                "#TESTCASE: worst",
+                "generator.py 12 34",
+                "generator.py 56 78"
+            ]
+        );
+    }
+
+    /**********************
+     * : WEIGHT
+     *********************/
+
+    fn get_testcase_weights(
+        gen: &CasesGen<impl Fn(TestcaseId, Option<&str>) -> OutputGenerator>,
+    ) -> Vec<f64> {
+        gen.result
+            .iter()
+            .filter_map(|entry| match entry {
+                TaskInputEntry::Testcase(testcase) => Some(testcase.weight),
+                _ => None,
+            })
+            .collect()
+    }
+
+    #[test]
+    fn test_testcase_weight() {
+        let gen = TestHelper::new()
+            .add_file("gen/gen.py")
+            .add_file("gen/input.txt")
+            .cases_gen(":GEN default gen/gen.py\n:SUBTASK 42\n:WEIGHT 3\n1\n2\n: WEIGHT 0.5 \n:COPY gen/input.txt\n:SUBTASK 58 INCLUDES 0")
+            .unwrap();
+        assert_eq!(
+            get_testcase_weights(&gen),
+            vec![3.0, 1.0, 0.5, 3.0, 1.0, 0.5]
+        );
+    }
+
+    #[test]
+    fn test_testcase_weight_expression() {
+        let gen = TestHelper::new()
+            .add_file("gen/gen.py")
+            .cases_gen(
+                ":GEN default gen/gen.py\n:SUBTASK 42\n:FOR i IN 1..3\n:WEIGHT 2 * $i\n$i\n:END",
+            )
+            .unwrap();
+        assert_eq!(get_testcase_weights(&gen), vec![2.0, 4.0, 6.0]);
+    }
+
+    #[test]
+    fn test_testcase_weight_not_positive() {
+        let gen = TestHelper::new()
+            .add_file("gen/gen.py")
+            .cases_gen(":GEN default gen/gen.py\n:SUBTASK 42\n:WEIGHT 1 - 1\n1");
+        assert!(gen.is_err());
+        assert_that!(gen.unwrap_err().to_string())
+            .contains("gen/cases.gen:3: Invalid testcase weight 0: it must be positive");
+    }
+
+    #[test]
+    fn test_testcase_weight_twice() {
+        let gen = TestHelper::new()
+            .add_file("gen/gen.py")
+            .cases_gen(":GEN default gen/gen.py\n:SUBTASK 42\n:WEIGHT 2\n:WEIGHT 3\n1");
+        assert!(gen.is_err());
+        assert_that!(gen.unwrap_err().to_string()).contains("it is already 2");
+    }
+
+    #[test]
+    fn test_testcase_weight_without_testcase() {
+        let gen = TestHelper::new()
+            .add_file("gen/gen.py")
+            .cases_gen(":GEN default gen/gen.py\n:SUBTASK 42\n1\n:WEIGHT 2");
+        assert!(gen.is_err());
+        assert_that!(gen.unwrap_err().to_string())
+            .contains("Testcase weight 2 is not followed by a testcase");
+    }
+
+    /**********************
+     * : TIME_LIMIT / : MEMORY_LIMIT
+     *********************/
+
+    fn get_subtask_limits(
+        gen: &CasesGen<impl Fn(TestcaseId, Option<&str>) -> OutputGenerator>,
+    ) -> Vec<(Option<f64>, Option<u64>)> {
+        gen.result
+            .iter()
+            .filter_map(|entry| match entry {
+                TaskInputEntry::Subtask(subtask) => {
+                    Some((subtask.time_limit, subtask.memory_limit))
+                }
+                _ => None,
+            })
+            .collect()
+    }
+
+    #[test]
+    fn test_subtask_limits() {
+        let gen = TestHelper::new()
+            .add_file("gen/gen.py")
+            .cases_gen(":GEN default gen/gen.py\n:SUBTASK 42\n:TIME_LIMIT 2.5\n1\n:SUBTASK 58\n:SET MB 512\n: MEMORY_LIMIT 2 * $MB\n2\n:TIME_LIMIT 3\n:SUBTASK 0")
+            .unwrap();
+        assert_eq!(
+            get_subtask_limits(&gen),
+            vec![(Some(2.5), None), (Some(3.0), Some(1024)), (None, None)]
+        );
+    }
+
+    #[test]
+    fn test_subtask_limits_outside_subtask() {
+        let gen = TestHelper::new()
+            .add_file("gen/gen.py")
+            .cases_gen(":GEN default gen/gen.py\n:TIME_LIMIT 2\n:SUBTASK 42\n1");
+        assert!(gen.is_err());
+        assert_that!(gen.unwrap_err().to_string())
+            .contains("gen/cases.gen:2: Cannot set the time limit outside a subtask");
+    }
+
+    #[test]
+    fn test_subtask_limits_invalid() {
+        let gen = TestHelper::new()
+            .add_file("gen/gen.py")
+            .cases_gen(":GEN default gen/gen.py\n:SUBTASK 42\n:TIME_LIMIT 0\n1");
+        assert!(gen.is_err());
+        assert_that!(gen.unwrap_err().to_string())
+            .contains("Invalid time limit 0: it must be positive");
+        let gen = TestHelper::new()
+            .add_file("gen/gen.py")
+            .cases_gen(":GEN default gen/gen.py\n:SUBTASK 42\n:MEMORY_LIMIT 256.5\n1");
+        assert!(gen.is_err());
+        assert_that!(gen.unwrap_err().to_string())
+            .contains("Invalid memory limit 256.5: it must be a positive integer");
+    }
 }
@@ -29,7 +29,7 @@ pub(crate) fn parse_gen_gen<P: AsRef<Path>, V, O>(
 ) -> Result<Vec<TaskInputEntry>, Error>
 where
     V: Fn(SubtaskId) -> InputValidator,
-    O: Fn(TestcaseId) -> OutputGenerator,
+    O: Fn(TestcaseId, Option<&str>) -> OutputGenerator,
 {
     let task_dir = path
         .as_ref()
@@ -43,11 +43,15 @@ where
     let mut testcase_count = 0;
     let mut subtask_id: SubtaskId = 0;
     let mut entries = vec![];
+    // the name set by `#TESTCASE:`, it's used by the next testcase
+    let mut testcase_name = None;
 
     let mut default_subtask = Some(SubtaskInfo {
         id: 0,
         description: None,
         max_score: 100.0,
+        time_limit: None,
+        memory_limit: None,
         testcases: HashMap::new(),
     });
 
@@ -76,6 +80,9 @@ where
                     .ok_or_else(|| format_err!("Corrupted parser"))?;
                 match line.as_rule() {
                     parser::Rule::subtask => {
+                        if let Some(name) = testcase_name {
+                            bail!("Testcase name '{}' is not followed by a testcase", name);
+                        }
                         default_subtask.take(); // ignore the default subtask ever
                         let score = line
                             .into_inner()
@@ -86,6 +93,8 @@ where
                             id: subtask_id,
                             description: None,
                             max_score: score.parse::<f64>().expect("Invalid subtask score"),
+                            time_limit: None,
+                            memory_limit: None,
                             testcases: HashMap::new(),
                         }));
                         subtask_id += 1;
@@ -100,11 +109,15 @@ where
                             .next()
                             .ok_or_else(|| format_err!("Corrupted parser"))?
                             .as_str();
+                        let output_generator =
+                            get_output_gen(testcase_count, testcase_name.as_deref());
                         entries.push(TaskInputEntry::Testcase(TestcaseInfo {
                             id: testcase_count,
+                            name: testcase_name.take(),
+                            weight: 1.0,
                             input_generator: InputGenerator::StaticFile(task_dir.join(what)),
                             input_validator: get_validator(subtask_id - 1),
-                            output_generator: get_output_gen(testcase_count),
+                            output_generator,
                         }));
                         testcase_count += 1;
                     }
@@ -115,18 +128,32 @@ where
                         }
                         let cmd: Vec<String> =
                             line.into_inner().map(|x| x.as_str().to_owned()).collect();
-                        let output_generator = get_output_gen(testcase_count);
+                        let output_generator =
+                            get_output_gen(testcase_count, testcase_name.as_deref());
                         if let OutputGenerator::StaticFile(_) = output_generator {
                             bail!("Generator detected but no solution found. Cannot generate output files.");
                         }
                         entries.push(TaskInputEntry::Testcase(TestcaseInfo {
                             id: testcase_count,
+                            name: testcase_name.take(),
+                            weight: 1.0,
                             input_generator: InputGenerator::Custom(generator.clone(), cmd),
                             input_validator: get_validator(subtask_id - 1),
                             output_generator,
                         }));
                         testcase_count += 1;
                     }
+                    parser::Rule::testcase_name => {
+                        let name = line
+                            .into_inner()
+                            .next()
+                            .ok_or_else(|| format_err!("Corrupted parser"))?
+                            .as_str();
+                        if testcase_name.is_some() {
+                            bail!("Testcase name '{}' follows another #TESTCASE", name);
+                        }
+                        testcase_name = Some(name.to_string());
+                    }
                     parser::Rule::comment => {}
                     parser::Rule::empty => {}
                     _ => unreachable!(),
@@ -136,6 +163,9 @@ where
             _ => unreachable!(),
         }
     }
+    if let Some(name) = testcase_name {
+        bail!("Testcase name '{}' is not followed by a testcase", name);
+    }
     Ok(entries)
 }
 
@@ -175,7 +205,7 @@ mod tests {
         InputValidator::AssumeValid
     }
 
-    fn get_output_generator(_testcase: TestcaseId) -> OutputGenerator {
+    fn get_output_generator(_testcase: TestcaseId, _name: Option<&str>) -> OutputGenerator {
         let source = SourceFile::new("a.py", "", None, None::<&str>).unwrap();
         OutputGenerator::Custom(Arc::new(source), vec![])
     }
@@ -374,6 +404,63 @@ mod tests {
         }
     }
 
+    #[test]
+    fn test_parser_testcase_name() {
+        let task = make_task("#TESTCASE: worst_case\n1234\n#COPY: random/file\n#TESTCASE:  sample \n#COPY: random/file\n");
+        let entries = get_entries(task.path());
+        if let [Subtask(_), Testcase(testcase1), Testcase(testcase2), Testcase(testcase3)] =
+            entries.as_slice()
+        {
+            assert_eq!(testcase1.name, Some("worst_case".to_string()));
+            assert_eq!(testcase2.name, None);
+            assert_eq!(testcase3.name, Some("sample".to_string()));
+        } else {
+            panic!("Wrong entries returned: {:?}", entries);
+        }
+    }
+
+    #[test]
+    fn test_parser_testcase_name_output_generator() {
+        let task = make_task("#TESTCASE: sample\n#COPY: random/file\n#TESTCASE: big\n1234\n");
+        let entries = parse_gen_gen(
+            task.path().join("gen").join("GEN"),
+            get_validator,
+            |testcase, name| {
+                let source = SourceFile::new("a.py", "", None, None::<&str>).unwrap();
+                let args = vec![testcase.to_string(), name.unwrap_or("-").to_string()];
+                OutputGenerator::Custom(Arc::new(source), args)
+            },
+        )
+        .unwrap();
+        if let [Subtask(_), Testcase(testcase1), Testcase(testcase2)] = entries.as_slice() {
+            match &testcase1.output_generator {
+                OutputGenerator::Custom(_, args) => assert_eq!(args, &vec!["0", "sample"]),
+                _ => panic!("Invalid generator"),
+            }
+            match &testcase2.output_generator {
+                OutputGenerator::Custom(_, args) => assert_eq!(args, &vec!["1", "big"]),
+                _ => panic!("Invalid generator"),
+            }
+        } else {
+            panic!("Wrong entries returned: {:?}", entries);
+        }
+    }
+
+    #[test]
+    fn test_parser_testcase_name_without_testcase() {
+        let task = make_task("#TESTCASE: worst_case\n#ST: 100\n1234\n");
+        let entries = parse_gen_gen(
+            task.path().join("gen").join("GEN"),
+            get_validator,
+            get_output_generator,
+        );
+        assert!(entries.is_err());
+        assert!(entries
+            .unwrap_err()
+            .to_string()
+            .contains("is not followed by a testcase"));
+    }
+
     #[test]
     fn test_parser_empty_lines() {
         let task = make_task("\n\n1234\n\n\n5678\n\n");
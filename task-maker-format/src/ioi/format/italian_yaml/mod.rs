@@ -23,6 +23,10 @@
 //!   input file will be simply copied from the path specified after the column. Example:
 //!   `#COPY: gen/hardcoded.in`. The path is relative to the task root directory and the file must
 //!   exists and be readable from task-maker. The path should not contain spaces.
+//! - if the line starts with `#TESTCASE:` it sets the name of the following testcase, which will be
+//!   shown in the UIs and used for its files: `input/input_<name>.txt` and
+//!   `output/output_<name>.txt`. Example: `#TESTCASE: line_worst_case`. The name should not contain
+//!   spaces.
 //! - non-empty lines not starting with `#` defines a new testcases each. Each line contains
 //!   command line arguments to pass to the generator executable. The generator should be named
 //!   `gen/generator.*` or `gen/generatore.*`. Example: `1 2 3`, the generator will be invoked
@@ -149,28 +153,48 @@
 //! ### `: CONSTRAINT operand (operator operand)+`
 //! This command adds a constraint that validates the parameters of the testcases. The arguments of
 //! `: CONSTRAINT` form an expression that is an inequality (with equalities allowed) between
-//! constants and variables. When a testcase is defined using a generator with the arguments known,
-//! all those variables become defined and will be checked with all the constraints.
+//! arithmetic expressions of constants and variables. When a testcase is defined using a generator
+//! with the arguments known, all those variables become defined and will be checked with all the
+//! constraints. If some of the variables of a constraint are not defined, or they are not numbers,
+//! the constraint is not checked.
 //!
 //! The operators available are: < <= > >= =, but note that the inequalities must have the same
 //! direction (cannot mix < and >).
 //!
+//! The operands can use the integer and floating point constants, the operators `+ - * / %`
+//! (`+` and `-` also as unary signs, e.g. `-$N` or `+5`), the power `^`, the functions `min(a, b, ...)` and `max(a, b, ...)` and the parenthesis. The
+//! operations between integers use the integer arithmetic (i.e. `7 / 2` is `3`), while the ones
+//! involving a floating point value are done in floating point.
+//!
 //! Constraints defined before the first subtask will be used for all the subtasks. Constraints
 //! defined inside a subtask will be used only for that subtask.
 //!
 //! Example: `:CONSTRAINT 0 <= $N < $M <= 1000000` will check that the variables `$N` and `$M` are
 //! between 0 and 1000000 and `$N` is smaller than `$M`.
 //!
-//! ### `: SUBTASK score [description]`
+//! Example: `:CONSTRAINT $N * $M <= 1000000` will check that the product of `$N` and `$M` is at
+//! most 1000000.
+//!
+//! ### `: SUBTASK score [INCLUDES ids...] [description]`
 //! This command marks the start of a new subtask, just like how `#ST` in `gen/GEN` did. The score
 //! can be a simple floating point number (either an integer or an integer.integer). The description
 //! that follows is optional and will be included in the subtask metadata.
 //!
+//! With `INCLUDES` followed by the 0-based indices of some previous subtasks, all the testcases of
+//! those subtasks will also be part of the new one. Those testcases are generated and evaluated
+//! only once, but their score counts in all the subtasks they are part of. Since they are evaluated
+//! only once, they use the limits of the first subtask they are part of, even if the new subtask
+//! has different limits. Note that the
+//! auto-generated `gen/GEN` cannot represent the included testcases.
+//!
 //! When a new subtask is started the generator and validator will be reset to the default ones.
 //!
 //! Example: `: SUBTASK 40 All the nodes are in a line` defines a new subtask worth 40 points, with
 //! the provided description.
 //!
+//! Example: `: SUBTASK 60 INCLUDES 0 1 No additional constraints` defines a new subtask worth 60
+//! points that contains all the testcases of the first two subtasks.
+//!
 //! ### `: COPY path`
 //! This command creates a new testcase coping the input file from the specified path, relative to
 //! the task root directory. The file will be validated using the current validator of the subtask.
@@ -191,6 +215,71 @@
 //! Example: `: RUN line 1 2 3` will run the `line` generator passing the three integers as
 //! arguments.
 //!
+//! ### `: FOR var IN from..to` ... `: END`
+//! The lines between `: FOR` and the matching `: END` are repeated once for each integer value
+//! between `from` and `to`, both included. In the testcases, in `: RUN` and in `: COPY` inside the
+//! loop, `$var` is replaced with the current value. The loop variable is also available in the
+//! constraint evaluation and in the validator arguments. Loops can be nested, but the loop
+//! variables must have different names. A loop can repeat at most 10000 times and its body cannot
+//! contain `: GEN`, `: VAL`, `: CONSTRAINT` or `: SUBTASK`, not even in the included files.
+//!
+//! Example: `: FOR seed IN 201..240` followed by `1000 1000 $seed` and by `: END` defines 40
+//! testcases, with the seeds from 201 to 240.
+//!
+//! ### `: SET NAME expr`
+//! This command evaluates the expression `expr` and assigns its value to the variable `NAME`. The
+//! expression has the same syntax of the operands of `: CONSTRAINT` and can use the variables
+//! previously defined with `: SET` or by an enclosing `: FOR`.
+//!
+//! In the testcases, in `: RUN` and in `: COPY`, `$NAME` is replaced with the value of the
+//! variable and `$(expr)` with the value of the expression. The variables are also available in
+//! the constraint evaluation and in the validator arguments.
+//!
+//! Example: `: SET MAXN 200000` followed by `$MAXN $(MAXN/2) 17` defines a testcase with the
+//! arguments `200000 100000 17`.
+//!
+//! ### `: TESTCASE name`
+//! This command sets the name of the following testcase, defined by a testcase line, by `: RUN` or
+//! by `: COPY`. The name will be shown in the UIs and in the messages about the testcase, for
+//! recognizing it without counting the lines. The variables in the name are replaced with their
+//! values, and the name should not contain spaces. The input and output files of a named testcase
+//! are `input/input_<name>.txt` and `output/output_<name>.txt`, instead of the ones with the id
+//! of the testcase, so two testcases cannot have the same name.
+//!
+//! Example: `: TESTCASE line_worst_case` followed by `1000000 line` defines a testcase named
+//! `line_worst_case`, whose input file is `input/input_line_worst_case.txt`.
+//!
+//! ### `: WEIGHT expr`
+//! This command sets the weight of the following testcase, like `: TESTCASE` does for its name.
+//! The weight is the value of the expression, which must be positive, and it is 1 by default. It
+//! is used only by the `sum` testcase score aggregator: the score of a subtask is the weighted
+//! average of the scores of its testcases.
+//!
+//! Example: `: WEIGHT 5` followed by `1000000 line` makes the testcase count as much as 5 of the
+//! others of the subtask.
+//!
+//! ### `: TIME_LIMIT expr` and `: MEMORY_LIMIT expr`
+//! These commands override the time limit (in seconds) and the memory limit (in MiB) of the task
+//! for the solutions evaluated on the testcases of the current subtask, for example for a subtask
+//! with larger inputs. The limits are the values of the expressions, which must be positive, and
+//! the memory limit must be an integer. The same overrides can be set in the `subtask_limits` field
+//! of the task.yaml, but not in both places. The multipliers of the languages (in the
+//! `limits_multipliers` field of the task.yaml) are applied to these limits too. The testcases
+//! included from other subtasks with `INCLUDES` keep the limits of their own subtask.
+//!
+//! Example: `: MEMORY_LIMIT 1024` allows the solutions to use up to 1GiB of memory in the current
+//! subtask.
+//!
+//! ### `: INCLUDE path`
+//! This command processes the lines of another file, as if they were written in place of the
+//! command. The path is relative to the directory of the file containing the command, while the
+//! paths inside the included file (e.g. the ones of `: GEN` and `: COPY`) are still relative to the
+//! task root directory. A file cannot include itself, not even indirectly. The errors in the
+//! included file are reported with its path and line number.
+//!
+//! Example: `: INCLUDE common.gen` processes the file `gen/common.gen`, for example with the
+//! generators and validators shared between the versions of a task.
+//!
 //! ## Testcase definition
 //! Similarly to `gen/GEN` lines that are not commands nor comments are simple testcase definition.
 //! Their semantics is the same of `: RUN default args...`.
@@ -213,7 +302,8 @@
 //! : VAL default gen/validator.py
 //! : VAL line gen/val_line.py $INPUT $ST_NUM # same as default
 //!
-//! : CONSTRAINT 1 <= $N <= 1000
+//! : SET MAXN 1000
+//! : CONSTRAINT 1 <= $N <= $MAXN
 //! : CONSTRAINT 1 <= $M <= 1000000
 //!
 //! : SUBTASK 0 Examples
@@ -228,9 +318,12 @@
 //! 500   102
 //!
 //! : SUBTASK 70
-//! 1000   1000      201
-//! 1000   1000000   202
+//! $MAXN  1000      201
+//! $MAXN  1000000   202
 //! : RUN hard 1000 1000 95% 12.3 203
+//! : FOR seed IN 204..210
+//! $MAXN  $(MAXN*1000)   $seed
+//! : END
 //! ```
 //!
 //! In this example 3 generators and 2 validators are defined, named `default`, `line`, `hard` and
@@ -239,7 +332,8 @@
 //! validated.
 //!
 //! Note that the second validator has the arguments specified, and they are the same as the default
-//! ones. Also note that the inline comment will be ignored.
+//! ones. Also note that the inline comment will be ignored. The limit on `$N` is stored in the
+//! `$MAXN` variable, which is used both by the constraint and by the testcases of the last subtask.
 //!
 //! This file defines 3 subtasks, worth 0, 30 and 70 points each.
 //!
@@ -250,10 +344,11 @@
 //! the `line` validator. Note that there is an additional constraint for the subtask, it will be
 //! checked only in this subtask.
 //!
-//! The third subtask will use the `default` generator and validator, except for the last testcase
+//! The third subtask will use the `default` generator and validator, except for the third testcase
 //! which will use the `hard` one. Note that since the `hard` generator does not have the argument
 //! specification, its parameters won't be checked. Also note that the constraint `$N <= 500` won't
-//! be checked because it was scoped only to the second subtask.
+//! be checked because it was scoped only to the second subtask. The loop at the end adds 7 more
+//! testcases, with the seeds from 204 to 210.
 //! The subtask also does not have a description, the default one (`Subtask 2`) will be used.
 
 use std::collections::HashMap;
@@ -262,20 +357,26 @@ use std::path::{Path, PathBuf};
 use std::str::FromStr;
 use std::sync::Arc;
 
-use failure::Error;
+use failure::{bail, format_err, Error};
+use itertools::Itertools;
 use serde::{Deserialize, Deserializer, Serialize, Serializer};
 
 pub(crate) use cases_gen::{is_gen_gen_deletable, TM_ALLOW_DELETE_COOKIE};
-use task_maker_lang::GraderMap;
+use task_maker_lang::{GraderMap, LanguageManager};
 
 use crate::ioi::sanity_checks::get_sanity_checks;
 use crate::ioi::TM_VALIDATION_FILE_NAME;
 use crate::ioi::{
-    make_booklets, Checker, IOITask, InputValidator, OutputGenerator, SubtaskId, SubtaskInfo,
-    TaskType, TestcaseId, TestcaseInfo, TestcaseScoreAggregator,
+    make_booklets, testcase_file_name, Checker, IOITask, InputValidator, LimitsMultipliers,
+    OutputGenerator, SubtaskId, SubtaskInfo, TaskType, TestcaseId, TestcaseInfo,
+    TestcaseScoreAggregator,
+};
+use crate::ioi::{
+    BatchTypeData, CommunicationTypeData, ManagerType, OutputOnlyTypeData, TwoStepsTypeData, UserIo,
+};
+use crate::{
+    find_source_file, list_files, CompilerSettingsRule, EvaluationConfig, TaskCompilerSettings,
 };
-use crate::ioi::{BatchTypeData, CommunicationTypeData};
-use crate::{find_source_file, list_files, EvaluationConfig};
 
 mod cases_gen;
 mod gen_gen;
@@ -292,6 +393,12 @@ struct TaskYAML {
     pub title: String,
     /// The score type to use for this task.
     pub score_type: Option<String>,
+    /// The parameters of the score type, one per subtask in order. Used only by `GroupThreshold`,
+    /// where they are the thresholds of the subtasks.
+    pub score_type_parameters: Option<Vec<f64>>,
+    /// The type of the task, using the CMS names (`Batch`, `Communication`, `OutputOnly` or
+    /// `TwoSteps`). If not set it's detected from the files inside the task directory.
+    pub task_type: Option<String>,
 
     /// The time limit for the execution of the solutions, if not set it's unlimited.
     #[serde(alias = "timeout")]
@@ -299,6 +406,18 @@ struct TaskYAML {
     /// The memory limit in MiB of the execution of the solution, if not set it's unlimited.
     #[serde(alias = "memlimit")]
     pub memory_limit: Option<u64>,
+    /// The limits of some subtasks, overriding the ones of the task, keyed by the id of the
+    /// subtask.
+    #[serde(default)]
+    pub subtask_limits: HashMap<SubtaskId, SubtaskLimitsYAML>,
+    /// The multipliers of the limits for the solutions in some languages, keyed by the name of the
+    /// language (e.g. `Python` or `C++`). The unknown languages are rejected.
+    #[serde(default)]
+    pub limits_multipliers: HashMap<String, LimitsMultipliers>,
+    /// The settings of the compilers of the source files of the task, applied in order to the
+    /// source files they match.
+    #[serde(default)]
+    pub compilers: Vec<CompilerSettingsRule>,
 
     /// Whether this is an output only task. Defaults to false.
     #[serde(default = "bool::default")]
@@ -320,6 +439,33 @@ struct TaskYAML {
 
     /// Number of solution processes to spawn in parallel in a communication task.
     pub num_processes: Option<u8>,
+    /// How the solutions of a communication task are connected to the manager: `fifo_io` (the
+    /// default) passes the paths of the FIFO pipes as arguments, `std_io` connects them to the
+    /// standard input and output of the solution.
+    pub user_io: Option<String>,
+    /// The protocol of the manager of a communication task: `cms` (score on stdout and message on
+    /// stderr) or `testlib` (a testlib interactor, the outcome is in the exit code). If not set
+    /// it's detected from the source of the manager.
+    pub manager_type: Option<String>,
+    /// The protocol of the checker: `cms` (score on stdout and message on stderr) or `testlib`
+    /// (outcome in the exit code). If not set it's detected from the source of the checker. It can
+    /// also be one of the built-in checkers: `tokens`, `case_insensitive` or `float`.
+    pub checker_type: Option<String>,
+    /// The maximum absolute difference between two numbers for the `float` checker. Defaults to
+    /// `1e-6`.
+    pub checker_absolute_epsilon: Option<f64>,
+    /// The maximum relative difference between two numbers for the `float` checker. Defaults to
+    /// `1e-6`.
+    pub checker_relative_epsilon: Option<f64>,
+}
+
+/// The limits of a subtask in the task.yaml, overriding the ones of the task.
+#[derive(Debug, Serialize, Deserialize)]
+struct SubtaskLimitsYAML {
+    /// The time limit for the solutions in the subtask.
+    pub time_limit: Option<f64>,
+    /// The memory limit in MiB for the solutions in the subtask.
+    pub memory_limit: Option<u64>,
 }
 
 /// The iterator item type when following the task input testcases.
@@ -344,6 +490,8 @@ pub(crate) enum TaskInputEntry {
 /// * `sol/` - folder with solutions, graders and stubs
 ///     * `solution.xxx` the official solution (also `soluzione`)
 ///     * other solutions with different names
+///     * in output only tasks, the submissions as directories or zip archives with the
+///       `output_<n>.txt` files inside
 /// * `check/` - folder with the checker (also `cor/`)
 ///     * `checker.xxx` (also `correttore`)
 /// * `input/` - folder with the input files
@@ -373,22 +521,37 @@ pub fn parse_task<P: AsRef<Path>>(
     let grader_map = Arc::new(GraderMap::new(graders));
     debug!("The graders are: {:#?}", grader_map);
 
-    let task_type = if let Some(comm) = parse_communication_task_data(task_dir, &yaml) {
-        comm
-    } else {
-        parse_batch_task_data(task_dir, grader_map.clone())
+    let task_type = match yaml.task_type.as_ref().map(String::as_str) {
+        Some("Batch") => parse_batch_task_data(task_dir, &yaml, grader_map.clone())?,
+        Some("Communication") => parse_communication_task_data(task_dir, &yaml)?
+            .ok_or_else(|| format_err!("Communication task without a manager"))?,
+        Some("OutputOnly") => parse_output_only_task_data(task_dir, &yaml)?,
+        Some("TwoSteps") => parse_two_steps_task_data(task_dir, &yaml, grader_map.clone())?,
+        Some(task_type) => bail!("Unsupported task type: {}", task_type),
+        None => {
+            if yaml.output_only {
+                parse_output_only_task_data(task_dir, &yaml)?
+            } else if let Some(comm) = parse_communication_task_data(task_dir, &yaml)? {
+                comm
+            } else {
+                parse_batch_task_data(task_dir, &yaml, grader_map.clone())?
+            }
+        }
     };
 
     let gen_gen = task_dir.join("gen").join("GEN");
     let cases_gen = task_dir.join("gen").join("cases.gen");
-    let output_generator: Box<dyn Fn(TestcaseId) -> OutputGenerator> =
-        if let TaskType::Batch(_) = &task_type {
-            Box::new(detect_output_generator(
+    let output_generator: Box<dyn Fn(TestcaseId, Option<&str>) -> OutputGenerator> =
+        match &task_type {
+            TaskType::Batch(_) | TaskType::OutputOnly(_) => Box::new(detect_output_generator(
                 task_dir.to_path_buf(),
                 grader_map.clone(),
-            ))
-        } else {
-            Box::new(|_| OutputGenerator::NotAvailable)
+            )),
+            TaskType::TwoSteps(_) => {
+                let generator = detect_output_generator(task_dir.to_path_buf(), grader_map.clone());
+                Box::new(move |tc, name| two_steps_output_generator(generator(tc, name)))
+            }
+            _ => Box::new(|_, _| OutputGenerator::NotAvailable),
         };
 
     let inputs = if cases_gen.exists() {
@@ -438,6 +601,10 @@ pub fn parse_task<P: AsRef<Path>>(
     if let Some(subtask) = last_subtask.take() {
         subtasks.insert(subtask.id, subtask);
     }
+    apply_subtask_limits(&yaml, &mut subtasks)?;
+    check_limits_multipliers(&yaml.limits_multipliers)?;
+    let testcase_score_aggregator = parse_score_type(&yaml, subtasks.len())?;
+    let compiler_settings = TaskCompilerSettings::new(yaml.compilers)?;
 
     let mut task = IOITask {
         path: task_dir.into(),
@@ -446,19 +613,11 @@ pub fn parse_task<P: AsRef<Path>>(
         title: yaml.title,
         time_limit: yaml.time_limit,
         memory_limit: yaml.memory_limit,
+        limits_multipliers: yaml.limits_multipliers,
+        compiler_settings,
         infile,
         outfile,
-        testcase_score_aggregator: yaml
-            .score_type
-            .as_ref()
-            .map(|s| TestcaseScoreAggregator::from_str(s))
-            .unwrap_or_else(|| {
-                if subtasks.len() == 1 {
-                    Ok(TestcaseScoreAggregator::Sum)
-                } else {
-                    Ok(TestcaseScoreAggregator::Min)
-                }
-            })?,
+        testcase_score_aggregator,
         subtasks,
         grader_map,
         booklets: Vec::new(),
@@ -474,6 +633,98 @@ pub fn parse_task<P: AsRef<Path>>(
     Ok(task)
 }
 
+/// Parse the score type of the task, using its parameters if needed. If the score type is not
+/// specified `Sum` is used for tasks with a single subtask, `Min` otherwise.
+fn parse_score_type(
+    yaml: &TaskYAML,
+    num_subtasks: usize,
+) -> Result<TestcaseScoreAggregator, Error> {
+    let score_type = match &yaml.score_type {
+        Some(score_type) => score_type,
+        None if num_subtasks == 1 => return Ok(TestcaseScoreAggregator::Sum),
+        None => return Ok(TestcaseScoreAggregator::Min),
+    };
+    match score_type.as_str() {
+        "threshold" | "GroupThreshold" => {
+            let thresholds = yaml.score_type_parameters.as_ref().ok_or_else(|| {
+                format_err!(
+                    "The score type {} requires score_type_parameters",
+                    score_type
+                )
+            })?;
+            if thresholds.len() != num_subtasks {
+                bail!(
+                    "The score type {} requires one parameter per subtask: {} found, {} expected",
+                    score_type,
+                    thresholds.len(),
+                    num_subtasks
+                );
+            }
+            Ok(TestcaseScoreAggregator::Threshold(
+                thresholds
+                    .iter()
+                    .enumerate()
+                    .map(|(id, threshold)| (id as SubtaskId, *threshold))
+                    .collect(),
+            ))
+        }
+        _ => TestcaseScoreAggregator::from_str(score_type),
+    }
+}
+
+/// Override the limits of the subtasks with the ones in the task.yaml. A limit cannot be set both in
+/// the task.yaml and in the `gen/cases.gen`.
+fn apply_subtask_limits(
+    yaml: &TaskYAML,
+    subtasks: &mut HashMap<SubtaskId, SubtaskInfo>,
+) -> Result<(), Error> {
+    for (id, limits) in &yaml.subtask_limits {
+        let subtask = subtasks.get_mut(id).ok_or_else(|| {
+            format_err!("Cannot set the limits of subtask {}: no such subtask", id)
+        })?;
+        if let Some(time_limit) = limits.time_limit {
+            if subtask.time_limit.is_some() {
+                bail!(
+                    "The time limit of subtask {} is set both in task.yaml and in gen/cases.gen",
+                    id
+                );
+            }
+            subtask.time_limit = Some(time_limit);
+        }
+        if let Some(memory_limit) = limits.memory_limit {
+            if subtask.memory_limit.is_some() {
+                bail!(
+                    "The memory limit of subtask {} is set both in task.yaml and in gen/cases.gen",
+                    id
+                );
+            }
+            subtask.memory_limit = Some(memory_limit);
+        }
+    }
+    Ok(())
+}
+
+/// Check that the keys of the `limits_multipliers` are known languages, either with their full name
+/// (e.g. `Python / Autodetect`) or with the part before the slash (e.g. `Python`).
+fn check_limits_multipliers(
+    limits_multipliers: &HashMap<String, LimitsMultipliers>,
+) -> Result<(), Error> {
+    let languages = LanguageManager::language_names();
+    for language in limits_multipliers.keys().sorted() {
+        let known = languages
+            .iter()
+            .any(|name| name == language || name.split(" / ").next() == Some(language.as_str()));
+        if !known {
+            bail!(
+                "Unknown language in limits_multipliers: {} (the known languages are: {})",
+                language,
+                languages.join(", ")
+            );
+        }
+    }
+    Ok(())
+}
+
 /// Search for a valid input validator inside the task directory. Will return a function that, given
 /// a subtask id, returns an `InputValidator` using that validator. If no validator is found,
 /// `InputValidator::AssumeValid` is used.
@@ -506,12 +757,13 @@ fn detect_validator(task_dir: PathBuf) -> impl Fn(SubtaskId) -> InputValidator {
 }
 
 /// Search for a valid output generator (aka official solution) inside the task directory. Will
-/// return a function that, given a testcase id, returns an `OutputGenerator` using that generator.
-/// If no generator is found, `OutputGenerator::StaticFile` is used instead.
+/// return a function that, given a testcase id and its name, returns an `OutputGenerator` using
+/// that generator. If no generator is found, `OutputGenerator::StaticFile` is used instead, reading
+/// the output file of the testcase from `output/`.
 fn detect_output_generator(
     task_dir: PathBuf,
     grader_map: Arc<GraderMap>,
-) -> impl Fn(TestcaseId) -> OutputGenerator {
+) -> impl Fn(TestcaseId, Option<&str>) -> OutputGenerator {
     let official_solution = find_source_file(
         &task_dir,
         vec![
@@ -527,17 +779,103 @@ fn detect_output_generator(
     .map(Arc::new);
     debug!("Detected output generator: {:?}", official_solution);
     let output_directory = task_dir.join("output");
-    move |tc: TestcaseId| -> OutputGenerator {
+    move |tc: TestcaseId, name: Option<&str>| -> OutputGenerator {
         if let Some(solution) = official_solution.as_ref() {
             OutputGenerator::Custom(solution.clone(), vec![])
         } else {
-            OutputGenerator::StaticFile(output_directory.join(format!("output{}.txt", tc)))
+            OutputGenerator::StaticFile(
+                output_directory.join(testcase_file_name("output", tc, name)),
+            )
         }
     }
 }
 
+/// Make the official solution of a two steps task generate the output files with the same two
+/// processes used for evaluating the solutions, instead of running it as a single program.
+fn two_steps_output_generator(generator: OutputGenerator) -> OutputGenerator {
+    match generator {
+        OutputGenerator::Custom(solution, _) => OutputGenerator::TwoSteps(solution),
+        generator => generator,
+    }
+}
+
 /// Parse the task components relative to the batch task type.
-fn parse_batch_task_data(task_dir: &Path, grader_map: Arc<GraderMap>) -> TaskType {
+fn parse_batch_task_data(
+    task_dir: &Path,
+    yaml: &TaskYAML,
+    grader_map: Arc<GraderMap>,
+) -> Result<TaskType, Error> {
+    let checker = detect_checker(task_dir, yaml)?;
+    let official_solution =
+        match detect_output_generator(task_dir.to_path_buf(), grader_map)(0, None) {
+            gen @ OutputGenerator::Custom(_, _) => Some(gen),
+            _ => None,
+        };
+    Ok(TaskType::Batch(BatchTypeData {
+        output_generator: official_solution,
+        checker,
+    }))
+}
+
+/// Parse the task components relative to the two steps task type.
+fn parse_two_steps_task_data(
+    task_dir: &Path,
+    yaml: &TaskYAML,
+    grader_map: Arc<GraderMap>,
+) -> Result<TaskType, Error> {
+    let checker = detect_checker(task_dir, yaml)?;
+    let official_solution =
+        match detect_output_generator(task_dir.to_path_buf(), grader_map)(0, None) {
+            gen @ OutputGenerator::Custom(_, _) => Some(two_steps_output_generator(gen)),
+            _ => None,
+        };
+    Ok(TaskType::TwoSteps(TwoStepsTypeData {
+        output_generator: official_solution,
+        checker,
+    }))
+}
+
+/// Parse the task components relative to the output only task type.
+fn parse_output_only_task_data(task_dir: &Path, yaml: &TaskYAML) -> Result<TaskType, Error> {
+    Ok(TaskType::OutputOnly(OutputOnlyTypeData {
+        checker: detect_checker(task_dir, yaml)?,
+    }))
+}
+
+/// Search for a custom checker inside the task directory, falling back to the white diff checker
+/// if none is found. The protocol of the checker is taken from the `checker_type` field of the
+/// yaml, if not set a checker is considered a testlib checker if it includes `testlib.h`. If the
+/// `checker_type` is a built-in checker the custom one is ignored, if it's `cms` or `testlib` the
+/// custom checker is required.
+fn detect_checker(task_dir: &Path, yaml: &TaskYAML) -> Result<Checker, Error> {
+    let checker_type = yaml.checker_type.as_ref().map(String::as_str);
+    match checker_type {
+        Some("tokens") => return Ok(Checker::Tokens),
+        Some("case_insensitive") => return Ok(Checker::CaseInsensitive),
+        Some("float") => {
+            let epsilon = |value: Option<f64>, default: f64, name: &str| match value {
+                Some(value) if !value.is_finite() || value < 0.0 => {
+                    bail!("Invalid {}: {} is not a non-negative number", name, value)
+                }
+                Some(value) => Ok(value),
+                None => Ok(default),
+            };
+            return Ok(Checker::Float {
+                absolute_epsilon: epsilon(
+                    yaml.checker_absolute_epsilon,
+                    Checker::DEFAULT_FLOAT_ABSOLUTE_EPSILON,
+                    "checker_absolute_epsilon",
+                )?,
+                relative_epsilon: epsilon(
+                    yaml.checker_relative_epsilon,
+                    Checker::DEFAULT_FLOAT_RELATIVE_EPSILON,
+                    "checker_relative_epsilon",
+                )?,
+            });
+        }
+        Some("cms") | Some("testlib") | None => {}
+        Some(checker_type) => bail!("Unsupported checker type: {}", checker_type),
+    }
     let checker = find_source_file(
         task_dir,
         vec![
@@ -555,34 +893,74 @@ fn parse_batch_task_data(task_dir: &Path, grader_map: Arc<GraderMap>) -> TaskTyp
         c.copy_exe();
         c
     })
-    .map(Arc::new)
-    .map(Checker::Custom)
-    .unwrap_or(Checker::WhiteDiff);
-
-    let official_solution = match detect_output_generator(task_dir.to_path_buf(), grader_map)(0) {
-        gen @ OutputGenerator::Custom(_, _) => Some(gen),
-        _ => None,
+    .map(Arc::new);
+    let checker = match (checker, checker_type) {
+        (Some(checker), _) => checker,
+        (None, None) => return Ok(Checker::WhiteDiff),
+        (None, Some(checker_type)) => bail!(
+            "The checker type is {} but no checker was found (check/checker.* or cor/correttore.*)",
+            checker_type
+        ),
     };
-    TaskType::Batch(BatchTypeData {
-        output_generator: official_solution,
-        checker,
-    })
+    match checker_type {
+        Some("testlib") => Ok(Checker::Testlib(checker)),
+        Some(_) => Ok(Checker::Custom(checker)),
+        None => {
+            let source = fs::read(&checker.path).unwrap_or_default();
+            if String::from_utf8_lossy(&source).contains("testlib.h") {
+                Ok(Checker::Testlib(checker))
+            } else {
+                Ok(Checker::Custom(checker))
+            }
+        }
+    }
 }
 
-/// Parse the task components relative to the communication task type.
-fn parse_communication_task_data(task_dir: &Path, yaml: &TaskYAML) -> Option<TaskType> {
-    let manager = Arc::new(find_source_file(
+/// Parse the task components relative to the communication task type. Returns `None` if the task
+/// doesn't have a manager.
+fn parse_communication_task_data(
+    task_dir: &Path,
+    yaml: &TaskYAML,
+) -> Result<Option<TaskType>, Error> {
+    let manager = match find_source_file(
         task_dir,
         vec!["check/manager.*", "cor/manager.*"],
         task_dir,
         None,
         Some(task_dir.join("bin").join("manager")),
-    )?);
+    ) {
+        Some(manager) => Arc::new(manager),
+        None => return Ok(None),
+    };
+    let user_io = match yaml.user_io.as_ref().map(String::as_str) {
+        None | Some("fifo_io") => UserIo::FifoIo,
+        Some("std_io") => UserIo::StdIo,
+        Some(user_io) => bail!("Unsupported user_io: {}", user_io),
+    };
+    let manager_type = match yaml.manager_type.as_ref().map(String::as_str) {
+        Some("cms") => ManagerType::Cms,
+        Some("testlib") => ManagerType::Testlib,
+        Some(manager_type) => bail!("Unsupported manager type: {}", manager_type),
+        None => {
+            let source = fs::read(&manager.path).unwrap_or_default();
+            if String::from_utf8_lossy(&source).contains("testlib.h") {
+                ManagerType::Testlib
+            } else {
+                ManagerType::Cms
+            }
+        }
+    };
+    let num_processes = yaml.num_processes.unwrap_or(1);
+    if manager_type == ManagerType::Testlib && num_processes != 1 {
+        bail!("A testlib interactor supports only one process of the solution");
+    }
 
-    Some(TaskType::Communication(CommunicationTypeData {
+    Ok(Some(TaskType::Communication(CommunicationTypeData {
         manager,
-        num_processes: yaml.num_processes.unwrap_or(1),
-    }))
+        num_processes,
+        user_io,
+        manager_type,
+    })))
 }
 
 /// Serializer of a boolean using the python syntax:
@@ -628,3 +1006,66 @@ fn default_infile() -> String {
 fn default_outfile() -> String {
     "output.txt".into()
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_check_limits_multipliers() {
+        let mut multipliers = HashMap::new();
+        multipliers.insert("Python".to_string(), LimitsMultipliers::default());
+        multipliers.insert("Java / JDK".to_string(), LimitsMultipliers::default());
+        assert!(check_limits_multipliers(&multipliers).is_ok());
+    }
+
+    #[test]
+    fn test_check_limits_multipliers_unknown() {
+        let mut multipliers = HashMap::new();
+        multipliers.insert("Pyhton".to_string(), LimitsMultipliers::default());
+        let err = check_limits_multipliers(&multipliers).unwrap_err();
+        assert!(err
+            .to_string()
+            .contains("Unknown language in limits_multipliers: Pyhton"));
+    }
+
+    fn checker_yaml(checker_type: &str) -> TaskYAML {
+        serde_yaml::from_str(&format!(
+            "name: task\ntitle: Task\nchecker_type: {}\n",
+            checker_type
+        ))
+        .unwrap()
+    }
+
+    #[test]
+    fn test_detect_checker_unsupported_type() {
+        let tmpdir = tempdir::TempDir::new("tm-test").unwrap();
+        std::fs::create_dir(tmpdir.path().join("check")).unwrap();
+        std::fs::write(tmpdir.path().join("check/checker.py"), "").unwrap();
+        let err = detect_checker(tmpdir.path(), &checker_yaml("foo")).unwrap_err();
+        assert!(err.to_string().contains("Unsupported checker type: foo"));
+        // the type is checked even without a custom checker
+        let tmpdir = tempdir::TempDir::new("tm-test").unwrap();
+        let err = detect_checker(tmpdir.path(), &checker_yaml("foo")).unwrap_err();
+        assert!(err.to_string().contains("Unsupported checker type: foo"));
+    }
+
+    #[test]
+    fn test_detect_checker_missing_custom_checker() {
+        let tmpdir = tempdir::TempDir::new("tm-test").unwrap();
+        for checker_type in &["cms", "testlib"] {
+            let err = detect_checker(tmpdir.path(), &checker_yaml(checker_type)).unwrap_err();
+            assert!(err.to_string().contains("no checker was found"));
+        }
+        std::fs::create_dir(tmpdir.path().join("check")).unwrap();
+        std::fs::write(tmpdir.path().join("check/checker.py"), "").unwrap();
+        match detect_checker(tmpdir.path(), &checker_yaml("testlib")).unwrap() {
+            Checker::Testlib(_) => {}
+            checker => panic!("Invalid checker: {:?}", checker),
+        }
+        match detect_checker(tmpdir.path(), &checker_yaml("cms")).unwrap() {
+            Checker::Custom(_) => {}
+            checker => panic!("Invalid checker: {:?}", checker),
+        }
+    }
+}
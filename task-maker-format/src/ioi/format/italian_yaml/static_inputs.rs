@@ -13,7 +13,7 @@ use crate::ioi::{
 struct StaticInputIter<V, O>
 where
     V: Fn(SubtaskId) -> InputValidator,
-    O: Fn(TestcaseId) -> OutputGenerator,
+    O: Fn(TestcaseId, Option<&str>) -> OutputGenerator,
 {
     /// The path to the input files directory.
     path: PathBuf,
@@ -28,7 +28,7 @@ where
 impl<V, O> Iterator for StaticInputIter<V, O>
 where
     V: Fn(SubtaskId) -> InputValidator,
-    O: Fn(TestcaseId) -> OutputGenerator,
+    O: Fn(TestcaseId, Option<&str>) -> OutputGenerator,
 {
     type Item = TaskInputEntry;
 
@@ -40,6 +40,8 @@ where
                 id: 0,
                 description: Some("Static testcases".into()),
                 max_score: 100.0,
+                time_limit: None,
+                memory_limit: None,
                 testcases: HashMap::new(),
             }));
         }
@@ -49,9 +51,11 @@ where
             self.index += 1;
             Some(TaskInputEntry::Testcase(TestcaseInfo {
                 id,
+                name: None,
+                weight: 1.0,
                 input_generator: InputGenerator::StaticFile(path),
                 input_validator: (self.get_validator)(0),
-                output_generator: (self.get_output_gen)(id),
+                output_generator: (self.get_output_gen)(id, None),
             }))
         } else {
             None
@@ -71,7 +75,7 @@ pub(crate) fn static_inputs<P: Into<PathBuf>, V, O>(
 ) -> Box<dyn Iterator<Item = TaskInputEntry>>
 where
     V: Fn(SubtaskId) -> InputValidator + 'static,
-    O: Fn(TestcaseId) -> OutputGenerator + 'static,
+    O: Fn(TestcaseId, Option<&str>) -> OutputGenerator + 'static,
 {
     Box::new(StaticInputIter {
         path: task_dir.into().join("input"),
@@ -94,7 +98,7 @@ mod tests {
         InputValidator::AssumeValid
     }
 
-    fn get_output_generator(_testcase: TestcaseId) -> OutputGenerator {
+    fn get_output_generator(_testcase: TestcaseId, _name: Option<&str>) -> OutputGenerator {
         OutputGenerator::StaticFile(PathBuf::from("foooo"))
     }
 
@@ -0,0 +1,605 @@
+//! Suggestion of the time and memory limits of a task from the resources used by the solutions.
+//!
+//! The evaluated solutions are split in two groups: the correct ones, that should fit in the
+//! limits, and the too slow ones, that should exceed the time limit. A solution is correct on the
+//! subtasks where it is annotated with `@check-accepted` and too slow on the subtasks where it is
+//! annotated with `@check-time-limit-exceeded`. The configuration can mark some solutions as
+//! correct or too slow on all the subtasks, overriding their annotations.
+//!
+//! The suggested time limit is placed halfway, on a logarithmic scale, between the slowest correct
+//! solution and the fastest too slow one, so that the two margins are equal. If no too slow
+//! solution is known, the time limit leaves a fixed margin to the slowest correct solution. The
+//! suggested memory limit leaves a fixed margin to the memory used by the correct solutions. All
+//! the resources are normalized by the limit multipliers of the languages of the solutions.
+//!
+//! The time of a correct solution killed for exceeding the time limit is not known, so no limit is
+//! suggested in that case: the task should be evaluated again with a larger time limit.
+
+use std::collections::HashSet;
+use std::path::PathBuf;
+use std::sync::{Arc, Mutex};
+
+use failure::{bail, format_err, Error};
+use serde::{Deserialize, Serialize};
+use typescript_definitions::TypeScriptify;
+
+use task_maker_dag::ExecutionStatus;
+
+use crate::ioi::{
+    IOITask, LimitsMultipliers, ScoreManager, SolutionCheck, SolutionCheckResult, TestcaseId,
+};
+use crate::EvaluationConfig;
+
+/// Without a too slow solution, the time limit is this many times the time of the slowest correct
+/// solution.
+const TIME_MARGIN: f64 = 2.0;
+/// The memory limit is this many times the memory used by the correct solutions.
+const MEMORY_MARGIN: f64 = 2.0;
+/// If the fastest too slow solution takes less than this many times the time of the slowest
+/// correct solution, the time limit cannot separate them reliably.
+const MIN_TIME_GAP: f64 = 2.0;
+/// The suggested time limit is a multiple of this value, in seconds.
+const TIME_STEP: f64 = 0.1;
+/// The suggested memory limit is a multiple of this value, in MiB.
+const MEMORY_STEP: u64 = 32;
+
+/// A solution whose resources are used for suggesting the limits.
+#[derive(Debug)]
+pub struct LimitSuggestionSource {
+    /// The path of the solution.
+    solution: PathBuf,
+    /// The multipliers of the limits of the language of the solution.
+    multipliers: LimitsMultipliers,
+    /// The testcases where the solution should fit in the limits.
+    correct: HashSet<TestcaseId>,
+    /// The testcases where the solution should exceed the time limit.
+    too_slow: HashSet<TestcaseId>,
+    /// The manager of the results of the solution.
+    score_manager: Arc<Mutex<ScoreManager>>,
+}
+
+/// The time used by a solution on the testcases relevant for the suggestion.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, TypeScriptify)]
+pub struct SolutionTiming {
+    /// The path of the solution.
+    pub solution: PathBuf,
+    /// The largest time used on the testcases, in seconds, normalized by the multiplier of the
+    /// language.
+    pub time: f64,
+    /// Whether the solution has been killed for exceeding the time limit: its actual time is at
+    /// least `time`.
+    pub killed: bool,
+}
+
+/// The limits suggested for a task, together with the timings they are based on.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, TypeScriptify)]
+pub struct LimitSuggestion {
+    /// The suggested time limit, in seconds.
+    pub time_limit: f64,
+    /// The suggested memory limit, in MiB.
+    pub memory_limit: u64,
+    /// The slowest among the correct solutions.
+    pub slowest_correct: SolutionTiming,
+    /// The fastest among the too slow solutions, if any.
+    pub fastest_too_slow: Option<SolutionTiming>,
+    /// The largest memory used by the correct solutions, in MiB, normalized by the multiplier of
+    /// the language.
+    pub correct_memory: f64,
+}
+
+impl LimitSuggestionSource {
+    /// Make the source for a solution, choosing the testcases where it is correct and where it is
+    /// too slow from its checks and from the configuration. Returns `None` if the solution is
+    /// neither correct nor too slow anywhere.
+    pub(crate) fn new(
+        task: &IOITask,
+        solution: PathBuf,
+        language: &str,
+        checks: &[SolutionCheck],
+        config: &EvaluationConfig,
+        score_manager: Arc<Mutex<ScoreManager>>,
+    ) -> Option<LimitSuggestionSource> {
+        let name = solution
+            .file_name()
+            .map(|name| name.to_string_lossy().to_string())
+            .unwrap_or_default();
+        let listed = |prefixes: &[String]| {
+            prefixes
+                .iter()
+                .any(|prefix| name.starts_with(prefix.as_str()))
+        };
+        let testcases = |result: Option<SolutionCheckResult>| -> HashSet<TestcaseId> {
+            task.subtasks
+                .values()
+                .filter(|subtask| match result {
+                    Some(result) => checks
+                        .iter()
+                        .any(|check| check.result == result && check.matches_subtask(subtask.id)),
+                    None => true,
+                })
+                .flat_map(|subtask| subtask.testcases.keys().cloned())
+                .collect()
+        };
+        let (correct, too_slow) = if listed(&config.correct_solutions) {
+            (testcases(None), HashSet::new())
+        } else if listed(&config.too_slow_solutions) {
+            (HashSet::new(), testcases(None))
+        } else {
+            (
+                testcases(Some(SolutionCheckResult::Accepted)),
+                testcases(Some(SolutionCheckResult::TimeLimitExceeded)),
+            )
+        };
+        if correct.is_empty() && too_slow.is_empty() {
+            return None;
+        }
+        Some(LimitSuggestionSource {
+            solution,
+            multipliers: task.language_multipliers(language),
+            correct,
+            too_slow,
+            score_manager,
+        })
+    }
+
+    /// The time used by the solution on the provided testcases, `None` if the solution has no
+    /// results on them.
+    fn timing(
+        &self,
+        score_manager: &ScoreManager,
+        testcases: &HashSet<TestcaseId>,
+    ) -> Option<SolutionTiming> {
+        let mut timing: Option<SolutionTiming> = None;
+        for testcase in testcases {
+            let resources = match score_manager.testcase_resources.get(testcase) {
+                Some(resources) => resources,
+                None => continue,
+            };
+            // the time limit is enforced on the user time only
+            let time = resources.cpu_time / self.multipliers.time;
+            let killed = match score_manager.testcase_statuses.get(testcase) {
+                Some(ExecutionStatus::TimeLimitExceeded)
+                | Some(ExecutionStatus::SysTimeLimitExceeded)
+                | Some(ExecutionStatus::WallTimeLimitExceeded) => true,
+                _ => false,
+            };
+            let timing = timing.get_or_insert_with(|| SolutionTiming {
+                solution: self.solution.clone(),
+                time,
+                killed,
+            });
+            timing.time = timing.time.max(time);
+            timing.killed |= killed;
+        }
+        timing
+    }
+
+    /// The memory used by the solution on the provided testcases, in MiB.
+    fn memory(&self, score_manager: &ScoreManager, testcases: &HashSet<TestcaseId>) -> f64 {
+        testcases
+            .iter()
+            .filter_map(|testcase| score_manager.testcase_resources.get(testcase))
+            .map(|resources| resources.memory as f64 / 1024.0 / self.multipliers.memory)
+            .fold(0.0, f64::max)
+    }
+}
+
+impl LimitSuggestion {
+    /// Suggest the limits from the results of the provided solutions. Fails if no correct solution
+    /// has been evaluated or if the slowest one has been killed for exceeding the time limit.
+    pub(crate) fn compute(sources: &[LimitSuggestionSource]) -> Result<LimitSuggestion, Error> {
+        let mut slowest_correct: Option<SolutionTiming> = None;
+        let mut fastest_too_slow: Option<SolutionTiming> = None;
+        let mut correct_memory = 0.0;
+        for source in sources {
+            let score_manager = source.score_manager.lock().unwrap();
+            if let Some(timing) = source.timing(&score_manager, &source.correct) {
+                if slowest_correct
+                    .as_ref()
+                    .map_or(true, |t| timing.time > t.time)
+                {
+                    slowest_correct = Some(timing);
+                }
+                correct_memory = f64::max(
+                    correct_memory,
+                    source.memory(&score_manager, &source.correct),
+                );
+            }
+            if let Some(timing) = source.timing(&score_manager, &source.too_slow) {
+                if fastest_too_slow
+                    .as_ref()
+                    .map_or(true, |t| timing.time < t.time)
+                {
+                    fastest_too_slow = Some(timing);
+                }
+            }
+        }
+        let slowest_correct =
+            slowest_correct.ok_or_else(|| format_err!("no correct solution has been evaluated"))?;
+        if slowest_correct.killed {
+            bail!(
+                "the correct solution {} has been killed after {:.3}s for exceeding the time \
+                 limit, evaluate it again with a larger time limit",
+                slowest_correct.solution.display(),
+                slowest_correct.time
+            );
+        }
+        let time_limit = match &fastest_too_slow {
+            Some(too_slow) if too_slow.time > slowest_correct.time => {
+                let time_limit = (slowest_correct.time * too_slow.time).sqrt();
+                // do not let the rounding cross the too slow solution
+                let rounded = round_up(time_limit, TIME_STEP);
+                if rounded < too_slow.time {
+                    rounded
+                } else {
+                    time_limit
+                }
+            }
+            _ => round_up(slowest_correct.time * TIME_MARGIN, TIME_STEP),
+        };
+        let memory_limit = round_up(correct_memory * MEMORY_MARGIN, MEMORY_STEP as f64) as u64;
+        Ok(LimitSuggestion {
+            time_limit,
+            memory_limit,
+            slowest_correct,
+            fastest_too_slow,
+            correct_memory,
+        })
+    }
+
+    /// How many times the time limit is larger than the time of the slowest correct solution,
+    /// `None` if that solution took no time.
+    pub fn time_margin(&self) -> Option<f64> {
+        if self.slowest_correct.time > 0.0 {
+            Some(self.time_limit / self.slowest_correct.time)
+        } else {
+            None
+        }
+    }
+
+    /// How many times the time of the fastest too slow solution is larger than the time limit.
+    pub fn too_slow_margin(&self) -> Option<f64> {
+        self.fastest_too_slow
+            .as_ref()
+            .map(|too_slow| too_slow.time / self.time_limit)
+    }
+
+    /// How many times the memory limit is larger than the memory used by the correct solutions,
+    /// `None` if they used no memory.
+    pub fn memory_margin(&self) -> Option<f64> {
+        if self.correct_memory > 0.0 {
+            Some(self.memory_limit as f64 / self.correct_memory)
+        } else {
+            None
+        }
+    }
+
+    /// The problems of the suggested limits, for example when the correct and the too slow
+    /// solutions are too close to be told apart.
+    pub fn warnings(&self) -> Vec<String> {
+        let too_slow = match &self.fastest_too_slow {
+            Some(too_slow) => too_slow,
+            None => return vec![],
+        };
+        let name = |timing: &SolutionTiming| {
+            timing
+                .solution
+                .file_name()
+                .map(|name| name.to_string_lossy().to_string())
+                .unwrap_or_default()
+        };
+        let gap = too_slow.time / self.slowest_correct.time;
+        if too_slow.time <= self.slowest_correct.time {
+            vec![format!(
+                "The too slow solution {} ({:.3}s) is not slower than the correct solution {} \
+                 ({:.3}s): no time limit can tell them apart",
+                name(too_slow),
+                too_slow.time,
+                name(&self.slowest_correct),
+                self.slowest_correct.time
+            )]
+        } else if gap < MIN_TIME_GAP && !too_slow.killed {
+            vec![format!(
+                "The too slow solution {} ({:.3}s) takes only {:.2}x the time of the correct \
+                 solution {} ({:.3}s): the margins of the time limit are too narrow",
+                name(too_slow),
+                too_slow.time,
+                gap,
+                name(&self.slowest_correct),
+                self.slowest_correct.time
+            )]
+        } else {
+            vec![]
+        }
+    }
+}
+
+/// Round the value up to a positive multiple of `step`.
+fn round_up(value: f64, step: f64) -> f64 {
+    // the epsilon avoids rounding up values that are already multiples of step
+    let steps = (value / step - 1e-9).ceil().max(1.0);
+    steps * step
+}
+
+#[cfg(test)]
+mod tests {
+    use task_maker_dag::ExecutionResourcesUsage;
+
+    use crate::ioi::dag::tests::make_task_with_subtasks;
+
+    use super::*;
+
+    fn make_task() -> IOITask {
+        let mut task = make_task_with_subtasks("/task");
+        task.time_limit = Some(1.0);
+        task
+    }
+
+    /// Make the source of a solution that used the provided time (in seconds) and memory (in MiB)
+    /// on each testcase.
+    fn make_source(
+        task: &IOITask,
+        name: &str,
+        checks: &str,
+        config: &EvaluationConfig,
+        resources: &[(f64, u64, ExecutionStatus)],
+    ) -> Option<LimitSuggestionSource> {
+        let mut manager = ScoreManager::new(task);
+        for (tc, (time, memory, status)) in resources.iter().enumerate() {
+            let tc = tc as TestcaseId;
+            manager.execution_status(tc, status);
+            manager.execution_resources(
+                tc,
+                &ExecutionResourcesUsage {
+                    cpu_time: *time,
+                    // the time limit doesn't include the system time
+                    sys_time: 1.0,
+                    wall_time: *time,
+                    memory: memory * 1024,
+                },
+            );
+        }
+        let checks = SolutionCheck::parse(checks).unwrap();
+        LimitSuggestionSource::new(
+            task,
+            name.into(),
+            "C++",
+            &checks,
+            config,
+            Arc::new(Mutex::new(manager)),
+        )
+    }
+
+    fn ok(time: f64, memory: u64) -> (f64, u64, ExecutionStatus) {
+        (time, memory, ExecutionStatus::Success)
+    }
+
+    #[test]
+    fn test_suggest_limits() {
+        let task = make_task();
+        let config = EvaluationConfig::default();
+        let sources = vec![
+            make_source(
+                &task,
+                "sol.cpp",
+                "// @check-accepted: *",
+                &config,
+                &[ok(0.1, 4), ok(0.2, 4), ok(0.3, 10), ok(0.4, 6)],
+            )
+            .unwrap(),
+            make_source(
+                &task,
+                "slow.cpp",
+                "// @check-accepted: st0\n// @check-time-limit-exceeded: st1",
+                &config,
+                &[ok(0.1, 50), ok(0.1, 50), ok(0.2, 50), ok(1.6, 50)],
+            )
+            .unwrap(),
+        ];
+        let suggestion = LimitSuggestion::compute(&sources).unwrap();
+        assert_eq!(
+            suggestion.slowest_correct.solution,
+            PathBuf::from("sol.cpp")
+        );
+        assert_abs_diff_eq!(suggestion.slowest_correct.time, 0.4);
+        let too_slow = suggestion.fastest_too_slow.as_ref().unwrap();
+        assert_eq!(too_slow.solution, PathBuf::from("slow.cpp"));
+        assert_abs_diff_eq!(too_slow.time, 1.6);
+        assert_abs_diff_eq!(suggestion.time_limit, 0.8);
+        assert_abs_diff_eq!(suggestion.time_margin().unwrap(), 2.0);
+        assert_abs_diff_eq!(suggestion.too_slow_margin().unwrap(), 2.0);
+        // slow.cpp is correct on subtask 0
+        assert_abs_diff_eq!(suggestion.correct_memory, 50.0);
+        assert_eq!(suggestion.memory_limit, 128);
+        assert_abs_diff_eq!(suggestion.memory_margin().unwrap(), 2.56);
+        assert!(suggestion.warnings().is_empty());
+    }
+
+    #[test]
+    fn test_suggest_limits_without_too_slow() {
+        let task = make_task();
+        let config = EvaluationConfig::default();
+        let sources = vec![make_source(
+            &task,
+            "sol.cpp",
+            "// @check-accepted: *",
+            &config,
+            &[ok(0.1, 1), ok(0.2, 1), ok(0.42, 1), ok(0.3, 1)],
+        )
+        .unwrap()];
+        let suggestion = LimitSuggestion::compute(&sources).unwrap();
+        assert_abs_diff_eq!(suggestion.time_limit, 0.9);
+        assert_eq!(suggestion.memory_limit, 32);
+        assert!(suggestion.fastest_too_slow.is_none());
+        assert!(suggestion.warnings().is_empty());
+    }
+
+    #[test]
+    fn test_suggest_limits_narrow_margin() {
+        let task = make_task();
+        let config = EvaluationConfig::default();
+        let sources = vec![
+            make_source(
+                &task,
+                "sol.cpp",
+                "// @check-accepted: *",
+                &config,
+                &[ok(1.0, 1), ok(1.0, 1), ok(1.0, 1), ok(1.0, 1)],
+            )
+            .unwrap(),
+            make_source(
+                &task,
+                "slow.cpp",
+                "// @check-time-limit-exceeded: *",
+                &config,
+                &[ok(1.5, 1), ok(1.5, 1), ok(1.5, 1), ok(1.5, 1)],
+            )
+            .unwrap(),
+        ];
+        let suggestion = LimitSuggestion::compute(&sources).unwrap();
+        assert!(suggestion.time_limit > 1.0 && suggestion.time_limit < 1.5);
+        let warnings = suggestion.warnings();
+        assert_eq!(warnings.len(), 1);
+        assert!(warnings[0].contains("too narrow"));
+    }
+
+    #[test]
+    fn test_suggest_limits_overlapping() {
+        let task = make_task();
+        let config = EvaluationConfig::default();
+        let sources = vec![
+            make_source(
+                &task,
+                "sol.cpp",
+                "// @check-accepted: *",
+                &config,
+                &[ok(1.0, 1), ok(1.0, 1), ok(1.0, 1), ok(1.0, 1)],
+            )
+            .unwrap(),
+            make_source(
+                &task,
+                "slow.cpp",
+                "// @check-time-limit-exceeded: *",
+                &config,
+                &[ok(0.5, 1), ok(0.5, 1), ok(0.5, 1), ok(0.5, 1)],
+            )
+            .unwrap(),
+        ];
+        let suggestion = LimitSuggestion::compute(&sources).unwrap();
+        assert_abs_diff_eq!(suggestion.time_limit, 2.0);
+        let warnings = suggestion.warnings();
+        assert_eq!(warnings.len(), 1);
+        assert!(warnings[0].contains("no time limit can tell them apart"));
+    }
+
+    #[test]
+    fn test_suggest_limits_killed() {
+        let task = make_task();
+        let config = EvaluationConfig::default();
+        let tle = (1.0, 1, ExecutionStatus::TimeLimitExceeded);
+        let sources = vec![
+            make_source(
+                &task,
+                "sol.cpp",
+                "// @check-accepted: *",
+                &config,
+                &[ok(0.8, 1), ok(0.8, 1), ok(0.8, 1), ok(0.8, 1)],
+            )
+            .unwrap(),
+            make_source(
+                &task,
+                "slow.cpp",
+                "// @check-time-limit-exceeded: *",
+                &config,
+                &[ok(0.5, 1), tle.clone(), tle.clone(), tle],
+            )
+            .unwrap(),
+        ];
+        let suggestion = LimitSuggestion::compute(&sources).unwrap();
+        assert!(suggestion.fastest_too_slow.as_ref().unwrap().killed);
+        // the actual time of the too slow solution is unknown, the margin may be larger
+        assert!(suggestion.warnings().is_empty());
+    }
+
+    #[test]
+    fn test_suggest_limits_correct_killed() {
+        let task = make_task();
+        let config = EvaluationConfig::default();
+        let tle = (1.0, 1, ExecutionStatus::TimeLimitExceeded);
+        let sources = vec![make_source(
+            &task,
+            "sol.cpp",
+            "// @check-accepted: *",
+            &config,
+            &[ok(0.8, 1), ok(0.8, 1), ok(0.8, 1), tle],
+        )
+        .unwrap()];
+        let err = LimitSuggestion::compute(&sources).unwrap_err();
+        assert!(err.to_string().contains("killed"));
+    }
+
+    #[test]
+    fn test_suggest_limits_no_resources() {
+        let task = make_task();
+        let config = EvaluationConfig::default();
+        let sources = vec![make_source(
+            &task,
+            "sol.cpp",
+            "// @check-accepted: *",
+            &config,
+            &[ok(0.0, 0), ok(0.0, 0), ok(0.0, 0), ok(0.0, 0)],
+        )
+        .unwrap()];
+        let suggestion = LimitSuggestion::compute(&sources).unwrap();
+        assert!(suggestion.time_margin().is_none());
+        assert!(suggestion.memory_margin().is_none());
+    }
+
+    #[test]
+    fn test_suggest_limits_no_correct() {
+        let task = make_task();
+        let config = EvaluationConfig::default();
+        let sources = vec![make_source(
+            &task,
+            "slow.cpp",
+            "// @check-time-limit-exceeded: *",
+            &config,
+            &[ok(2.0, 1), ok(2.0, 1), ok(2.0, 1), ok(2.0, 1)],
+        )
+        .unwrap()];
+        assert!(LimitSuggestion::compute(&sources).is_err());
+    }
+
+    #[test]
+    fn test_suggestion_source_config() {
+        let task = make_task();
+        let config = EvaluationConfig {
+            correct_solutions: vec!["sol".into()],
+            too_slow_solutions: vec!["bf".into()],
+            ..Default::default()
+        };
+        let resources = [ok(0.1, 1), ok(0.1, 1), ok(0.1, 1), ok(0.1, 1)];
+        let source = make_source(&task, "sol2.cpp", "", &config, &resources).unwrap();
+        assert_eq!(source.correct.len(), 4);
+        assert!(source.too_slow.is_empty());
+        let source = make_source(
+            &task,
+            "bf.cpp",
+            "// @check-accepted: *",
+            &config,
+            &resources,
+        );
+        let source = source.unwrap();
+        assert!(source.correct.is_empty());
+        assert_eq!(source.too_slow.len(), 4);
+        assert!(make_source(&task, "wrong.cpp", "", &config, &resources).is_none());
+        let source = make_source(
+            &task,
+            "wrong.cpp",
+            "// @check-wrong-answer: st1",
+            &config,
+            &resources,
+        );
+        assert!(source.is_none());
+    }
+}
@@ -15,20 +15,24 @@
 //! a `Checker`, a program that computes the score of the testcase given the input file, the output
 //! file and the _correct_ output file (the one produced by the jury).
 
-use std::collections::HashMap;
+use std::collections::{HashMap, HashSet};
 use std::path::{Path, PathBuf};
 use std::str::FromStr;
 use std::sync::{Arc, Mutex};
 
 use failure::{bail, format_err, Error};
+use itertools::Itertools;
 use serde::{Deserialize, Serialize};
 use typescript_definitions::TypeScriptify;
 
 use curses_ui::CursesUI;
 pub use dag::*;
 pub use format::italian_yaml;
+pub use limit_suggestion::*;
+pub use solution_checks::*;
 pub use statement::*;
 pub use task_info::*;
+use task_maker_dag::{ExecutionResourcesUsage, ExecutionStatus};
 use task_maker_lang::GraderMap;
 pub use ui_state::*;
 
@@ -36,13 +40,17 @@ use crate::ioi::format::italian_yaml::TM_ALLOW_DELETE_COOKIE;
 use crate::ioi::italian_yaml::is_gen_gen_deletable;
 use crate::sanity_checks::SanityChecks;
 use crate::ui::*;
-use crate::{EvaluationConfig, EvaluationData, TaskFormat, TaskInfo, UISender};
+use crate::{
+    EvaluationConfig, EvaluationData, TaskCompilerSettings, TaskFormat, TaskInfo, UISender,
+};
 
 mod curses_ui;
 mod dag;
 pub(crate) mod finish_ui;
 mod format;
+mod limit_suggestion;
 pub mod sanity_checks;
+mod solution_checks;
 mod statement;
 pub(crate) mod task_info;
 pub(crate) mod ui_state;
@@ -62,6 +70,12 @@ pub struct ScoreManager {
     max_subtask_scores: HashMap<SubtaskId, f64>,
     /// The scores of each testcase.
     testcase_scores: HashMap<SubtaskId, HashMap<TestcaseId, Option<f64>>>,
+    /// The weight of each testcase.
+    testcase_weights: HashMap<TestcaseId, f64>,
+    /// The status of the execution of the solution on each testcase.
+    testcase_statuses: HashMap<TestcaseId, ExecutionStatus>,
+    /// The resources used by the solution on each testcase.
+    testcase_resources: HashMap<TestcaseId, ExecutionResourcesUsage>,
     /// The aggregator to use for computing the subtask scores.
     aggregator: TestcaseScoreAggregator,
 }
@@ -81,6 +95,12 @@ pub struct IOITask {
     pub time_limit: Option<f64>,
     /// The memory limit in MiB of the execution of the solution, if `None` it's unlimited.
     pub memory_limit: Option<u64>,
+    /// The multipliers of the limits for the solutions in some languages, keyed by the name of the
+    /// language.
+    pub limits_multipliers: HashMap<String, LimitsMultipliers>,
+    /// The settings of the compilers of the source files of the task.
+    #[serde(default)]
+    pub compiler_settings: TaskCompilerSettings,
     /// The input file for the solutions, usually `Some("input.txt")` or `None` (stdin).
     pub infile: Option<PathBuf>,
     /// The output file for the solutions, usually `Some("output.txt")` or `None` (stdout).
@@ -118,10 +138,26 @@ pub struct SubtaskInfo {
     pub description: Option<String>,
     /// The maximum score of the subtask, must be >= 0.
     pub max_score: f64,
+    /// The time limit for the solutions in this subtask, overriding the one of the task. A testcase
+    /// part of many subtasks is evaluated only once, with the limits of the first subtask.
+    pub time_limit: Option<f64>,
+    /// The memory limit in MiB for the solutions in this subtask, overriding the one of the task.
+    pub memory_limit: Option<u64>,
     /// The testcases inside this subtask.
     pub testcases: HashMap<TestcaseId, TestcaseInfo>,
 }
 
+/// The multipliers of the time and memory limits for the solutions in a language, for example
+/// because the language is slower than the others.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, TypeScriptify)]
+#[serde(default)]
+pub struct LimitsMultipliers {
+    /// The multiplier of the time limit.
+    pub time: f64,
+    /// The multiplier of the memory limit.
+    pub memory: f64,
+}
+
 /// A testcase of a IOI task.
 ///
 /// Every testcase has an input and an output that will be put in the input/ and output/ folders.
@@ -130,6 +166,10 @@ pub struct SubtaskInfo {
 pub struct TestcaseInfo {
     /// The id of the testcase.
     pub id: TestcaseId,
+    /// The name of the testcase, if any, for recognizing it without counting the lines.
+    pub name: Option<String>,
+    /// The weight of the testcase in the score of its subtasks, used by the `sum` aggregator.
+    pub weight: f64,
     /// The generator of the input file for this testcase.
     pub input_generator: InputGenerator,
     /// The validator of the input file for this testcase.
@@ -138,6 +178,27 @@ pub struct TestcaseInfo {
     pub output_generator: OutputGenerator,
 }
 
+impl TestcaseInfo {
+    /// The name of the input file of this testcase inside the `input/` directory of the task.
+    pub fn input_file_name(&self) -> String {
+        testcase_file_name("input", self.id, self.name.as_deref())
+    }
+
+    /// The name of the output file of this testcase inside the `output/` directory of the task.
+    pub fn output_file_name(&self) -> String {
+        testcase_file_name("output", self.id, self.name.as_deref())
+    }
+}
+
+/// The name of an input or output file of a testcase: `input3.txt` for the testcase 3, or
+/// `input_worst.txt` if the testcase is named `worst`.
+pub(crate) fn testcase_file_name(kind: &str, id: TestcaseId, name: Option<&str>) -> String {
+    match name {
+        Some(name) => format!("{}_{}.txt", kind, name),
+        None => format!("{}{}.txt", kind, id),
+    }
+}
+
 impl IOITask {
     /// Try to make a `Task` from the specified path. Will return `Err` if the format of the task
     /// is not IOI or if the task is corrupted and cannot be parsed.
@@ -145,6 +206,59 @@ impl IOITask {
         format::italian_yaml::parse_task(path, eval_config)
     }
 
+    /// Whether the solutions of this task are output files instead of programs.
+    pub fn is_output_only(&self) -> bool {
+        match self.task_type {
+            TaskType::OutputOnly(_) => true,
+            _ => false,
+        }
+    }
+
+    /// The name of the testcase with the provided id, if it has one.
+    pub fn testcase_name(&self, testcase: TestcaseId) -> Option<&str> {
+        self.subtasks
+            .values()
+            .filter_map(|subtask| subtask.testcases.get(&testcase))
+            .find_map(|testcase| testcase.name.as_deref())
+    }
+
+    /// The time limit for the solutions in the specified language on the testcases of a subtask.
+    /// The limit of the subtask, if any, overrides the one of the task and it is scaled by the
+    /// multiplier of the language.
+    pub fn solution_time_limit(&self, subtask_id: SubtaskId, language: &str) -> Option<f64> {
+        let time_limit = self
+            .subtasks
+            .get(&subtask_id)
+            .and_then(|subtask| subtask.time_limit)
+            .or(self.time_limit)?;
+        Some(time_limit * self.language_multipliers(language).time)
+    }
+
+    /// The memory limit in MiB for the solutions in the specified language on the testcases of a
+    /// subtask. The limit of the subtask, if any, overrides the one of the task and it is scaled by
+    /// the multiplier of the language.
+    pub fn solution_memory_limit(&self, subtask_id: SubtaskId, language: &str) -> Option<u64> {
+        let memory_limit = self
+            .subtasks
+            .get(&subtask_id)
+            .and_then(|subtask| subtask.memory_limit)
+            .or(self.memory_limit)?;
+        let multiplier = self.language_multipliers(language).memory;
+        Some((memory_limit as f64 * multiplier).ceil() as u64)
+    }
+
+    /// The multipliers of the limits for the specified language. The language is searched first by
+    /// its full name (e.g. `Python / Autodetect`) and then by the part before the slash (e.g.
+    /// `Python`).
+    fn language_multipliers(&self, language: &str) -> LimitsMultipliers {
+        let short_name = language.split(" / ").next().unwrap_or(language);
+        self.limits_multipliers
+            .get(language)
+            .or_else(|| self.limits_multipliers.get(short_name))
+            .cloned()
+            .unwrap_or_default()
+    }
+
     /// Check if in the provided path there could be a IOI-like task.
     pub fn is_valid<P: AsRef<Path>>(path: P) -> bool {
         let path = path.as_ref();
@@ -174,28 +288,102 @@ impl TaskFormat for IOITask {
         eval.sender.send(UIMessage::IOITask {
             task: Box::new(self.clone()),
         })?;
+        eval.compiler_settings = self.compiler_settings.clone();
         self.sanity_checks.pre_hook(&self, eval)?;
         let empty_score_manager = ScoreManager::new(&self);
-        let solutions: Vec<_> = config
-            .filter_solutions(&self.path, vec!["sol/*"], Some(self.grader_map.clone()))
+        // in output only tasks the solutions are replaced by the submitted output files
+        let (solutions, submissions) = if let TaskType::OutputOnly(_) = &self.task_type {
+            (Vec::new(), OutputOnlySubmission::find(&self.path, config))
+        } else {
+            (
+                config.filter_solutions(&self.path, vec!["sol/*"], Some(self.grader_map.clone())),
+                Vec::new(),
+            )
+        };
+        let solutions: Vec<_> = solutions
+            .into_iter()
+            .map(|source| {
+                let repetitions = config.repetitions_of(&source.path);
+                let score_manager = Arc::new(Mutex::new(empty_score_manager.clone()));
+                (source, repetitions, score_manager)
+            })
+            .collect();
+        // the checks written in the solutions are verified after the evaluation
+        let mut solution_checks = Vec::new();
+        // the resources of the solutions are used for suggesting the limits after the evaluation
+        let mut limit_suggestion = if config.suggest_limits {
+            Some(Vec::new())
+        } else {
+            None
+        };
+        for (solution, _, score_manager) in solutions.iter() {
+            let checks = SolutionCheck::parse_file(&solution.path).map_err(|e| {
+                format_err!(
+                    "Invalid checks in solution {}: {}",
+                    solution.name(),
+                    e.to_string()
+                )
+            })?;
+            if let Some(sources) = limit_suggestion.as_mut() {
+                sources.extend(LimitSuggestionSource::new(
+                    &self,
+                    solution.path.clone(),
+                    solution.language().name(),
+                    &checks,
+                    config,
+                    score_manager.clone(),
+                ));
+            }
+            if !checks.is_empty() {
+                solution_checks.push(SolutionChecks::new(
+                    solution.path.clone(),
+                    checks,
+                    score_manager.clone(),
+                ));
+            }
+        }
+        let submissions: Vec<_> = submissions
             .into_iter()
-            .map(|source| (source, Arc::new(Mutex::new(empty_score_manager.clone()))))
+            .map(|sub| (sub, Arc::new(Mutex::new(empty_score_manager.clone()))))
             .collect();
 
-        for subtask in self.subtasks.values() {
+        // a testcase may be part of many subtasks, it's generated and evaluated only once, in the
+        // first subtask that contains it
+        let mut processed = HashSet::new();
+        // the named testcases are written to files with their name, which must be unique
+        let mut names: HashMap<&str, TestcaseId> = HashMap::new();
+        for subtask in self.subtasks.values().sorted_by_key(|st| st.id) {
             trace!("Executing the generation of subtask {}", subtask.id);
 
             for testcase in subtask.testcases.values() {
+                if !processed.insert(testcase.id) {
+                    continue;
+                }
+                if let Some(name) = &testcase.name {
+                    if name.contains('/') {
+                        bail!("Invalid name of testcase {}: {}", testcase.id, name);
+                    }
+                    if let Some(other) = names.insert(name, testcase.id) {
+                        bail!(
+                            "Testcases {} and {} have the same name: {}",
+                            other,
+                            testcase.id,
+                            name
+                        );
+                    }
+                }
                 trace!(
                     "Executing the generation of testcase {} of subtask {}",
                     testcase.id,
                     subtask.id
                 );
 
-                let input =
-                    testcase
-                        .input_generator
-                        .generate_and_bind(eval, subtask.id, testcase.id)?;
+                let input = testcase.input_generator.generate_and_bind(
+                    eval,
+                    subtask.id,
+                    testcase.id,
+                    &testcase.input_file_name(),
+                )?;
                 let val_handle = testcase.input_validator.validate_and_bind(
                     eval,
                     subtask.id,
@@ -207,11 +395,12 @@ impl TaskFormat for IOITask {
                     eval,
                     subtask.id,
                     testcase.id,
+                    &testcase.output_file_name(),
                     input,
                     val_handle,
                 )?;
 
-                for (solution, score_manager) in solutions.iter() {
+                for (solution, repetitions, score_manager) in solutions.iter() {
                     trace!(
                         "Evaluation of the solution {:?} against subtask {} / testcase {}",
                         solution.name(),
@@ -225,17 +414,68 @@ impl TaskFormat for IOITask {
                         subtask.id,
                         testcase.id,
                         solution,
+                        *repetitions,
                         input,
                         val_handle,
                         output,
                         score_manager.clone(),
                     )?;
                 }
+                for (submission, score_manager) in submissions.iter() {
+                    trace!(
+                        "Evaluation of the submission {:?} against subtask {} / testcase {}",
+                        submission.name(),
+                        subtask.id,
+                        testcase.id
+                    );
+
+                    self.task_type.evaluate_submission(
+                        eval,
+                        subtask.id,
+                        testcase.id,
+                        submission,
+                        input,
+                        output,
+                        score_manager.clone(),
+                    )?;
+                }
             }
         }
         for booklet in self.booklets.iter() {
             booklet.build(eval)?;
         }
+        if let Some(sources) = limit_suggestion {
+            eval.post_evaluation_hooks.push(Box::new(move |ui| {
+                match LimitSuggestion::compute(&sources) {
+                    Ok(suggestion) => {
+                        for message in suggestion.warnings() {
+                            ui.send(UIMessage::Warning { message })?;
+                        }
+                        ui.send(UIMessage::IOILimitSuggestion { suggestion })?;
+                    }
+                    Err(e) => ui.send(UIMessage::Warning {
+                        message: format!("Cannot suggest the limits: {}", e.to_string()),
+                    })?,
+                }
+                Ok(())
+            }));
+        }
+        if !solution_checks.is_empty() {
+            let task = self.clone();
+            eval.post_evaluation_hooks.push(Box::new(move |ui| {
+                let mut num_failed = 0;
+                for checks in solution_checks.iter() {
+                    for message in checks.check(&task) {
+                        ui.send(UIMessage::Error { message })?;
+                        num_failed += 1;
+                    }
+                }
+                if num_failed > 0 {
+                    bail!("{} solution checks failed", num_failed);
+                }
+                Ok(())
+            }));
+        }
         Ok(())
     }
 
@@ -289,14 +529,18 @@ impl TaskFormat for IOITask {
             std::fs::remove_dir_all(bin_path)?;
         }
         // remove the compiled checkers
-        if let TaskType::Batch(data) = &self.task_type {
-            if let Checker::Custom(_) = data.checker {
-                for checker in &["check/checker", "cor/correttore"] {
-                    let path = self.path.join(checker);
-                    if path.exists() {
-                        info!("Removing {:?}", path);
-                        std::fs::remove_file(path)?;
-                    }
+        let checker = match &self.task_type {
+            TaskType::Batch(data) => Some(&data.checker),
+            TaskType::OutputOnly(data) => Some(&data.checker),
+            TaskType::TwoSteps(data) => Some(&data.checker),
+            _ => None,
+        };
+        if let Some(Checker::Custom(_)) | Some(Checker::Testlib(_)) = checker {
+            for checker in &["check/checker", "cor/correttore"] {
+                let path = self.path.join(checker);
+                if path.exists() {
+                    info!("Removing {:?}", path);
+                    std::fs::remove_file(path)?;
                 }
             }
         }
@@ -322,13 +566,27 @@ impl TaskFormat for IOITask {
     }
 }
 
+impl Default for LimitsMultipliers {
+    fn default() -> Self {
+        LimitsMultipliers {
+            time: 1.0,
+            memory: 1.0,
+        }
+    }
+}
+
 impl FromStr for TestcaseScoreAggregator {
     type Err = Error;
 
     fn from_str(s: &str) -> Result<Self, Self::Err> {
         match s {
-            "min" => Ok(TestcaseScoreAggregator::Min),
-            "sum" => Ok(TestcaseScoreAggregator::Sum),
+            "min" | "GroupMin" => Ok(TestcaseScoreAggregator::Min),
+            "sum" | "Sum" | "GroupSum" => Ok(TestcaseScoreAggregator::Sum),
+            "mul" | "GroupMul" => Ok(TestcaseScoreAggregator::Mul),
+            "threshold" | "GroupThreshold" => bail!(
+                "The testcase score aggregator {} requires the thresholds of the subtasks",
+                s
+            ),
             _ => bail!("Invalid testcase score aggregator: {}", s),
         }
     }
@@ -349,6 +607,14 @@ impl ScoreManager {
                 .values()
                 .map(|st| (st.id, st.testcases.keys().map(|tc| (*tc, None)).collect()))
                 .collect(),
+            testcase_weights: task
+                .subtasks
+                .values()
+                .flat_map(|st| st.testcases.values())
+                .map(|tc| (tc.id, tc.weight))
+                .collect(),
+            testcase_statuses: HashMap::new(),
+            testcase_resources: HashMap::new(),
             aggregator: task.testcase_score_aggregator.clone(),
         }
     }
@@ -368,6 +634,15 @@ impl ScoreManager {
             .get_mut(&subtask_id)
             .ok_or_else(|| format_err!("Unknown subtask {}", subtask_id))?
             .insert(testcase_id, Some(score));
+        // the testcase may be included also in other subtasks, its score counts in all of them
+        let mut subtasks = Vec::new();
+        for (st, testcases) in self.testcase_scores.iter_mut() {
+            if let Some(tc_score) = testcases.get_mut(&testcase_id) {
+                *tc_score = Some(score);
+                subtasks.push(*st);
+            }
+        }
+        subtasks.sort();
         sender.send(UIMessage::IOITestcaseScore {
             subtask: subtask_id,
             testcase: testcase_id,
@@ -375,15 +650,11 @@ impl ScoreManager {
             score,
             message,
         })?;
-        if self.testcase_scores[&subtask_id]
-            .values()
-            .all(Option::is_some)
-        {
-            let normalized_score = self.aggregator.aggregate(
-                self.testcase_scores[&subtask_id]
-                    .values()
-                    .map(|score| score.unwrap()),
-            );
+        for subtask_id in subtasks {
+            let normalized_score = match self.normalized_subtask_score(subtask_id) {
+                Some(score) => score,
+                None => continue,
+            };
             let subtask_score = self.max_subtask_scores[&subtask_id] * normalized_score;
             self.subtask_scores.insert(subtask_id, Some(subtask_score));
             sender.send(UIMessage::IOISubtaskScore {
@@ -399,11 +670,54 @@ impl ScoreManager {
                     .map(|score| score.unwrap())
                     .sum();
                 sender.send(UIMessage::IOITaskScore {
-                    solution,
+                    solution: solution.clone(),
                     score: task_score,
                 })?;
             }
         }
         Ok(())
     }
+
+    /// Store the status of the execution of the solution on a testcase. When the solution is made
+    /// of many processes the first failure is kept.
+    pub fn execution_status(&mut self, testcase_id: TestcaseId, status: &ExecutionStatus) {
+        let current = self.testcase_statuses.get(&testcase_id);
+        if current.map_or(true, ExecutionStatus::is_success) {
+            self.testcase_statuses.insert(testcase_id, status.clone());
+        }
+    }
+
+    /// Store the resources used by the solution on a testcase. When the solution is made of many
+    /// processes the largest usage of each resource is kept, since the limits apply to each
+    /// process separately.
+    pub fn execution_resources(
+        &mut self,
+        testcase_id: TestcaseId,
+        resources: &ExecutionResourcesUsage,
+    ) {
+        let current = self
+            .testcase_resources
+            .entry(testcase_id)
+            .or_insert_with(|| resources.clone());
+        current.cpu_time = current.cpu_time.max(resources.cpu_time);
+        current.sys_time = current.sys_time.max(resources.sys_time);
+        current.wall_time = current.wall_time.max(resources.wall_time);
+        current.memory = current.memory.max(resources.memory);
+    }
+
+    /// The score of the subtask, from 0.0 to 1.0, if all its testcases have been scored.
+    fn normalized_subtask_score(&self, subtask_id: SubtaskId) -> Option<f64> {
+        let testcases = self.testcase_scores.get(&subtask_id)?;
+        if !testcases.values().all(Option::is_some) {
+            return None;
+        }
+        Some(
+            self.aggregator.aggregate(
+                subtask_id,
+                testcases
+                    .iter()
+                    .map(|(tc, score)| (score.unwrap(), self.testcase_weights[tc])),
+            ),
+        )
+    }
 }
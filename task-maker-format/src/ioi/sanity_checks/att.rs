@@ -167,20 +167,22 @@ impl SanityCheck<IOITask> for AttSampleFilesValid {
                 let correct_output =
                     correct_output.ok_or_else(|| format_err!("Missing official solution"))?;
                 if let Some(sol) = sol {
-                    let sender = eval.sender.clone();
-                    let output_name = output_name.clone();
-                    eval.dag.on_execution_done(&sol.uuid, move |res| {
-                        if !res.status.is_success() {
-                            sender.send(UIMessage::Warning {
-                                message: format!(
-                                    "Solution failed on sample input file {}",
-                                    output_name.display()
-                                ),
-                            })?;
-                        }
-                        Ok(())
-                    });
-                    eval.dag.add_execution(sol);
+                    for exec in &sol.executions {
+                        let sender = eval.sender.clone();
+                        let output_name = output_name.clone();
+                        eval.dag.on_execution_done(&exec.uuid, move |res| {
+                            if !res.status.is_success() {
+                                sender.send(UIMessage::Warning {
+                                    message: format!(
+                                        "Solution failed on sample input file {}",
+                                        output_name.display()
+                                    ),
+                                })?;
+                            }
+                            Ok(())
+                        });
+                    }
+                    eval.dag.add_execution_group(sol);
                 }
 
                 // validate the output with the correct one
@@ -1,6 +1,9 @@
+use std::collections::HashMap;
+
 use failure::Error;
+use itertools::Itertools;
 
-use crate::ioi::IOITask;
+use crate::ioi::{IOITask, SubtaskInfo, TestcaseId};
 use crate::sanity_checks::SanityCheck;
 use crate::ui::{UIMessage, UIMessageSender};
 use crate::{list_files, EvaluationData, UISender};
@@ -54,3 +57,40 @@ impl SanityCheck<IOITask> for BrokenSymlinks {
         Ok(())
     }
 }
+
+/// Check that the testcases included in many subtasks have the same limits in all of them. Those
+/// testcases are evaluated only once, with the limits of the first subtask.
+#[derive(Debug, Default)]
+pub struct IncludedTestcasesLimits;
+
+impl SanityCheck<IOITask> for IncludedTestcasesLimits {
+    fn name(&self) -> &'static str {
+        "IncludedTestcasesLimits"
+    }
+
+    fn pre_hook(&mut self, task: &IOITask, eval: &mut EvaluationData) -> Result<(), Error> {
+        let limits = |subtask: &SubtaskInfo| {
+            (
+                subtask.time_limit.or(task.time_limit),
+                subtask.memory_limit.or(task.memory_limit),
+            )
+        };
+        // the subtask where each testcase is evaluated
+        let mut evaluated_in: HashMap<TestcaseId, &SubtaskInfo> = HashMap::new();
+        for subtask in task.subtasks.values().sorted_by_key(|st| st.id) {
+            for testcase in subtask.testcases.keys().sorted() {
+                let first = *evaluated_in.entry(*testcase).or_insert(subtask);
+                if limits(first) != limits(subtask) {
+                    eval.sender.send(UIMessage::Warning {
+                        message: format!(
+                            "Testcase {} is evaluated with the limits of subtask {}, not with the \
+                             ones of subtask {}",
+                            testcase, first.id, subtask.id
+                        ),
+                    })?;
+                }
+            }
+        }
+        Ok(())
+    }
+}
@@ -0,0 +1,465 @@
+//! Checks on the expected outcome of the solutions.
+//!
+//! The source file of a solution may contain some annotations that tell which outcome the solution
+//! is expected to get on some subtasks, for example:
+//!
+//! ```text
+//! // @check-accepted: st0 st1
+//! // @check-wrong-answer: st2
+//! // @check-time-limit-exceeded: *
+//! ```
+//!
+//! Each annotation must be at the start of a comment, the text elsewhere in the source file is
+//! ignored.
+//!
+//! After the evaluation the annotations are compared with the actual results of the solution and
+//! every mismatch is reported as an error.
+//!
+//! The subtasks are specified by their id (optionally prefixed by `st`) and can be glob patterns,
+//! `*` matches all the subtasks.
+
+use std::path::{Path, PathBuf};
+use std::str::FromStr;
+use std::sync::{Arc, Mutex};
+
+use failure::{bail, format_err, Error};
+use itertools::Itertools;
+use regex::Regex;
+use serde::{Deserialize, Serialize};
+use typescript_definitions::TypeScriptify;
+
+use task_maker_dag::ExecutionStatus;
+
+use crate::ioi::{IOITask, ScoreManager, SubtaskId};
+
+lazy_static! {
+    /// The prefix of the annotations inside the source files of the solutions. The annotation has
+    /// to be at the start of a comment: `//`, `#`, `/*`, `(*`, `{`, `--` or the `*` at the start of
+    /// a line inside a block comment.
+    static ref CHECK_PREFIX: Regex =
+        Regex::new(r"(?:^\s*\*|//|#|/\*|\(\*|\{|--)\s*@check-").expect("Invalid regex");
+}
+
+/// The tolerance used when comparing the scores.
+const SCORE_EPSILON: f64 = 1e-6;
+
+/// The outcome that a solution is expected to get on a subtask.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, TypeScriptify)]
+pub enum SolutionCheckResult {
+    /// The solution gets all the points of the subtask.
+    Accepted,
+    /// The solution produces a wrong output (with score zero) on at least one testcase.
+    WrongAnswer,
+    /// The solution gets a partial score (strictly between zero and one) on at least one testcase.
+    PartialScore,
+    /// The solution exceeds the time limit on at least one testcase.
+    TimeLimitExceeded,
+    /// The solution exceeds the memory limit on at least one testcase.
+    MemoryLimitExceeded,
+    /// The solution crashes on at least one testcase.
+    RuntimeError,
+}
+
+/// A check on the outcome of a solution, parsed from an annotation of its source file.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, TypeScriptify)]
+pub struct SolutionCheck {
+    /// The expected outcome of the solution.
+    pub result: SolutionCheckResult,
+    /// The patterns of the subtasks this check refers to.
+    pub subtasks: Vec<String>,
+    /// The line of the source file where the check is written, 1-based.
+    pub line: usize,
+}
+
+/// The checks of a solution together with the `ScoreManager` that collects its results.
+#[derive(Debug)]
+pub struct SolutionChecks {
+    /// The path of the solution.
+    pub solution: PathBuf,
+    /// The checks written in the source file of the solution.
+    pub checks: Vec<SolutionCheck>,
+    /// The manager of the results of the solution.
+    score_manager: Arc<Mutex<ScoreManager>>,
+}
+
+impl SolutionCheckResult {
+    /// The name of the result as used in the annotations.
+    pub fn name(self) -> &'static str {
+        match self {
+            SolutionCheckResult::Accepted => "accepted",
+            SolutionCheckResult::WrongAnswer => "wrong-answer",
+            SolutionCheckResult::PartialScore => "partial-score",
+            SolutionCheckResult::TimeLimitExceeded => "time-limit-exceeded",
+            SolutionCheckResult::MemoryLimitExceeded => "memory-limit-exceeded",
+            SolutionCheckResult::RuntimeError => "runtime-error",
+        }
+    }
+
+    /// All the known results.
+    fn all() -> &'static [SolutionCheckResult] {
+        &[
+            SolutionCheckResult::Accepted,
+            SolutionCheckResult::WrongAnswer,
+            SolutionCheckResult::PartialScore,
+            SolutionCheckResult::TimeLimitExceeded,
+            SolutionCheckResult::MemoryLimitExceeded,
+            SolutionCheckResult::RuntimeError,
+        ]
+    }
+
+    /// Whether the results of a solution inside the `ScoreManager` satisfy this check on the
+    /// specified subtask.
+    fn check(self, score_manager: &ScoreManager, subtask_id: SubtaskId) -> bool {
+        let testcases = match score_manager.testcase_scores.get(&subtask_id) {
+            Some(testcases) => testcases,
+            None => return false,
+        };
+        let status = |testcase| score_manager.testcase_statuses.get(testcase);
+        let successful_scores = || {
+            testcases
+                .iter()
+                .filter(|(tc, _)| status(*tc).map_or(false, ExecutionStatus::is_success))
+                .filter_map(|(_, score)| *score)
+        };
+        let any_status = |f: fn(&ExecutionStatus) -> bool| {
+            testcases.keys().any(|tc| status(tc).map_or(false, f))
+        };
+        match self {
+            SolutionCheckResult::Accepted => score_manager
+                .normalized_subtask_score(subtask_id)
+                .map_or(false, |score| score >= 1.0 - SCORE_EPSILON),
+            SolutionCheckResult::WrongAnswer => {
+                successful_scores().any(|score| score.abs() < SCORE_EPSILON)
+            }
+            SolutionCheckResult::PartialScore => successful_scores()
+                .any(|score| score >= SCORE_EPSILON && score <= 1.0 - SCORE_EPSILON),
+            SolutionCheckResult::TimeLimitExceeded => any_status(|status| match status {
+                ExecutionStatus::TimeLimitExceeded
+                | ExecutionStatus::SysTimeLimitExceeded
+                | ExecutionStatus::WallTimeLimitExceeded => true,
+                _ => false,
+            }),
+            SolutionCheckResult::MemoryLimitExceeded => any_status(|status| match status {
+                ExecutionStatus::MemoryLimitExceeded => true,
+                _ => false,
+            }),
+            SolutionCheckResult::RuntimeError => any_status(|status| match status {
+                ExecutionStatus::ReturnCode(_) | ExecutionStatus::Signal(_, _) => true,
+                _ => false,
+            }),
+        }
+    }
+}
+
+impl FromStr for SolutionCheckResult {
+    type Err = Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        SolutionCheckResult::all()
+            .iter()
+            .find(|res| res.name() == s)
+            .cloned()
+            .ok_or_else(|| {
+                format_err!(
+                    "Unknown check result '{}', valid ones are: {}",
+                    s,
+                    SolutionCheckResult::all()
+                        .iter()
+                        .map(|res| res.name())
+                        .join(", ")
+                )
+            })
+    }
+}
+
+impl SolutionCheck {
+    /// Whether this check refers to the specified subtask.
+    pub fn matches_subtask(&self, subtask_id: SubtaskId) -> bool {
+        let id = subtask_id.to_string();
+        self.subtasks.iter().any(|pattern| {
+            glob::Pattern::new(pattern.trim_start_matches("st"))
+                .map(|pattern| pattern.matches(&id))
+                .unwrap_or(false)
+        })
+    }
+
+    /// Parse the checks from the source file of a solution.
+    pub fn parse_file<P: AsRef<Path>>(path: P) -> Result<Vec<SolutionCheck>, Error> {
+        let path = path.as_ref();
+        let content = std::fs::read(path)
+            .map_err(|e| format_err!("Cannot read {}: {}", path.display(), e.to_string()))?;
+        SolutionCheck::parse(&String::from_utf8_lossy(&content))
+    }
+
+    /// Parse the checks from the content of the source file of a solution.
+    pub fn parse(content: &str) -> Result<Vec<SolutionCheck>, Error> {
+        let mut checks = Vec::new();
+        for (index, line) in content.lines().enumerate() {
+            if let Some(check) = SolutionCheck::parse_line(line, index + 1)? {
+                checks.push(check);
+            }
+        }
+        Ok(checks)
+    }
+
+    /// Parse a single line of the source file, returning the check it contains, if any.
+    fn parse_line(line: &str, line_no: usize) -> Result<Option<SolutionCheck>, Error> {
+        let start = match CHECK_PREFIX.find(line) {
+            Some(prefix) => prefix.end(),
+            None => return Ok(None),
+        };
+        let check = &line[start..];
+        let colon = match check.find(':') {
+            Some(colon) => colon,
+            None => bail!("Line {}: missing ':' after the check name", line_no),
+        };
+        let result = SolutionCheckResult::from_str(check[..colon].trim())
+            .map_err(|e| format_err!("Line {}: {}", line_no, e.to_string()))?;
+        let subtasks: Vec<String> = check[colon + 1..]
+            .split_whitespace()
+            // allow the check to be inside a block comment
+            .filter(|pattern| *pattern != "*/")
+            .map(String::from)
+            .collect();
+        if subtasks.is_empty() {
+            bail!("Line {}: no subtask specified", line_no);
+        }
+        for pattern in subtasks.iter() {
+            glob::Pattern::new(pattern.trim_start_matches("st")).map_err(|e| {
+                format_err!(
+                    "Line {}: invalid subtask pattern '{}': {}",
+                    line_no,
+                    pattern,
+                    e.to_string()
+                )
+            })?;
+        }
+        Ok(Some(SolutionCheck {
+            result,
+            subtasks,
+            line: line_no,
+        }))
+    }
+}
+
+impl SolutionChecks {
+    /// Make a new `SolutionChecks` for a solution, its results will be read from the provided
+    /// `ScoreManager` after the evaluation.
+    pub fn new(
+        solution: PathBuf,
+        checks: Vec<SolutionCheck>,
+        score_manager: Arc<Mutex<ScoreManager>>,
+    ) -> SolutionChecks {
+        SolutionChecks {
+            solution,
+            checks,
+            score_manager,
+        }
+    }
+
+    /// Compare the checks with the results of the solution, returning a message for each mismatch.
+    pub fn check(&self, task: &IOITask) -> Vec<String> {
+        let score_manager = self.score_manager.lock().unwrap();
+        let name = self
+            .solution
+            .file_name()
+            .map(|name| name.to_string_lossy().to_string())
+            .unwrap_or_default();
+        let mut errors = Vec::new();
+        for check in self.checks.iter() {
+            let subtasks: Vec<_> = task
+                .subtasks
+                .keys()
+                .filter(|st| check.matches_subtask(**st))
+                .sorted()
+                .collect();
+            if subtasks.is_empty() {
+                errors.push(format!(
+                    "Solution {} (line {}): @check-{} does not match any subtask",
+                    name,
+                    check.line,
+                    check.result.name()
+                ));
+            }
+            for subtask in subtasks {
+                if !check.result.check(&score_manager, *subtask) {
+                    errors.push(format!(
+                        "Solution {} (line {}): expected {} on subtask {}",
+                        name,
+                        check.line,
+                        check.result.name(),
+                        subtask
+                    ));
+                }
+            }
+        }
+        errors
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::ioi::dag::tests::make_task_with_subtasks;
+    use crate::ioi::TestcaseId;
+    use crate::ui::UIMessageSender;
+
+    use super::*;
+
+    fn make_checks(
+        checks: &str,
+        statuses: &[ExecutionStatus],
+        scores: &[f64],
+    ) -> (IOITask, SolutionChecks) {
+        let task = make_task_with_subtasks("/task");
+        let mut manager = ScoreManager::new(&task);
+        let (sender, _receiver) = UIMessageSender::new();
+        let sender = Arc::new(Mutex::new(sender));
+        for (tc, (status, score)) in statuses.iter().zip(scores.iter()).enumerate() {
+            let tc = tc as TestcaseId;
+            manager.execution_status(tc, status);
+            manager
+                .score(tc / 2, tc, *score, "".into(), sender.clone(), "sol".into())
+                .unwrap();
+        }
+        let checks = SolutionCheck::parse(checks).unwrap();
+        let checks = SolutionChecks::new("sol.cpp".into(), checks, Arc::new(Mutex::new(manager)));
+        (task, checks)
+    }
+
+    #[test]
+    fn test_parse_checks() {
+        let checks = SolutionCheck::parse(
+            "#include <iostream>\n// @check-accepted: st0 st1\n/* @check-time-limit-exceeded: * */\n",
+        )
+        .unwrap();
+        assert_eq!(
+            checks,
+            vec![
+                SolutionCheck {
+                    result: SolutionCheckResult::Accepted,
+                    subtasks: vec!["st0".into(), "st1".into()],
+                    line: 2,
+                },
+                SolutionCheck {
+                    result: SolutionCheckResult::TimeLimitExceeded,
+                    subtasks: vec!["*".into()],
+                    line: 3,
+                }
+            ]
+        );
+    }
+
+    #[test]
+    fn test_parse_checks_comment_start() {
+        let checks = SolutionCheck::parse(
+            "/*\n * @check-accepted: *\n */\nint x; // @check-wrong-answer: 1\n{ @check-runtime-error: 2 }\n",
+        )
+        .unwrap();
+        let results: Vec<_> = checks.iter().map(|check| check.result).collect();
+        assert_eq!(
+            results,
+            vec![
+                SolutionCheckResult::Accepted,
+                SolutionCheckResult::WrongAnswer,
+                SolutionCheckResult::RuntimeError
+            ]
+        );
+    }
+
+    #[test]
+    fn test_parse_checks_not_in_comment() {
+        let checks = SolutionCheck::parse(
+            "// send to user@check-accepted.com\nprintf(\"@check-wrong-answer: 1\");\n// see the @check-accepted: annotations\n",
+        )
+        .unwrap();
+        assert!(checks.is_empty());
+    }
+
+    #[test]
+    fn test_parse_checks_unknown_result() {
+        let err = SolutionCheck::parse("// @check-wrong: st1").unwrap_err();
+        assert!(err.to_string().contains("Line 1"));
+        assert!(err.to_string().contains("wrong"));
+    }
+
+    #[test]
+    fn test_parse_checks_missing_subtasks() {
+        let err = SolutionCheck::parse("\n# @check-accepted:").unwrap_err();
+        assert!(err.to_string().contains("Line 2"));
+    }
+
+    #[test]
+    fn test_matches_subtask() {
+        let check = SolutionCheck::parse("// @check-accepted: 1 st2*").unwrap();
+        assert!(!check[0].matches_subtask(0));
+        assert!(check[0].matches_subtask(1));
+        assert!(check[0].matches_subtask(2));
+        assert!(check[0].matches_subtask(23));
+        assert!(!check[0].matches_subtask(3));
+    }
+
+    #[test]
+    fn test_check_accepted() {
+        let (task, checks) = make_checks(
+            "// @check-accepted: *",
+            &[
+                ExecutionStatus::Success,
+                ExecutionStatus::Success,
+                ExecutionStatus::Success,
+                ExecutionStatus::Success,
+            ],
+            &[1.0, 1.0, 1.0, 1.0],
+        );
+        assert!(checks.check(&task).is_empty());
+    }
+
+    #[test]
+    fn test_check_mismatch() {
+        let (task, checks) = make_checks(
+            "// @check-accepted: st0\n// @check-time-limit-exceeded: st1",
+            &[
+                ExecutionStatus::Success,
+                ExecutionStatus::Success,
+                ExecutionStatus::Success,
+                ExecutionStatus::ReturnCode(1),
+            ],
+            &[1.0, 0.0, 1.0, 0.0],
+        );
+        let errors = checks.check(&task);
+        assert_eq!(errors.len(), 2);
+        assert!(errors[0].contains("expected accepted on subtask 0"));
+        assert!(errors[1].contains("expected time-limit-exceeded on subtask 1"));
+    }
+
+    #[test]
+    fn test_check_statuses() {
+        let (task, checks) = make_checks(
+            "// @check-wrong-answer: st0\n// @check-runtime-error: st1\n// @check-partial-score: 0",
+            &[
+                ExecutionStatus::Success,
+                ExecutionStatus::Success,
+                ExecutionStatus::Signal(11, "Segmentation fault".into()),
+                ExecutionStatus::Success,
+            ],
+            &[0.0, 0.5, 0.0, 1.0],
+        );
+        assert!(checks.check(&task).is_empty());
+    }
+
+    #[test]
+    fn test_check_no_subtask() {
+        let (task, checks) = make_checks(
+            "// @check-accepted: st5",
+            &[
+                ExecutionStatus::Success,
+                ExecutionStatus::Success,
+                ExecutionStatus::Success,
+                ExecutionStatus::Success,
+            ],
+            &[1.0, 1.0, 1.0, 1.0],
+        );
+        let errors = checks.check(&task);
+        assert_eq!(errors.len(), 1);
+        assert!(errors[0].contains("does not match any subtask"));
+    }
+}
@@ -1,3 +1,4 @@
+use std::collections::HashMap;
 use std::path::PathBuf;
 
 use failure::Error;
@@ -5,7 +6,7 @@ use itertools::Itertools;
 use serde::{Deserialize, Serialize};
 use typescript_definitions::TypeScriptify;
 
-use crate::ioi::IOITask;
+use crate::ioi::{IOITask, LimitsMultipliers, TestcaseId};
 
 /// Task information structure.
 #[derive(Debug, Clone, Serialize, Deserialize, TypeScriptify)]
@@ -18,6 +19,8 @@ pub struct IOITaskInfo {
     title: String,
     /// Scoring info.
     scoring: TaskInfoScoring,
+    /// Testcases of the task.
+    testcases: Vec<TaskInfoTestcase>,
     /// Limits of the task.
     limits: TaskInfoLimits,
     /// Statements of the task.
@@ -33,6 +36,8 @@ pub struct TaskInfoLimits {
     time: Option<f64>,
     /// Memory limit in megabytes.
     memory: Option<u64>,
+    /// Multipliers of the limits for some languages, keyed by the name of the language.
+    multipliers: HashMap<String, LimitsMultipliers>,
 }
 
 /// Attachment of the task.
@@ -53,6 +58,23 @@ pub struct TaskInfoSubtask {
     max_score: f64,
     /// Number of testcases for this subtask.
     testcases: u64,
+    /// The parameter of the score type for this subtask, if any (e.g. the threshold).
+    score_parameter: Option<f64>,
+    /// The weights of the testcases of this subtask, sorted by testcase id.
+    testcase_weights: Vec<f64>,
+    /// Time limit in seconds for this subtask, if it overrides the one of the task.
+    time_limit: Option<f64>,
+    /// Memory limit in megabytes for this subtask, if it overrides the one of the task.
+    memory_limit: Option<u64>,
+}
+
+/// Info of a testcase.
+#[derive(Debug, Clone, Serialize, Deserialize, TypeScriptify)]
+pub struct TaskInfoTestcase {
+    /// The id of the testcase.
+    id: TestcaseId,
+    /// The name of the testcase, if any.
+    name: Option<String>,
 }
 
 /// Scoring for the task.
@@ -60,6 +82,8 @@ pub struct TaskInfoSubtask {
 pub struct TaskInfoScoring {
     /// Maximum score for the task.
     max_score: f64,
+    /// The name of the score type, as in CMS.
+    score_type: String,
     /// Subtasks of this task.
     subtasks: Vec<TaskInfoSubtask>,
 }
@@ -87,19 +111,41 @@ impl IOITaskInfo {
                     .subtasks
                     .iter()
                     .fold(0.0, |sum, (_, subtask)| sum + subtask.max_score),
+                score_type: task.testcase_score_aggregator.cms_name().into(),
                 subtasks: task
                     .subtasks
                     .iter()
                     .sorted_by_key(|(&id, _)| id)
-                    .map(|(_, subtask)| TaskInfoSubtask {
+                    .map(|(&id, subtask)| TaskInfoSubtask {
                         max_score: subtask.max_score,
                         testcases: subtask.testcases.len() as u64,
+                        score_parameter: task.testcase_score_aggregator.subtask_parameter(id),
+                        testcase_weights: subtask
+                            .testcases
+                            .iter()
+                            .sorted_by_key(|(&id, _)| id)
+                            .map(|(_, testcase)| testcase.weight)
+                            .collect(),
+                        time_limit: subtask.time_limit,
+                        memory_limit: subtask.memory_limit,
                     })
                     .collect(),
             },
+            testcases: task
+                .subtasks
+                .values()
+                .flat_map(|subtask| subtask.testcases.values())
+                .unique_by(|testcase| testcase.id)
+                .sorted_by_key(|testcase| testcase.id)
+                .map(|testcase| TaskInfoTestcase {
+                    id: testcase.id,
+                    name: testcase.name.clone(),
+                })
+                .collect(),
             limits: TaskInfoLimits {
                 time: task.time_limit,
                 memory: task.memory_limit,
+                multipliers: task.limits_multipliers.clone(),
             },
             statements: task
                 .booklets
@@ -60,6 +60,8 @@ pub enum TestcaseEvaluationStatus {
     Failed,
     /// The evaluation has been skipped.
     Skipped,
+    /// The output file is missing from the submission of an output only task.
+    MissingOutput,
 }
 
 /// State of the generation of a testcases.
@@ -150,6 +152,27 @@ impl SolutionEvaluationState {
                 .collect(),
         }
     }
+
+    /// The evaluation states of a testcase, one for each subtask that contains it.
+    fn testcases_mut(
+        &mut self,
+        subtask: SubtaskId,
+        testcase: TestcaseId,
+    ) -> Vec<&mut SolutionTestcaseEvaluationState> {
+        if !self
+            .subtasks
+            .get(&subtask)
+            .expect("Missing subtask")
+            .testcases
+            .contains_key(&testcase)
+        {
+            panic!("Missing testcase");
+        }
+        self.subtasks
+            .values_mut()
+            .filter_map(|st| st.testcases.get_mut(&testcase))
+            .collect()
+    }
 }
 
 /// The status of the compilation of a dependency of a booklet.
@@ -185,8 +208,12 @@ pub struct UIState {
     pub executor_status: Option<ExecutorStatus<SystemTime>>,
     /// The status of the booklets
     pub booklets: HashMap<String, BookletState>,
+    /// The limits suggested for the task, if requested.
+    pub limit_suggestion: Option<LimitSuggestion>,
     /// All the emitted warnings.
     pub warnings: Vec<String>,
+    /// All the emitted errors.
+    pub errors: Vec<String>,
 }
 
 impl TestcaseEvaluationStatus {
@@ -252,6 +279,7 @@ impl TestcaseEvaluationStatus {
             RuntimeError => "Runtime error".into(),
             Failed => "Execution failed".into(),
             Skipped => "Execution skipped".into(),
+            MissingOutput => "Missing output file".into(),
         }
     }
 }
@@ -293,9 +321,32 @@ impl UIState {
             evaluations: HashMap::new(),
             executor_status: None,
             booklets: HashMap::new(),
+            limit_suggestion: None,
             warnings: Vec::new(),
+            errors: Vec::new(),
         }
     }
+
+    /// The generation states of a testcase, one for each subtask that contains it.
+    fn testcase_generations(
+        &mut self,
+        subtask: SubtaskId,
+        testcase: TestcaseId,
+    ) -> Vec<&mut TestcaseGenerationState> {
+        if !self
+            .generations
+            .get(&subtask)
+            .expect("Subtask is gone")
+            .testcases
+            .contains_key(&testcase)
+        {
+            panic!("Testcase is gone");
+        }
+        self.generations
+            .values_mut()
+            .filter_map(|st| st.testcases.get_mut(&testcase))
+            .collect()
+    }
 }
 
 impl UIStateT for UIState {
@@ -315,27 +366,26 @@ impl UIStateT for UIState {
                 testcase,
                 status,
             } => {
-                let gen = self
-                    .generations
-                    .get_mut(&subtask)
-                    .expect("Subtask is gone")
-                    .testcases
-                    .get_mut(&testcase)
-                    .expect("Testcase is gone");
-                match status {
-                    UIExecutionStatus::Pending => gen.status = TestcaseGenerationStatus::Pending,
-                    UIExecutionStatus::Started { .. } => {
-                        gen.status = TestcaseGenerationStatus::Generating
-                    }
-                    UIExecutionStatus::Done { result } => {
-                        if let ExecutionStatus::Success = result.status {
-                            gen.status = TestcaseGenerationStatus::Generated;
-                        } else {
-                            gen.status = TestcaseGenerationStatus::Failed;
+                for gen in self.testcase_generations(subtask, testcase) {
+                    match status.clone() {
+                        UIExecutionStatus::Pending => {
+                            gen.status = TestcaseGenerationStatus::Pending
+                        }
+                        UIExecutionStatus::Started { .. } => {
+                            gen.status = TestcaseGenerationStatus::Generating
+                        }
+                        UIExecutionStatus::Done { result } => {
+                            if let ExecutionStatus::Success = result.status {
+                                gen.status = TestcaseGenerationStatus::Generated;
+                            } else {
+                                gen.status = TestcaseGenerationStatus::Failed;
+                            }
+                            gen.generation = Some(result);
+                        }
+                        UIExecutionStatus::Skipped => {
+                            gen.status = TestcaseGenerationStatus::Skipped
                         }
-                        gen.generation = Some(result);
                     }
-                    UIExecutionStatus::Skipped => gen.status = TestcaseGenerationStatus::Skipped,
                 }
             }
             UIMessage::IOIValidation {
@@ -343,30 +393,27 @@ impl UIStateT for UIState {
                 testcase,
                 status,
             } => {
-                let gen = self
-                    .generations
-                    .get_mut(&subtask)
-                    .expect("Subtask is gone")
-                    .testcases
-                    .get_mut(&testcase)
-                    .expect("Testcase is gone");
-                match status {
-                    UIExecutionStatus::Pending => gen.status = TestcaseGenerationStatus::Pending,
-                    UIExecutionStatus::Started { .. } => {
-                        gen.status = TestcaseGenerationStatus::Validating
-                    }
-                    UIExecutionStatus::Done { result } => {
-                        if let ExecutionStatus::Success = result.status {
-                            gen.status = TestcaseGenerationStatus::Validated;
-                        } else {
-                            gen.status = TestcaseGenerationStatus::Failed;
+                for gen in self.testcase_generations(subtask, testcase) {
+                    match status.clone() {
+                        UIExecutionStatus::Pending => {
+                            gen.status = TestcaseGenerationStatus::Pending
                         }
-                        gen.validation = Some(result);
-                    }
-                    UIExecutionStatus::Skipped => {
-                        if let TestcaseGenerationStatus::Failed = gen.status {
-                        } else {
-                            gen.status = TestcaseGenerationStatus::Skipped;
+                        UIExecutionStatus::Started { .. } => {
+                            gen.status = TestcaseGenerationStatus::Validating
+                        }
+                        UIExecutionStatus::Done { result } => {
+                            if let ExecutionStatus::Success = result.status {
+                                gen.status = TestcaseGenerationStatus::Validated;
+                            } else {
+                                gen.status = TestcaseGenerationStatus::Failed;
+                            }
+                            gen.validation = Some(result);
+                        }
+                        UIExecutionStatus::Skipped => {
+                            if let TestcaseGenerationStatus::Failed = gen.status {
+                            } else {
+                                gen.status = TestcaseGenerationStatus::Skipped;
+                            }
                         }
                     }
                 }
@@ -376,30 +423,27 @@ impl UIStateT for UIState {
                 testcase,
                 status,
             } => {
-                let gen = self
-                    .generations
-                    .get_mut(&subtask)
-                    .expect("Subtask is gone")
-                    .testcases
-                    .get_mut(&testcase)
-                    .expect("Testcase is gone");
-                match status {
-                    UIExecutionStatus::Pending => gen.status = TestcaseGenerationStatus::Pending,
-                    UIExecutionStatus::Started { .. } => {
-                        gen.status = TestcaseGenerationStatus::Solving
-                    }
-                    UIExecutionStatus::Done { result } => {
-                        if let ExecutionStatus::Success = result.status {
-                            gen.status = TestcaseGenerationStatus::Solved;
-                        } else {
-                            gen.status = TestcaseGenerationStatus::Failed;
+                for gen in self.testcase_generations(subtask, testcase) {
+                    match status.clone() {
+                        UIExecutionStatus::Pending => {
+                            gen.status = TestcaseGenerationStatus::Pending
                         }
-                        gen.solution = Some(result);
-                    }
-                    UIExecutionStatus::Skipped => {
-                        if let TestcaseGenerationStatus::Failed = gen.status {
-                        } else {
-                            gen.status = TestcaseGenerationStatus::Skipped;
+                        UIExecutionStatus::Started { .. } => {
+                            gen.status = TestcaseGenerationStatus::Solving
+                        }
+                        UIExecutionStatus::Done { result } => {
+                            if let ExecutionStatus::Success = result.status {
+                                gen.status = TestcaseGenerationStatus::Solved;
+                            } else {
+                                gen.status = TestcaseGenerationStatus::Failed;
+                            }
+                            gen.solution = Some(result);
+                        }
+                        UIExecutionStatus::Skipped => {
+                            if let TestcaseGenerationStatus::Failed = gen.status {
+                            } else {
+                                gen.status = TestcaseGenerationStatus::Skipped;
+                            }
                         }
                     }
                 }
@@ -417,50 +461,48 @@ impl UIStateT for UIState {
                     .evaluations
                     .entry(solution)
                     .or_insert_with(|| SolutionEvaluationState::new(task));
-                let subtask = eval.subtasks.get_mut(&subtask).expect("Missing subtask");
-                let mut testcase = subtask
-                    .testcases
-                    .get_mut(&testcase)
-                    .expect("Missing testcase");
-                if testcase.results.len() != num_parts {
-                    testcase.results = vec![None; num_parts];
-                }
-                match status {
-                    UIExecutionStatus::Pending => {}
-                    UIExecutionStatus::Started { .. } => {
-                        testcase.status = TestcaseEvaluationStatus::Solving
+                for testcase in eval.testcases_mut(subtask, testcase) {
+                    if testcase.results.len() != num_parts {
+                        testcase.results = vec![None; num_parts];
                     }
-                    UIExecutionStatus::Done { result } => {
-                        match result.status {
-                            ExecutionStatus::Success => {
-                                testcase.status = TestcaseEvaluationStatus::Solved
-                            }
-                            ExecutionStatus::ReturnCode(_) => {
-                                testcase.status = TestcaseEvaluationStatus::RuntimeError
-                            }
-                            ExecutionStatus::Signal(_, _) => {
-                                testcase.status = TestcaseEvaluationStatus::RuntimeError
-                            }
-                            ExecutionStatus::TimeLimitExceeded => {
-                                testcase.status = TestcaseEvaluationStatus::TimeLimitExceeded
-                            }
-                            ExecutionStatus::SysTimeLimitExceeded => {
-                                testcase.status = TestcaseEvaluationStatus::TimeLimitExceeded
-                            }
-                            ExecutionStatus::WallTimeLimitExceeded => {
-                                testcase.status = TestcaseEvaluationStatus::WallTimeLimitExceeded
-                            }
-                            ExecutionStatus::MemoryLimitExceeded => {
-                                testcase.status = TestcaseEvaluationStatus::MemoryLimitExceeded
-                            }
-                            ExecutionStatus::InternalError(_) => {
-                                testcase.status = TestcaseEvaluationStatus::Failed
+                    match status.clone() {
+                        UIExecutionStatus::Pending => {}
+                        UIExecutionStatus::Started { .. } => {
+                            testcase.status = TestcaseEvaluationStatus::Solving
+                        }
+                        UIExecutionStatus::Done { result } => {
+                            match result.status {
+                                ExecutionStatus::Success => {
+                                    testcase.status = TestcaseEvaluationStatus::Solved
+                                }
+                                ExecutionStatus::ReturnCode(_) => {
+                                    testcase.status = TestcaseEvaluationStatus::RuntimeError
+                                }
+                                ExecutionStatus::Signal(_, _) => {
+                                    testcase.status = TestcaseEvaluationStatus::RuntimeError
+                                }
+                                ExecutionStatus::TimeLimitExceeded => {
+                                    testcase.status = TestcaseEvaluationStatus::TimeLimitExceeded
+                                }
+                                ExecutionStatus::SysTimeLimitExceeded => {
+                                    testcase.status = TestcaseEvaluationStatus::TimeLimitExceeded
+                                }
+                                ExecutionStatus::WallTimeLimitExceeded => {
+                                    testcase.status =
+                                        TestcaseEvaluationStatus::WallTimeLimitExceeded
+                                }
+                                ExecutionStatus::MemoryLimitExceeded => {
+                                    testcase.status = TestcaseEvaluationStatus::MemoryLimitExceeded
+                                }
+                                ExecutionStatus::InternalError(_) => {
+                                    testcase.status = TestcaseEvaluationStatus::Failed
+                                }
                             }
+                            testcase.results[part] = Some(result);
+                        }
+                        UIExecutionStatus::Skipped => {
+                            testcase.status = TestcaseEvaluationStatus::Skipped
                         }
-                        testcase.results[part] = Some(result);
-                    }
-                    UIExecutionStatus::Skipped => {
-                        testcase.status = TestcaseEvaluationStatus::Skipped
                     }
                 }
             }
@@ -475,19 +517,30 @@ impl UIStateT for UIState {
                     .evaluations
                     .entry(solution)
                     .or_insert_with(|| SolutionEvaluationState::new(task));
-                let subtask = eval.subtasks.get_mut(&subtask).expect("Missing subtask");
-                let mut testcase = subtask
-                    .testcases
-                    .get_mut(&testcase)
-                    .expect("Missing testcase");
-                match status {
-                    UIExecutionStatus::Started { .. } => {
-                        testcase.status = TestcaseEvaluationStatus::Checking;
-                    }
-                    UIExecutionStatus::Done { result } => {
-                        testcase.checker = Some(result);
+                for testcase in eval.testcases_mut(subtask, testcase) {
+                    match status.clone() {
+                        UIExecutionStatus::Started { .. } => {
+                            testcase.status = TestcaseEvaluationStatus::Checking;
+                        }
+                        UIExecutionStatus::Done { result } => {
+                            testcase.checker = Some(result);
+                        }
+                        _ => {}
                     }
-                    _ => {}
+                }
+            }
+            UIMessage::IOIMissingOutput {
+                subtask,
+                testcase,
+                solution,
+            } => {
+                let task = &self.task;
+                let eval = self
+                    .evaluations
+                    .entry(solution)
+                    .or_insert_with(|| SolutionEvaluationState::new(task));
+                for testcase in eval.testcases_mut(subtask, testcase) {
+                    testcase.status = TestcaseEvaluationStatus::MissingOutput;
                 }
             }
             UIMessage::IOITestcaseScore {
@@ -502,19 +555,17 @@ impl UIStateT for UIState {
                     .evaluations
                     .entry(solution)
                     .or_insert_with(|| SolutionEvaluationState::new(task));
-                let subtask = eval.subtasks.get_mut(&subtask).expect("Missing subtask");
-                let mut testcase = subtask
-                    .testcases
-                    .get_mut(&testcase)
-                    .expect("Missing testcase");
-                testcase.score = Some(score);
-                if !testcase.status.has_completed() {
-                    if score == 0.0 {
-                        testcase.status = TestcaseEvaluationStatus::WrongAnswer(message);
-                    } else if (score - 1.0).abs() < 0.001 {
-                        testcase.status = TestcaseEvaluationStatus::Accepted(message);
-                    } else {
-                        testcase.status = TestcaseEvaluationStatus::Partial(message);
+                for testcase in eval.testcases_mut(subtask, testcase) {
+                    testcase.score = Some(score);
+                    if !testcase.status.has_completed() {
+                        if score == 0.0 {
+                            testcase.status =
+                                TestcaseEvaluationStatus::WrongAnswer(message.clone());
+                        } else if (score - 1.0).abs() < 0.001 {
+                            testcase.status = TestcaseEvaluationStatus::Accepted(message.clone());
+                        } else {
+                            testcase.status = TestcaseEvaluationStatus::Partial(message.clone());
+                        }
                     }
                 }
             }
@@ -576,9 +627,15 @@ impl UIStateT for UIState {
                     .expect("Statement dependency step is gone")
                     .status = status;
             }
+            UIMessage::IOILimitSuggestion { suggestion } => {
+                self.limit_suggestion = Some(suggestion);
+            }
             UIMessage::Warning { message } => {
                 self.warnings.push(message);
             }
+            UIMessage::Error { message } => {
+                self.errors.push(message);
+            }
             UIMessage::TerryTask { .. }
             | UIMessage::TerryGeneration { .. }
             | UIMessage::TerryValidation { .. }
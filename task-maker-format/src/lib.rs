@@ -28,6 +28,7 @@ use itertools::Itertools;
 use serde::{Deserialize, Serialize};
 use typescript_definitions::TypeScriptify;
 
+pub use compiler_settings::{CompilerSettingsRule, TaskCompilerSettings};
 pub use sanity_checks::get_sanity_check_names;
 pub use source_file::SourceFile;
 pub use tag::{Tag, VALID_TAGS};
@@ -38,6 +39,7 @@ use crate::ioi::task_info::IOITaskInfo;
 use crate::terry::Seed;
 use crate::ui::UI;
 
+mod compiler_settings;
 pub mod ioi;
 mod sanity_checks;
 mod source_file;
@@ -111,6 +113,19 @@ pub struct EvaluationConfig {
     pub seed: Option<Seed>,
     /// Do not write any file inside the task directory.
     pub dry_run: bool,
+    /// How many times each evaluation of the solutions is run, for having more reliable timings.
+    pub repetitions: usize,
+    /// The number of repetitions of the solutions whose names start with the first value of the
+    /// pairs, overriding `repetitions`. If more prefixes match, the longest one is used.
+    pub solution_repetitions: Vec<(String, usize)>,
+    /// Suggest the time and memory limits of the task from the resources used by the solutions.
+    pub suggest_limits: bool,
+    /// When suggesting the limits, the solutions whose names start with one of these prefixes are
+    /// considered correct on all the subtasks, regardless of their annotations.
+    pub correct_solutions: Vec<String>,
+    /// When suggesting the limits, the solutions whose names start with one of these prefixes are
+    /// considered too slow on all the subtasks, regardless of their annotations.
+    pub too_slow_solutions: Vec<String>,
 }
 
 /// The data for an evaluation, including the DAG and the UI channel.
@@ -121,8 +136,17 @@ pub struct EvaluationData {
     pub dag: ExecutionDAG,
     /// The sender of the UI.
     pub sender: Arc<Mutex<ui::UIMessageSender>>,
+    /// The settings of the compilers of the source files of the task.
+    pub compiler_settings: TaskCompilerSettings,
+    /// The functions to call after the evaluation completed, for checking the results of the
+    /// evaluation. They are called after the post hook of the sanity checks.
+    pub post_evaluation_hooks: Vec<PostEvaluationHook>,
 }
 
+/// A function called after the evaluation completed with the sender of the UI. If it returns an
+/// error the evaluation fails.
+pub type PostEvaluationHook = Box<dyn FnOnce(&mut ui::UIMessageSender) -> Result<(), Error>>;
+
 impl EvaluationData {
     /// Crate a new `EvaluationData` returning the data and the receiving part of the UI channel.
     pub fn new<P: Into<PathBuf>>(task_root: P) -> (EvaluationData, ui::UIChannelReceiver) {
@@ -132,6 +156,8 @@ impl EvaluationData {
                 task_root: task_root.into(),
                 dag: ExecutionDAG::new(),
                 sender: Arc::new(Mutex::new(sender)),
+                compiler_settings: TaskCompilerSettings::default(),
+                post_evaluation_hooks: Vec::new(),
             },
             receiver,
         )
@@ -170,6 +196,21 @@ impl EvaluationConfig {
             .collect_vec()
     }
 
+    /// How many times each evaluation of the solution with the provided path is run.
+    pub fn repetitions_of(&self, solution: &Path) -> usize {
+        let name = solution
+            .file_name()
+            .map(|name| name.to_string_lossy().to_string())
+            .unwrap_or_default();
+        self.solution_repetitions
+            .iter()
+            .filter(|(prefix, _)| name.starts_with(prefix.as_str()))
+            .max_by_key(|(prefix, _)| prefix.len())
+            .map(|(_, repetitions)| *repetitions)
+            .unwrap_or(self.repetitions)
+            .max(1)
+    }
+
     /// Returns the fixed solutions in the config or, if none is specified, all the ones matching
     /// the provided pattern in the provided base directory.
     fn solution_paths(&self, base_dir: &Path, patterns: Vec<&str>) -> Vec<PathBuf> {
@@ -380,4 +421,20 @@ mod tests {
         );
         assert!(source.is_none());
     }
+
+    #[test]
+    fn test_repetitions_of() {
+        let config = EvaluationConfig {
+            repetitions: 3,
+            solution_repetitions: vec![("sol".into(), 5), ("sol_slow".into(), 1)],
+            ..Default::default()
+        };
+        assert_eq!(config.repetitions_of(Path::new("sol/wrong.cpp")), 3);
+        assert_eq!(config.repetitions_of(Path::new("sol/sol.cpp")), 5);
+        assert_eq!(config.repetitions_of(Path::new("sol/sol_slow.cpp")), 1);
+        assert_eq!(
+            EvaluationConfig::default().repetitions_of(Path::new("sol.cpp")),
+            1
+        );
+    }
 }
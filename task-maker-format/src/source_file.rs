@@ -1,3 +1,4 @@
+use std::borrow::Cow;
 use std::path::PathBuf;
 use std::sync::Arc;
 
@@ -47,7 +48,7 @@ impl SourceFile {
         description: S,
         args: I,
     ) -> Result<Execution, Error> {
-        let (comp, exec) = self.base.execute(
+        let (comp, exec) = self.configured(eval).execute(
             &mut eval.dag,
             description,
             args.into_iter().map(|s| s.into()).collect(),
@@ -58,11 +59,23 @@ impl SourceFile {
 
     /// Prepare the source file if needed and return the executable file.
     pub fn executable(&self, eval: &mut EvaluationData) -> Result<FileUuid, Error> {
-        let (exe, comp) = self.base.executable(&mut eval.dag)?;
+        let (exe, comp) = self.configured(eval).executable(&mut eval.dag)?;
         self.bind_compilation_exe(eval, comp)?;
         Ok(exe)
     }
 
+    /// The source file to use in the evaluation, compiled with the compiler settings of the task
+    /// for this file, if any.
+    fn configured(&self, eval: &EvaluationData) -> Cow<task_maker_lang::SourceFile> {
+        let settings =
+            eval.compiler_settings
+                .settings_for(&eval.task_root, &self.path, self.language());
+        match settings {
+            Some(settings) => Cow::Owned(self.base.with_compiler_settings(&settings)),
+            None => Cow::Borrowed(&self.base),
+        }
+    }
+
     /// Bind the callbacks for the compilation callbacks.
     fn bind_compilation_exe(
         &self,
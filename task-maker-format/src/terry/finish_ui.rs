@@ -30,6 +30,7 @@ impl FinishUITrait<UIState> for FinishUI {
         ui.print_summary(state);
         println!();
         FinishUIUtils::new(&mut ui.stream).print_messages(&state.warnings);
+        FinishUIUtils::new(&mut ui.stream).print_errors(&state.errors);
     }
 }
 
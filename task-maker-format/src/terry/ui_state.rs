@@ -21,6 +21,8 @@ pub struct UIState {
     pub executor_status: Option<ExecutorStatus<SystemTime>>,
     /// All the emitted warnings.
     pub warnings: Vec<String>,
+    /// All the emitted errors.
+    pub errors: Vec<String>,
 }
 
 /// The state of the evaluation of a solution.
@@ -84,6 +86,7 @@ impl UIState {
             solutions: HashMap::new(),
             executor_status: None,
             warnings: Vec::new(),
+            errors: Vec::new(),
         }
     }
 }
@@ -181,17 +184,22 @@ impl UIStateT for UIState {
             UIMessage::Warning { message } => {
                 self.warnings.push(message);
             }
+            UIMessage::Error { message } => {
+                self.errors.push(message);
+            }
             UIMessage::IOITask { .. }
             | UIMessage::IOIGeneration { .. }
             | UIMessage::IOIValidation { .. }
             | UIMessage::IOISolution { .. }
             | UIMessage::IOIEvaluation { .. }
             | UIMessage::IOIChecker { .. }
+            | UIMessage::IOIMissingOutput { .. }
             | UIMessage::IOITestcaseScore { .. }
             | UIMessage::IOISubtaskScore { .. }
             | UIMessage::IOITaskScore { .. }
             | UIMessage::IOIBooklet { .. }
-            | UIMessage::IOIBookletDependency { .. } => unreachable!("IOI message on Terry UI"),
+            | UIMessage::IOIBookletDependency { .. }
+            | UIMessage::IOILimitSuggestion { .. } => unreachable!("IOI message on Terry UI"),
         }
     }
 }
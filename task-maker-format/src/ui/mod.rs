@@ -276,6 +276,16 @@ impl<'a> FinishUIUtils<'a> {
             }
         }
     }
+
+    /// Print the errors.
+    pub fn print_errors(&mut self, errors: &[String]) {
+        if !errors.is_empty() {
+            cwriteln!(self, RED, "Errors:");
+            for error in errors.iter() {
+                println!(" - {}", error);
+            }
+        }
+    }
 }
 
 /// The sender of the UIMessage
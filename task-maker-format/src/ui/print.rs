@@ -242,6 +242,17 @@ impl UI for PrintUI {
                     solution, testcase, subtask
                 ));
             }
+            UIMessage::IOIMissingOutput {
+                subtask,
+                testcase,
+                solution,
+            } => {
+                print!("[MISSING] ");
+                self.write_message(format!(
+                    "Submission {:?} misses the output of testcase {} of subtask {}",
+                    solution, testcase, subtask
+                ));
+            }
             UIMessage::IOITestcaseScore {
                 subtask,
                 testcase,
@@ -291,10 +302,21 @@ impl UI for PrintUI {
                     num_steps
                 ));
             }
+            UIMessage::IOILimitSuggestion { suggestion } => {
+                print!("[LIMITS]  ");
+                self.write_message(format!(
+                    "Suggested time limit {:.2}s and memory limit {}MiB",
+                    suggestion.time_limit, suggestion.memory_limit
+                ));
+            }
             UIMessage::Warning { message } => {
                 cwrite!(self, WARNING, "[WARNING] ");
                 print!("{}", message);
             }
+            UIMessage::Error { message } => {
+                cwrite!(self, ERROR, "[ERROR]   ");
+                print!("{}", message);
+            }
             UIMessage::TerryTask { task } => {
                 self.terry_state = Some(terry::ui_state::UIState::new(task.as_ref()));
             }
@@ -96,6 +96,16 @@ pub enum UIMessage {
         status: UIExecutionStatus,
     },
 
+    /// The output file of a testcase is missing from an output only submission.
+    IOIMissingOutput {
+        /// The id of the subtask.
+        subtask: SubtaskId,
+        /// The id of the testcase.
+        testcase: TestcaseId,
+        /// The path of the submission.
+        solution: PathBuf,
+    },
+
     /// The score of a testcase is ready.
     IOITestcaseScore {
         /// The id of the subtask.
@@ -153,6 +163,12 @@ pub enum UIMessage {
         status: UIExecutionStatus,
     },
 
+    /// The limits suggested for the task from the resources used by the solutions.
+    IOILimitSuggestion {
+        /// The suggested limits.
+        suggestion: ioi::LimitSuggestion,
+    },
+
     /// The information about the task which is being run.
     TerryTask {
         /// The task information.
@@ -206,4 +222,10 @@ pub enum UIMessage {
         /// The message of the warning.
         message: String,
     },
+
+    /// An error has been emitted.
+    Error {
+        /// The message of the error.
+        message: String,
+    },
 }
@@ -50,6 +50,20 @@ fn test_sanity_checks_max_score() {
     has_warning(&warnings, "The score of the task");
 }
 
+#[test]
+fn test_sanity_checks_included_testcases_limits() {
+    let mut task = utils::new_task();
+    let testcase = task.subtasks[&0].testcases[&0].clone();
+    let subtask = task.subtasks.get_mut(&1).unwrap();
+    subtask.testcases.insert(0, testcase);
+    subtask.time_limit = Some(2.0);
+    let warnings = get_warnings(&task);
+    has_warning(
+        &warnings,
+        "Testcase 0 is evaluated with the limits of subtask 0, not with the ones of subtask 1",
+    );
+}
+
 #[test]
 fn test_sanity_checks_att_graders() {
     let tmpdir = tempdir::TempDir::new("tm-test").unwrap();
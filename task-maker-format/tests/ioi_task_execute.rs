@@ -18,6 +18,62 @@ fn test_ioi_task_execute_copy() {
     assert_eq!(eval.dag.data.provided_files.len(), 6);
 }
 
+#[test]
+fn test_ioi_task_execute_testcase_names() {
+    let tmpdir = tempdir::TempDir::new("tm-test").unwrap();
+    let mut task = utils::new_task_with_context(tmpdir.path());
+    task.subtasks
+        .get_mut(&1)
+        .unwrap()
+        .testcases
+        .get_mut(&2)
+        .unwrap()
+        .name = Some("worst".into());
+    let (mut eval, _receiver) = EvaluationData::new(tmpdir.path());
+    task.build_dag(&mut eval, &EvaluationConfig::default())
+        .unwrap();
+    let mut written: Vec<_> = eval
+        .dag
+        .file_callbacks
+        .values()
+        .filter_map(|callbacks| callbacks.write_to.as_ref())
+        .map(|write_to| {
+            write_to
+                .dest
+                .strip_prefix(tmpdir.path())
+                .unwrap()
+                .to_owned()
+        })
+        .collect();
+    written.sort();
+    let expected: Vec<PathBuf> = vec![
+        "input/input0.txt".into(),
+        "input/input1.txt".into(),
+        "input/input_worst.txt".into(),
+        "output/output0.txt".into(),
+        "output/output1.txt".into(),
+        "output/output_worst.txt".into(),
+    ];
+    assert_eq!(written, expected);
+}
+
+#[test]
+fn test_ioi_task_execute_duplicated_testcase_names() {
+    let tmpdir = tempdir::TempDir::new("tm-test").unwrap();
+    let mut task = utils::new_task_with_context(tmpdir.path());
+    for (st, tc) in &[(0, 0), (1, 2)] {
+        let subtask = task.subtasks.get_mut(st).unwrap();
+        subtask.testcases.get_mut(tc).unwrap().name = Some("worst".into());
+    }
+    let (mut eval, _receiver) = EvaluationData::new(tmpdir.path());
+    let err = task
+        .build_dag(&mut eval, &EvaluationConfig::default())
+        .unwrap_err();
+    assert!(err
+        .to_string()
+        .contains("Testcases 0 and 2 have the same name: worst"));
+}
+
 #[test]
 fn test_ioi_task_execute_gen() {
     let tmpdir = tempdir::TempDir::new("tm-test").unwrap();
@@ -100,6 +156,65 @@ fn test_ioi_task_execute_eval() {
         .unwrap();
     assert_eq!(eval.dag.data.provided_files.len(), 6 + 1); // io + sol/sol.py
     assert_eq!(eval.dag.data.execution_groups.len(), 3 + 3); // eval + checker
+    assert!(eval.post_evaluation_hooks.is_empty());
+}
+
+#[test]
+fn test_ioi_task_execute_eval_checks() {
+    let tmpdir = tempdir::TempDir::new("tm-test").unwrap();
+    let task = utils::new_task_with_context(tmpdir.path());
+
+    std::fs::create_dir(tmpdir.path().join("sol")).unwrap();
+    std::fs::write(
+        tmpdir.path().join("sol").join("sol.py"),
+        "# @check-accepted: *\nfoo",
+    )
+    .unwrap();
+
+    let (mut eval, _receiver) = EvaluationData::new(tmpdir.path());
+    task.build_dag(&mut eval, &EvaluationConfig::default())
+        .unwrap();
+    // the checks are verified after the evaluation
+    assert_eq!(eval.post_evaluation_hooks.len(), 1);
+}
+
+#[test]
+fn test_ioi_task_execute_eval_suggest_limits() {
+    let tmpdir = tempdir::TempDir::new("tm-test").unwrap();
+    let task = utils::new_task_with_context(tmpdir.path());
+
+    std::fs::create_dir(tmpdir.path().join("sol")).unwrap();
+    std::fs::write(tmpdir.path().join("sol").join("sol.py"), "foo").unwrap();
+
+    let (mut eval, _receiver) = EvaluationData::new(tmpdir.path());
+    let config = EvaluationConfig {
+        suggest_limits: true,
+        ..Default::default()
+    };
+    task.build_dag(&mut eval, &config).unwrap();
+    // the limits are suggested after the evaluation
+    assert_eq!(eval.post_evaluation_hooks.len(), 1);
+}
+
+#[test]
+fn test_ioi_task_execute_eval_invalid_checks() {
+    let tmpdir = tempdir::TempDir::new("tm-test").unwrap();
+    let task = utils::new_task_with_context(tmpdir.path());
+
+    std::fs::create_dir(tmpdir.path().join("sol")).unwrap();
+    std::fs::write(
+        tmpdir.path().join("sol").join("sol.py"),
+        "# @check-wrong: *\nfoo",
+    )
+    .unwrap();
+
+    let (mut eval, _receiver) = EvaluationData::new(tmpdir.path());
+    let err = task
+        .build_dag(&mut eval, &EvaluationConfig::default())
+        .unwrap_err();
+    assert!(err
+        .to_string()
+        .contains("Invalid checks in solution sol.py"));
 }
 
 #[test]
@@ -2,7 +2,10 @@ use std::path::PathBuf;
 
 use task_maker_dag::ExecutionStatus;
 use task_maker_exec::ExecutorStatus;
-use task_maker_format::ioi::{TestcaseEvaluationStatus, TestcaseGenerationStatus, UIState};
+use task_maker_format::ioi::{
+    Checker, OutputOnlyTypeData, TaskType, TestcaseEvaluationStatus, TestcaseGenerationStatus,
+    UIState,
+};
 use task_maker_format::ui::UIStateT;
 use task_maker_format::ui::{CompilationStatus, UIExecutionStatus, UIMessage};
 
@@ -622,6 +625,90 @@ fn test_ui_state_testcase_score_wrong_answer() {
     );
 }
 
+#[test]
+fn test_ui_state_testcase_score_missing_output() {
+    let mut task = utils::new_task();
+    task.task_type = TaskType::OutputOnly(OutputOnlyTypeData {
+        checker: Checker::WhiteDiff,
+    });
+    let mut ui = UIState::new(&task);
+    let file = PathBuf::from("file.zip");
+    ui.apply(UIMessage::IOIMissingOutput {
+        subtask: 0,
+        testcase: 0,
+        solution: file.clone(),
+    });
+    assert_eq!(
+        ui.evaluations[&file].subtasks[&0].testcases[&0].status,
+        TestcaseEvaluationStatus::MissingOutput
+    );
+    ui.apply(UIMessage::IOITestcaseScore {
+        subtask: 0,
+        testcase: 0,
+        solution: file.clone(),
+        score: 0.0,
+        message: "Missing output file".to_string(),
+    });
+    assert_eq!(
+        ui.evaluations[&file].subtasks[&0].testcases[&0].score,
+        Some(0.0)
+    );
+    assert_eq!(
+        ui.evaluations[&file].subtasks[&0].testcases[&0].status,
+        TestcaseEvaluationStatus::MissingOutput
+    );
+}
+
+#[test]
+fn test_ui_state_testcase_score_output_only_not_missing() {
+    let mut task = utils::new_task();
+    task.task_type = TaskType::OutputOnly(OutputOnlyTypeData {
+        checker: Checker::WhiteDiff,
+    });
+    let mut ui = UIState::new(&task);
+    let file = PathBuf::from("file");
+    ui.apply(UIMessage::IOITestcaseScore {
+        subtask: 0,
+        testcase: 0,
+        solution: file.clone(),
+        score: 0.0,
+        message: "nope".to_string(),
+    });
+    assert_eq!(
+        ui.evaluations[&file].subtasks[&0].testcases[&0].status,
+        TestcaseEvaluationStatus::WrongAnswer("nope".into())
+    );
+}
+
+#[test]
+fn test_ui_state_testcase_score_output_only_wrong_answer() {
+    let mut task = utils::new_task();
+    task.task_type = TaskType::OutputOnly(OutputOnlyTypeData {
+        checker: Checker::WhiteDiff,
+    });
+    let mut ui = UIState::new(&task);
+    let file = PathBuf::from("file");
+    ui.apply(UIMessage::IOIChecker {
+        subtask: 0,
+        testcase: 0,
+        solution: file.clone(),
+        status: UIExecutionStatus::Started {
+            worker: Default::default(),
+        },
+    });
+    ui.apply(UIMessage::IOITestcaseScore {
+        subtask: 0,
+        testcase: 0,
+        solution: file.clone(),
+        score: 0.0,
+        message: "nope".to_string(),
+    });
+    assert_eq!(
+        ui.evaluations[&file].subtasks[&0].testcases[&0].status,
+        TestcaseEvaluationStatus::WrongAnswer("nope".into())
+    );
+}
+
 #[test]
 fn test_ui_state_testcase_score_partial() {
     let task = utils::new_task();
@@ -739,3 +826,44 @@ fn test_ui_state_warning() {
     assert_eq!(ui.warnings.len(), 1);
     assert_eq!(ui.warnings[0], "test");
 }
+
+#[test]
+fn test_ui_state_shared_testcase() {
+    let mut task = utils::new_task();
+    let testcase = task.subtasks[&0].testcases[&0].clone();
+    task.subtasks
+        .get_mut(&1)
+        .unwrap()
+        .testcases
+        .insert(0, testcase);
+    let mut ui = UIState::new(&task);
+    let file = PathBuf::from("sol");
+    ui.apply(UIMessage::IOIGeneration {
+        subtask: 0,
+        testcase: 0,
+        status: UIExecutionStatus::Done {
+            result: utils::good_result(),
+        },
+    });
+    ui.apply(UIMessage::IOITestcaseScore {
+        subtask: 0,
+        testcase: 0,
+        solution: file.clone(),
+        score: 1.0,
+        message: "yes".to_string(),
+    });
+    for subtask in &[0, 1] {
+        assert_eq!(
+            ui.generations[subtask].testcases[&0].status,
+            TestcaseGenerationStatus::Generated
+        );
+        assert_eq!(
+            ui.evaluations[&file].subtasks[subtask].testcases[&0].status,
+            TestcaseEvaluationStatus::Accepted("yes".to_string())
+        );
+    }
+    assert_eq!(
+        ui.evaluations[&file].subtasks[&1].testcases[&1].status,
+        TestcaseEvaluationStatus::Pending
+    );
+}
@@ -1,6 +1,7 @@
 #[macro_use]
 extern crate approx;
 
+use std::collections::HashMap;
 use std::path::PathBuf;
 use std::sync::{Arc, Mutex};
 use task_maker_format::ioi::*;
@@ -138,3 +139,117 @@ fn test_score_manager() {
     }
     assert!(receiver.try_recv().is_err());
 }
+
+#[test]
+fn test_score_manager_threshold() {
+    let mut task = utils::new_task();
+    let mut thresholds = HashMap::new();
+    thresholds.insert(0, 0.5);
+    thresholds.insert(1, 0.5);
+    task.testcase_score_aggregator = TestcaseScoreAggregator::Threshold(thresholds);
+    let mut manager = ScoreManager::new(&task);
+    let (sender, receiver) = UIMessageSender::new();
+    let sender = Arc::new(Mutex::new(sender));
+
+    manager
+        .score(0, 0, 0.5, "foo".into(), sender.clone(), "sol".into())
+        .unwrap();
+    manager
+        .score(1, 1, 0.2, "foo".into(), sender.clone(), "sol".into())
+        .unwrap();
+    manager
+        .score(1, 2, 0.7, "foo".into(), sender, "sol".into())
+        .unwrap();
+    let mut subtask_scores = HashMap::new();
+    let mut task_score = None;
+    while let Ok(mex) = receiver.try_recv() {
+        match mex {
+            UIMessage::IOISubtaskScore { subtask, score, .. } => {
+                subtask_scores.insert(subtask, score);
+            }
+            UIMessage::IOITaskScore { score, .. } => task_score = Some(score),
+            _ => {}
+        }
+    }
+    assert_abs_diff_eq!(subtask_scores[&0], 10.0);
+    assert_abs_diff_eq!(subtask_scores[&1], 0.0);
+    assert_abs_diff_eq!(task_score.unwrap(), 10.0);
+}
+
+#[test]
+fn test_score_manager_shared_testcase() {
+    let mut task = utils::new_task();
+    let testcase = task.subtasks[&0].testcases[&0].clone();
+    task.subtasks
+        .get_mut(&1)
+        .unwrap()
+        .testcases
+        .insert(0, testcase);
+    let mut manager = ScoreManager::new(&task);
+    let (sender, receiver) = UIMessageSender::new();
+    let sender = Arc::new(Mutex::new(sender));
+
+    manager
+        .score(1, 1, 1.0, "foo".into(), sender.clone(), "sol".into())
+        .unwrap();
+    manager
+        .score(1, 2, 1.0, "foo".into(), sender.clone(), "sol".into())
+        .unwrap();
+    while let Ok(mex) = receiver.try_recv() {
+        if let UIMessage::IOISubtaskScore { .. } = mex {
+            panic!("Subtask 1 is not completed yet: {:?}", mex);
+        }
+    }
+    manager
+        .score(0, 0, 0.5, "foo".into(), sender, "sol".into())
+        .unwrap();
+    let mut subtask_scores = HashMap::new();
+    let mut task_score = None;
+    while let Ok(mex) = receiver.try_recv() {
+        match mex {
+            UIMessage::IOISubtaskScore { subtask, score, .. } => {
+                subtask_scores.insert(subtask, score);
+            }
+            UIMessage::IOITaskScore { score, .. } => task_score = Some(score),
+            _ => {}
+        }
+    }
+    assert_abs_diff_eq!(subtask_scores[&0], 5.0);
+    assert_abs_diff_eq!(subtask_scores[&1], 45.0);
+    assert_abs_diff_eq!(task_score.unwrap(), 50.0);
+}
+
+#[test]
+fn test_score_manager_sum_weighted() {
+    let mut task = utils::new_task();
+    task.testcase_score_aggregator = TestcaseScoreAggregator::Sum;
+    let subtask = task.subtasks.get_mut(&1).unwrap();
+    subtask.testcases.get_mut(&1).unwrap().weight = 3.0;
+    let mut manager = ScoreManager::new(&task);
+    let (sender, receiver) = UIMessageSender::new();
+    let sender = Arc::new(Mutex::new(sender));
+
+    manager
+        .score(0, 0, 1.0, "foo".into(), sender.clone(), "sol".into())
+        .unwrap();
+    manager
+        .score(1, 1, 1.0, "foo".into(), sender.clone(), "sol".into())
+        .unwrap();
+    manager
+        .score(1, 2, 0.0, "foo".into(), sender, "sol".into())
+        .unwrap();
+    let mut subtask_scores = HashMap::new();
+    let mut task_score = None;
+    while let Ok(mex) = receiver.try_recv() {
+        match mex {
+            UIMessage::IOISubtaskScore { subtask, score, .. } => {
+                subtask_scores.insert(subtask, score);
+            }
+            UIMessage::IOITaskScore { score, .. } => task_score = Some(score),
+            _ => {}
+        }
+    }
+    assert_abs_diff_eq!(subtask_scores[&0], 10.0);
+    assert_abs_diff_eq!(subtask_scores[&1], 67.5);
+    assert_abs_diff_eq!(task_score.unwrap(), 77.5);
+}
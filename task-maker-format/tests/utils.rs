@@ -28,6 +28,7 @@ pub fn new_task_with_context(path: &Path) -> IOITask {
         title: "The Task".to_string(),
         time_limit: None,
         memory_limit: None,
+        limits_multipliers: HashMap::new(),
         infile: None,
         outfile: None,
         subtasks: HashMap::new(),
@@ -38,15 +39,20 @@ pub fn new_task_with_context(path: &Path) -> IOITask {
         difficulty: None,
         syllabus_level: None,
         sanity_checks: Arc::new(get_sanity_checks(&[])),
+        compiler_settings: Default::default(),
     };
     let st0 = task.subtasks.entry(0).or_insert(SubtaskInfo {
         id: 0,
         description: None,
         max_score: 10.0,
+        time_limit: None,
+        memory_limit: None,
         testcases: HashMap::default(),
     });
     st0.testcases.entry(0).or_insert(TestcaseInfo {
         id: 0,
+        name: None,
+        weight: 1.0,
         input_generator: InputGenerator::StaticFile(p.clone()),
         input_validator: InputValidator::AssumeValid,
         output_generator: OutputGenerator::StaticFile(p.clone()),
@@ -55,16 +61,22 @@ pub fn new_task_with_context(path: &Path) -> IOITask {
         id: 1,
         description: None,
         max_score: 90.0,
+        time_limit: None,
+        memory_limit: None,
         testcases: HashMap::default(),
     });
     st1.testcases.entry(1).or_insert(TestcaseInfo {
         id: 1,
+        name: None,
+        weight: 1.0,
         input_generator: InputGenerator::StaticFile(p.clone()),
         input_validator: InputValidator::AssumeValid,
         output_generator: OutputGenerator::StaticFile(p.clone()),
     });
     st1.testcases.entry(2).or_insert(TestcaseInfo {
         id: 2,
+        name: None,
+        weight: 1.0,
         input_generator: InputGenerator::StaticFile(p.clone()),
         input_validator: InputValidator::AssumeValid,
         output_generator: OutputGenerator::StaticFile(p),
@@ -85,6 +97,7 @@ pub fn good_result() -> ExecutionResult {
         },
         stdout: None,
         stderr: None,
+        samples: vec![],
     }
 }
 
@@ -101,5 +114,6 @@ pub fn bad_result() -> ExecutionResult {
         },
         stdout: None,
         stderr: None,
+        samples: vec![],
     }
 }
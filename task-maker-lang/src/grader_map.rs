@@ -111,6 +111,7 @@ mod tests {
 
     use crate::languages::c::{LanguageC, LanguageCConfiguration};
     use crate::languages::cpp::{LanguageCpp, LanguageCppConfiguration};
+    use crate::languages::go::{LanguageGo, LanguageGoConfiguration};
     use crate::languages::python::{LanguagePython, LanguagePythonVersion};
 
     use super::*;
@@ -139,6 +140,17 @@ mod tests {
         assert_that!(deps).is_empty();
     }
 
+    #[test]
+    fn test_get_compilation_deps_go() {
+        let grader_map = GraderMap::new(vec!["grader.go", "grader.cpp"]);
+
+        let lang = LanguageGo::new(LanguageGoConfiguration::from_env());
+        let deps = grader_map.get_compilation_deps(&lang);
+        assert_that!(deps).has_length(1);
+        assert_that!(deps[0].sandbox_path).is_equal_to(PathBuf::from("grader.go"));
+        assert_that!(grader_map.get_runtime_deps(&lang)).is_empty();
+    }
+
     #[test]
     fn test_get_runtime_deps() {
         let grader_map = GraderMap::new(vec!["grader.cpp", "grader.py"]);
@@ -1,9 +1,10 @@
 use std::path::Path;
+use std::sync::Arc;
 
 use task_maker_dag::*;
 
 use crate::languages::cpp::find_cpp_deps;
-use crate::languages::Language;
+use crate::languages::{CompilerSettings, Language};
 use crate::Dependency;
 
 /// Configuration of the C language to use.
@@ -46,11 +47,11 @@ impl LanguageCConfiguration {
 }
 
 impl Language for LanguageC {
-    fn name(&self) -> &'static str {
+    fn name(&self) -> &str {
         "C"
     }
 
-    fn extensions(&self) -> Vec<&'static str> {
+    fn extensions(&self) -> Vec<&str> {
         vec!["c"]
     }
 
@@ -89,6 +90,17 @@ impl Language for LanguageC {
     fn compilation_dependencies(&self, path: &Path) -> Vec<Dependency> {
         find_cpp_deps(path)
     }
+
+    fn with_compiler_settings(&self, settings: &CompilerSettings) -> Option<Arc<dyn Language>> {
+        let mut config = self.config.clone();
+        if let Some(std_version) = &settings.std_version {
+            config.std_version = std_version.clone();
+        }
+        config
+            .extra_flags
+            .extend(settings.extra_flags.iter().cloned());
+        Some(Arc::new(LanguageC::new(config)))
+    }
 }
 
 #[cfg(test)]
@@ -123,6 +135,25 @@ mod tests {
         assert_that!(new_args.iter()).contains("bar.c".to_string());
     }
 
+    #[test]
+    fn test_with_compiler_settings() {
+        let lang = LanguageC::new(LanguageCConfiguration {
+            compiler: ExecutionCommand::System("gcc".into()),
+            std_version: "gnu11".to_string(),
+            extra_flags: vec!["-lfoobar".into()],
+        });
+        let settings = CompilerSettings {
+            std_version: Some("c99".into()),
+            extra_flags: vec!["-Wextra".into()],
+        };
+        let lang = lang.with_compiler_settings(&settings).unwrap();
+        let args = lang.compilation_args(Path::new("foo.c"), None);
+        assert_that!(args).contains("-std=c99".to_string());
+        assert_that!(args).does_not_contain("-std=gnu11".to_string());
+        assert_that!(args).contains("-lfoobar".to_string());
+        assert_that!(args).contains("-Wextra".to_string());
+    }
+
     #[test]
     fn test_executable_name() {
         let lang = LanguageC::new(LanguageCConfiguration::from_env());
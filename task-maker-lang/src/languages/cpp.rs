@@ -1,10 +1,11 @@
 use std::path::{Path, PathBuf};
+use std::sync::Arc;
 
 use regex::Regex;
 
 use task_maker_dag::*;
 
-use crate::languages::{find_dependencies, Language};
+use crate::languages::{find_dependencies, CompilerSettings, Language};
 use crate::Dependency;
 
 /// Configuration of the C++ language to use.
@@ -47,11 +48,11 @@ impl LanguageCpp {
 }
 
 impl Language for LanguageCpp {
-    fn name(&self) -> &'static str {
+    fn name(&self) -> &str {
         "C++"
     }
 
-    fn extensions(&self) -> Vec<&'static str> {
+    fn extensions(&self) -> Vec<&str> {
         vec!["cpp", "cc", "c++"]
     }
 
@@ -97,6 +98,17 @@ impl Language for LanguageCpp {
     fn compilation_dependencies(&self, path: &Path) -> Vec<Dependency> {
         find_cpp_deps(path)
     }
+
+    fn with_compiler_settings(&self, settings: &CompilerSettings) -> Option<Arc<dyn Language>> {
+        let mut config = self.config.clone();
+        if let Some(std_version) = &settings.std_version {
+            config.std_version = std_version.clone();
+        }
+        config
+            .extra_flags
+            .extend(settings.extra_flags.iter().cloned());
+        Some(Arc::new(LanguageCpp::new(config)))
+    }
 }
 
 /// Extract all the dependencies of a C/C++ source file.
@@ -169,6 +181,25 @@ mod tests {
         assert_that!(new_args.iter()).contains("bar.cpp".to_string());
     }
 
+    #[test]
+    fn test_with_compiler_settings() {
+        let lang = LanguageCpp::new(LanguageCppConfiguration {
+            compiler: ExecutionCommand::System("gcc".into()),
+            std_version: "gnu11".to_string(),
+            extra_flags: vec!["-lfoobar".into()],
+        });
+        let settings = CompilerSettings {
+            std_version: Some("c++17".into()),
+            extra_flags: vec!["-Wextra".into()],
+        };
+        let lang = lang.with_compiler_settings(&settings).unwrap();
+        let args = lang.compilation_args(Path::new("foo.cpp"), None);
+        assert_that!(args).contains("-std=c++17".to_string());
+        assert_that!(args).does_not_contain("-std=gnu11".to_string());
+        assert_that!(args).contains("-lfoobar".to_string());
+        assert_that!(args).contains("-Wextra".to_string());
+    }
+
     #[test]
     fn test_executable_name() {
         let lang = LanguageCpp::new(LanguageCppConfiguration::from_env());
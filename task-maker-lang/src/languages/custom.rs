@@ -0,0 +1,436 @@
+use std::path::{Path, PathBuf};
+
+use failure::{bail, format_err, Error};
+use regex::Regex;
+use serde::{Deserialize, Serialize};
+
+use task_maker_dag::*;
+
+use crate::languages::{find_dependencies, Language};
+use crate::Dependency;
+
+/// The placeholder of the command templates replaced with the name of the source file.
+const SOURCE_PLACEHOLDER: &str = "{source}";
+/// The placeholder of the command templates replaced with the name of the executable.
+const EXE_PLACEHOLDER: &str = "{exe}";
+
+/// The content of a file with the user-defined languages.
+///
+/// ```yaml
+/// languages:
+///   - name: Haskell
+///     extensions: [hs]
+///     compile: ["ghc", "-O2", "-o", "{exe}", "{source}"]
+///     dependencies: ['^import\s+"([^"]+)"']
+///     limits:
+///       multiprocess: true
+/// ```
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct CustomLanguagesConfiguration {
+    /// The languages defined in the file.
+    #[serde(default)]
+    pub languages: Vec<CustomLanguageConfiguration>,
+}
+
+/// The definition of a user-defined language.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct CustomLanguageConfiguration {
+    /// The name of the language, it must be unique between all the languages.
+    pub name: String,
+    /// The extensions of the source files of this language, without the leading dot.
+    pub extensions: Vec<String>,
+    /// The template of the compilation command, the first item is the compiler. If not specified
+    /// the language is not compiled.
+    #[serde(default)]
+    pub compile: Option<Vec<String>>,
+    /// The template of the command that runs the program, the first item is the command to run.
+    /// The arguments of the program are appended to it. If the command is `{exe}` the executable
+    /// itself is run.
+    #[serde(default = "default_run_template")]
+    pub run: Vec<String>,
+    /// The regular expressions matching the files the source file depends on. The first capture
+    /// group is the path of the dependency, relative to the file that matches.
+    #[serde(default)]
+    pub dependencies: Vec<String>,
+    /// The adjustments to the limits of the program.
+    #[serde(default)]
+    pub limits: CustomLanguageLimits,
+}
+
+/// The adjustments to the limits of the programs of a user-defined language.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct CustomLanguageLimits {
+    /// Whether the program is allowed to spawn processes and threads.
+    #[serde(default)]
+    pub multiprocess: bool,
+    /// The memory, in MiB, to give to the program on top of its memory limit (e.g. for the
+    /// runtime of a virtual machine).
+    #[serde(default)]
+    pub extra_memory: u64,
+}
+
+/// A language defined by the user in a configuration file.
+///
+/// The command templates can contain the `{source}` and `{exe}` placeholders: the former is
+/// replaced with the name of the source file inside the sandbox, the latter with the name of the
+/// compiled file (during the compilation) or of the executable (when running the program). For the
+/// languages that are not compiled the executable is the source file itself.
+#[derive(Debug)]
+pub struct CustomLanguage {
+    /// The name of the language.
+    name: String,
+    /// The extensions of the language, lowercase and without the leading dot.
+    extensions: Vec<String>,
+    /// The template of the compilation command, if the language is compiled.
+    compile: Option<Vec<String>>,
+    /// The template of the runtime command.
+    run: Vec<String>,
+    /// The regular expressions matching the dependencies of the source files.
+    dependencies: Vec<Regex>,
+    /// The adjustments to the limits.
+    limits: CustomLanguageLimits,
+}
+
+/// The default runtime command: the executable itself.
+fn default_run_template() -> Vec<String> {
+    vec![EXE_PLACEHOLDER.to_string()]
+}
+
+impl CustomLanguagesConfiguration {
+    /// Parse the languages from the specified YAML file.
+    pub fn from_file<P: AsRef<Path>>(path: P) -> Result<CustomLanguagesConfiguration, Error> {
+        let path = path.as_ref();
+        let file = std::fs::File::open(path)
+            .map_err(|e| format_err!("Cannot open the languages file {:?}: {}", path, e))?;
+        serde_yaml::from_reader(file)
+            .map_err(|e| format_err!("Invalid languages file {:?}: {}", path, e))
+    }
+}
+
+impl CustomLanguage {
+    /// Make a new language from its definition, checking that the definition is valid.
+    pub fn new(config: CustomLanguageConfiguration) -> Result<CustomLanguage, Error> {
+        if config.name.is_empty() {
+            bail!("The name of a language cannot be empty");
+        }
+        if config.extensions.is_empty() {
+            bail!("Language {} has no extensions", config.name);
+        }
+        if config.compile.as_ref().map_or(false, |c| c.is_empty()) {
+            bail!("Language {} has an empty compilation command", config.name);
+        }
+        if config.run.is_empty() {
+            bail!("Language {} has an empty runtime command", config.name);
+        }
+        let mut dependencies = Vec::new();
+        for pattern in &config.dependencies {
+            let regex = Regex::new(pattern)
+                .map_err(|e| format_err!("Invalid dependency pattern of {}: {}", config.name, e))?;
+            if regex.captures_len() < 2 {
+                bail!(
+                    "The dependency pattern {:?} of {} has no capture groups",
+                    pattern,
+                    config.name
+                );
+            }
+            dependencies.push(regex);
+        }
+        let extensions = config
+            .extensions
+            .iter()
+            .map(|ext| ext.trim_start_matches('.').to_lowercase())
+            .collect();
+        Ok(CustomLanguage {
+            name: config.name,
+            extensions,
+            compile: config.compile,
+            run: config.run,
+            dependencies,
+            limits: config.limits,
+        })
+    }
+
+    /// Extract the dependencies of a file, in the form (local_path, sandbox_path). The sandbox path
+    /// is the path matched by the patterns, the local path is the same path relative to the file.
+    fn extract_dependencies(&self, path: &Path) -> Vec<(PathBuf, PathBuf)> {
+        let path = match path.canonicalize() {
+            Ok(path) => path,
+            _ => return vec![],
+        };
+        let content = match std::fs::read_to_string(&path) {
+            Ok(content) => content,
+            _ => return vec![],
+        };
+        let mut res = Vec::new();
+        for regex in &self.dependencies {
+            for cap in regex.captures_iter(&content) {
+                if let Some(dep) = cap.get(1) {
+                    let dep = PathBuf::from(dep.as_str());
+                    res.push((path.with_file_name(&dep), dep));
+                }
+            }
+        }
+        res
+    }
+
+    /// The dependencies of a source file, either at compile time or at runtime.
+    fn dependencies(&self, path: &Path) -> Vec<Dependency> {
+        if self.dependencies.is_empty() {
+            return vec![];
+        }
+        find_dependencies(path, |file| self.extract_dependencies(file))
+    }
+}
+
+impl Language for CustomLanguage {
+    fn name(&self) -> &str {
+        &self.name
+    }
+
+    fn extensions(&self) -> Vec<&str> {
+        self.extensions.iter().map(String::as_str).collect()
+    }
+
+    fn need_compilation(&self) -> bool {
+        self.compile.is_some()
+    }
+
+    fn compilation_command(&self, _path: &Path, _write_to: Option<&Path>) -> ExecutionCommand {
+        match &self.compile {
+            Some(compile) => ExecutionCommand::system(&compile[0]),
+            None => panic!("Language {} cannot be compiled!", self.name),
+        }
+    }
+
+    fn compilation_args(&self, path: &Path, write_to: Option<&Path>) -> Vec<String> {
+        let compile = match &self.compile {
+            Some(compile) => compile,
+            None => panic!("Language {} cannot be compiled!", self.name),
+        };
+        let exe = self.compiled_file_name(path, write_to);
+        expand_template(&compile[1..], &source_name(path), &exe.to_string_lossy())
+    }
+
+    fn compilation_add_file(&self, mut args: Vec<String>, file: &Path) -> Vec<String> {
+        args.push(file.to_string_lossy().to_string());
+        args
+    }
+
+    fn compilation_dependencies(&self, path: &Path) -> Vec<Dependency> {
+        if self.need_compilation() {
+            self.dependencies(path)
+        } else {
+            vec![]
+        }
+    }
+
+    fn runtime_command(&self, path: &Path, write_to: Option<&Path>) -> ExecutionCommand {
+        let exe = self.executable_name(path, write_to);
+        match self.run[0].as_str() {
+            EXE_PLACEHOLDER => ExecutionCommand::local(exe),
+            command => ExecutionCommand::system(command),
+        }
+    }
+
+    fn runtime_args(
+        &self,
+        path: &Path,
+        write_to: Option<&Path>,
+        mut args: Vec<String>,
+    ) -> Vec<String> {
+        let exe = self.executable_name(path, write_to);
+        let exe = exe.to_string_lossy();
+        let source = if self.need_compilation() {
+            source_name(path)
+        } else {
+            exe.to_string()
+        };
+        let mut res = expand_template(&self.run[1..], &source, &exe);
+        res.append(&mut args);
+        res
+    }
+
+    fn runtime_dependencies(&self, path: &Path) -> Vec<Dependency> {
+        if self.need_compilation() {
+            vec![]
+        } else {
+            self.dependencies(path)
+        }
+    }
+
+    fn custom_limits(&self, limits: &mut ExecutionLimits) {
+        if self.limits.multiprocess {
+            limits.nproc = None;
+        }
+    }
+
+    fn adapt_to_limits(&self, execution: &mut Execution) {
+        if self.limits.extra_memory == 0 {
+            return;
+        }
+        if let Some(memory) = execution.limits.memory {
+            execution
+                .limits
+                .memory(memory + self.limits.extra_memory * 1024);
+        }
+    }
+
+    fn executable_name(&self, path: &Path, write_to: Option<&Path>) -> PathBuf {
+        match write_to {
+            Some(write_to) => PathBuf::from(write_to.file_name().expect("Invalid file name")),
+            // the interpreters may need the extension of the source file
+            None if !self.need_compilation() => PathBuf::from(source_name(path)),
+            None => PathBuf::from(path.file_stem().expect("Invalid file name")),
+        }
+    }
+}
+
+/// The name of the source file inside the sandbox.
+fn source_name(path: &Path) -> String {
+    path.file_name()
+        .expect("Invalid source file name")
+        .to_string_lossy()
+        .to_string()
+}
+
+/// Replace the placeholders in all the items of the template.
+fn expand_template(template: &[String], source: &str, exe: &str) -> Vec<String> {
+    template
+        .iter()
+        .map(|arg| {
+            arg.replace(SOURCE_PLACEHOLDER, source)
+                .replace(EXE_PLACEHOLDER, exe)
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use std::fs::write;
+
+    use spectral::prelude::*;
+
+    use super::*;
+
+    fn parse(yaml: &str) -> Vec<CustomLanguageConfiguration> {
+        serde_yaml::from_str::<CustomLanguagesConfiguration>(yaml)
+            .unwrap()
+            .languages
+    }
+
+    #[test]
+    fn test_parse_compiled() {
+        let config = parse(
+            r#"
+languages:
+  - name: Haskell
+    extensions: [hs, .LHS]
+    compile: ["ghc", "-O2", "-o", "{exe}", "{source}"]
+    dependencies: ['^import\s+"([^"]+)"']
+    limits:
+      multiprocess: true
+"#,
+        );
+        let lang = CustomLanguage::new(config[0].clone()).unwrap();
+        assert_that!(lang.name()).is_equal_to("Haskell");
+        assert_that!(lang.extensions()).is_equal_to(vec!["hs", "lhs"]);
+        assert_that!(lang.need_compilation()).is_true();
+        let path = Path::new("sol.hs");
+        assert_that!(lang.compilation_command(path, None))
+            .is_equal_to(ExecutionCommand::system("ghc"));
+        assert_that!(lang.compilation_args(path, None)).is_equal_to(vec![
+            "-O2".to_string(),
+            "-o".to_string(),
+            "compiled".to_string(),
+            "sol.hs".to_string(),
+        ]);
+        assert_that!(lang.runtime_command(path, None)).is_equal_to(ExecutionCommand::local("sol"));
+        assert_that!(lang.runtime_args(path, None, vec!["arg".into()]))
+            .is_equal_to(vec!["arg".to_string()]);
+        let mut limits = ExecutionLimits::unrestricted();
+        limits.nproc(1);
+        lang.custom_limits(&mut limits);
+        assert_that!(limits.nproc).is_none();
+    }
+
+    #[test]
+    fn test_parse_interpreted() {
+        let config = parse(
+            r#"
+languages:
+  - name: Ruby
+    extensions: [rb]
+    run: ["ruby", "--disable-gems", "{source}"]
+    limits:
+      extra_memory: 64
+"#,
+        );
+        let lang = CustomLanguage::new(config[0].clone()).unwrap();
+        assert_that!(lang.need_compilation()).is_false();
+        let path = Path::new("sol.rb");
+        assert_that!(lang.executable_name(path, None)).is_equal_to(PathBuf::from("sol.rb"));
+        assert_that!(lang.runtime_command(path, None))
+            .is_equal_to(ExecutionCommand::system("ruby"));
+        assert_that!(lang.runtime_args(path, None, vec!["arg".into()])).is_equal_to(vec![
+            "--disable-gems".to_string(),
+            "sol.rb".to_string(),
+            "arg".to_string(),
+        ]);
+        let mut exec = Execution::new("exec", lang.runtime_command(path, None));
+        lang.adapt_to_limits(&mut exec);
+        assert_that!(exec.limits.memory).is_none();
+        exec.limits_mut().memory(1024);
+        lang.adapt_to_limits(&mut exec);
+        assert_that!(exec.limits.memory).is_equal_to(Some(1024 + 64 * 1024));
+    }
+
+    #[test]
+    fn test_invalid_languages() {
+        let base = CustomLanguageConfiguration {
+            name: "Lang".to_string(),
+            extensions: vec!["lang".to_string()],
+            compile: None,
+            run: default_run_template(),
+            dependencies: vec![],
+            limits: CustomLanguageLimits::default(),
+        };
+        assert!(CustomLanguage::new(base.clone()).is_ok());
+        let mut config = base.clone();
+        config.extensions.clear();
+        assert!(CustomLanguage::new(config).is_err());
+        let mut config = base.clone();
+        config.compile = Some(vec![]);
+        assert!(CustomLanguage::new(config).is_err());
+        let mut config = base.clone();
+        config.run.clear();
+        assert!(CustomLanguage::new(config).is_err());
+        let mut config = base.clone();
+        config.dependencies = vec!["import (.*".to_string()];
+        assert!(CustomLanguage::new(config).is_err());
+        let mut config = base;
+        config.dependencies = vec!["import .*".to_string()];
+        assert!(CustomLanguage::new(config).is_err());
+    }
+
+    #[test]
+    fn test_dependencies() {
+        let tmpdir = tempdir::TempDir::new("tm-test").unwrap();
+        let path = tmpdir.path().join("sol.ml");
+        write(&path, "#use \"lib.ml\"\nlet () = ()").unwrap();
+        write(tmpdir.path().join("lib.ml"), "#use \"missing.ml\"").unwrap();
+        let config = parse(
+            r#"
+languages:
+  - name: OCaml
+    extensions: [ml]
+    run: ["ocaml", "{source}"]
+    dependencies: ['#use\s+"([^"]+)"']
+"#,
+        );
+        let lang = CustomLanguage::new(config[0].clone()).unwrap();
+        assert_that!(lang.compilation_dependencies(&path)).is_empty();
+        let deps = lang.runtime_dependencies(&path);
+        assert_that!(deps).has_length(1);
+        assert_that!(deps[0].sandbox_path).is_equal_to(PathBuf::from("lib.ml"));
+    }
+}
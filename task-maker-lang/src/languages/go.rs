@@ -0,0 +1,163 @@
+use std::path::Path;
+
+use task_maker_dag::*;
+
+use crate::languages::Language;
+
+/// The directory, inside the tmpfs of the compilation sandbox, where the Go tool stores its build
+/// cache and its workspace.
+const GO_TMP_DIR: &str = "/tmp/go";
+
+/// Configuration of the Go language to use.
+#[derive(Clone, Debug)]
+pub struct LanguageGoConfiguration {
+    /// The Go tool to use (e.g. ExecutionCommand::system("go") ).
+    pub compiler: ExecutionCommand,
+    /// Extra flags to pass to `go build`.
+    pub extra_flags: Vec<String>,
+}
+
+/// The Go language.
+///
+/// The source file is compiled with `go build`, together with the grader, if any, which should be
+/// in the `main` package as well.
+#[derive(Debug)]
+pub struct LanguageGo {
+    pub config: LanguageGoConfiguration,
+}
+
+impl LanguageGoConfiguration {
+    /// Get the configuration of Go from the environment variables.
+    pub fn from_env() -> LanguageGoConfiguration {
+        let compiler = std::env::var_os("TM_GO").unwrap_or_else(|| "go".into());
+        let extra_flags = std::env::var("TM_GOFLAGS").unwrap_or_else(|_| String::new());
+        let extra_flags = shell_words::split(&extra_flags).expect("Invalid $TM_GOFLAGS");
+        LanguageGoConfiguration {
+            compiler: ExecutionCommand::System(compiler.into()),
+            extra_flags,
+        }
+    }
+}
+
+impl LanguageGo {
+    /// Make a new LanguageGo using the specified configuration.
+    pub fn new(config: LanguageGoConfiguration) -> LanguageGo {
+        LanguageGo { config }
+    }
+}
+
+impl Language for LanguageGo {
+    fn name(&self) -> &str {
+        "Go"
+    }
+
+    fn extensions(&self) -> Vec<&str> {
+        vec!["go"]
+    }
+
+    fn need_compilation(&self) -> bool {
+        true
+    }
+
+    fn compilation_command(&self, _path: &Path, _write_to: Option<&Path>) -> ExecutionCommand {
+        self.config.compiler.clone()
+    }
+
+    fn compilation_args(&self, path: &Path, write_to: Option<&Path>) -> Vec<String> {
+        let exe_name = self.compiled_file_name(path, write_to);
+        let mut args = vec![
+            "build".to_string(),
+            "-o".to_string(),
+            exe_name.to_string_lossy().to_string(),
+        ];
+        for arg in &self.config.extra_flags {
+            args.push(arg.clone());
+        }
+        args.push(
+            path.file_name()
+                .expect("Invalid source file name")
+                .to_string_lossy()
+                .to_string(),
+        );
+        args
+    }
+
+    fn compilation_add_file(&self, mut args: Vec<String>, file: &Path) -> Vec<String> {
+        args.push(file.to_string_lossy().to_string());
+        args
+    }
+
+    fn custom_compilation(&self, execution: &mut Execution) {
+        // the Go tool needs a writable directory for its cache, which is kept in the tmpfs that all
+        // the compilation sandboxes have
+        execution
+            .env("HOME", GO_TMP_DIR)
+            .env("GOCACHE", format!("{}/cache", GO_TMP_DIR))
+            .env("GOPATH", format!("{}/path", GO_TMP_DIR))
+            .env("GOTMPDIR", "/tmp")
+            // the compiled program should not depend on the C libraries and the compilation should
+            // not try to download a different toolchain
+            .env("CGO_ENABLED", "0")
+            .env("GOTOOLCHAIN", "local");
+    }
+
+    fn custom_limits(&self, limits: &mut ExecutionLimits) {
+        // the Go runtime spawns some threads, each one counting as a process
+        limits.nproc = None;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use spectral::prelude::*;
+
+    use super::*;
+
+    fn make_lang() -> LanguageGo {
+        LanguageGo::new(LanguageGoConfiguration {
+            compiler: ExecutionCommand::System("go".into()),
+            extra_flags: vec!["-trimpath".into()],
+        })
+    }
+
+    #[test]
+    fn test_compilation_args() {
+        let lang = make_lang();
+        let args = lang.compilation_args(Path::new("foo.go"), None);
+        assert_that!(args).is_equal_to(vec![
+            "build".to_string(),
+            "-o".to_string(),
+            "compiled".to_string(),
+            "-trimpath".to_string(),
+            "foo.go".to_string(),
+        ]);
+    }
+
+    #[test]
+    fn test_compilation_add_grader() {
+        let lang = make_lang();
+        let args = lang.compilation_args(Path::new("foo.go"), None);
+        let args = lang.compilation_add_file(args, Path::new("grader.go"));
+        let n = args.len();
+        assert_that!(args[n - 2..].to_vec())
+            .is_equal_to(vec!["foo.go".to_string(), "grader.go".to_string()]);
+    }
+
+    #[test]
+    fn test_custom_compilation() {
+        let lang = make_lang();
+        let mut exec = Execution::new("comp", lang.compilation_command(Path::new("foo.go"), None));
+        lang.custom_compilation(&mut exec);
+        assert_that!(exec.env.get("GOCACHE")).is_equal_to(Some(&format!("{}/cache", GO_TMP_DIR)));
+        assert_that!(exec.env.get("GOTOOLCHAIN")).is_equal_to(Some(&"local".to_string()));
+    }
+
+    #[test]
+    fn test_custom_limits() {
+        let lang = make_lang();
+        let mut limits = ExecutionLimits::unrestricted();
+        limits.nproc(1);
+        lang.custom_limits(&mut limits);
+        assert_that!(limits.nproc).is_none();
+    }
+}
@@ -0,0 +1,349 @@
+use std::path::{Path, PathBuf};
+
+use regex::Regex;
+
+use task_maker_dag::*;
+
+use crate::languages::Language;
+use crate::Dependency;
+
+/// The script that compiles the source files and packs the classes in a single jar file. Its
+/// arguments are the compiler, the `jar` tool, the name of the jar, the main class and then the
+/// source files.
+const COMPILATION_SCRIPT: &str = r#"set -e
+javac="$1"; jar="$2"; output="$3"; main="$4"; shift 4
+mkdir -p classes
+"$javac" -encoding UTF-8 -d classes "$@"
+"$jar" cfe "$output" "$main" -C classes ."#;
+/// The index, inside the compilation arguments, of the name of the main class.
+const MAIN_CLASS_ARG: usize = 6;
+/// The name of the class of the grader, which contains the entry point of the program.
+const GRADER_CLASS: &str = "grader";
+/// Default memory, in KiB, that the JVM needs for itself on top of the heap of the program.
+const DEFAULT_JVM_MEMORY_OVERHEAD: u64 = 256 * 1024;
+/// The flags always passed to the JVM: a single-threaded garbage collector and no performance
+/// data written to `/tmp`.
+const JVM_FLAGS: &[&str] = &["-XX:+UseSerialGC", "-XX:-UsePerfData"];
+
+/// Configuration of the Java language to use.
+#[derive(Clone, Debug)]
+pub struct LanguageJavaConfiguration {
+    /// The compiler to use (e.g. `javac`).
+    pub compiler: String,
+    /// The tool for building the jar archives (e.g. `jar`).
+    pub jar: String,
+    /// The Java Virtual Machine to use (e.g. `java`).
+    pub runtime: String,
+    /// Memory, in KiB, that the JVM needs for itself on top of the heap of the program. It is
+    /// taken from the memory limit of the program, up to half of it.
+    pub memory_overhead: u64,
+}
+
+/// The Java language.
+///
+/// The source files are compiled into a jar archive whose entry point is the grader, if any, or
+/// the class of the source file. Since in Java the public classes should be in a file with their
+/// name, the source file is compiled with the name of its public class.
+///
+/// The memory limit of the program covers both its heap and the memory of the JVM itself: the heap
+/// is limited to the memory limit minus the overhead of the JVM (see `$TM_JAVA_MEMORY_OVERHEAD`).
+#[derive(Debug)]
+pub struct LanguageJava {
+    pub config: LanguageJavaConfiguration,
+}
+
+impl LanguageJavaConfiguration {
+    /// Get the configuration of Java from the environment variables. `$TM_JAVA_MEMORY_OVERHEAD`
+    /// is the memory, in MiB, reserved to the JVM inside the memory limit (256 by default).
+    pub fn from_env() -> LanguageJavaConfiguration {
+        let memory_overhead = match std::env::var("TM_JAVA_MEMORY_OVERHEAD") {
+            Ok(overhead) => {
+                overhead
+                    .parse::<u64>()
+                    .expect("Invalid $TM_JAVA_MEMORY_OVERHEAD")
+                    * 1024
+            }
+            Err(_) => DEFAULT_JVM_MEMORY_OVERHEAD,
+        };
+        LanguageJavaConfiguration {
+            compiler: std::env::var("TM_JAVAC").unwrap_or_else(|_| "javac".into()),
+            jar: std::env::var("TM_JAR").unwrap_or_else(|_| "jar".into()),
+            runtime: std::env::var("TM_JAVA").unwrap_or_else(|_| "java".into()),
+            memory_overhead,
+        }
+    }
+}
+
+impl LanguageJava {
+    /// Make a new LanguageJava using the specified configuration.
+    pub fn new(config: LanguageJavaConfiguration) -> LanguageJava {
+        LanguageJava { config }
+    }
+}
+
+impl Language for LanguageJava {
+    fn name(&self) -> &str {
+        "Java / JDK"
+    }
+
+    fn extensions(&self) -> Vec<&str> {
+        vec!["java"]
+    }
+
+    fn need_compilation(&self) -> bool {
+        true
+    }
+
+    fn compilation_command(&self, _path: &Path, _write_to: Option<&Path>) -> ExecutionCommand {
+        ExecutionCommand::system("sh")
+    }
+
+    fn compilation_args(&self, path: &Path, write_to: Option<&Path>) -> Vec<String> {
+        let class_name = class_name(path);
+        vec![
+            "-c".to_string(),
+            COMPILATION_SCRIPT.to_string(),
+            "javac".to_string(),
+            self.config.compiler.clone(),
+            self.config.jar.clone(),
+            self.compiled_file_name(path, write_to)
+                .to_string_lossy()
+                .to_string(),
+            class_name.clone(),
+            format!("{}.java", class_name),
+        ]
+    }
+
+    fn compilation_add_file(&self, mut args: Vec<String>, file: &Path) -> Vec<String> {
+        // the grader contains the entry point of the program
+        if file.file_stem().map_or(false, |stem| stem == GRADER_CLASS) {
+            args[MAIN_CLASS_ARG] = GRADER_CLASS.to_string();
+        }
+        args.push(file.to_string_lossy().to_string());
+        args
+    }
+
+    fn compilation_dependencies(&self, path: &Path) -> Vec<Dependency> {
+        let sandbox_path = PathBuf::from(format!("{}.java", class_name(path)));
+        if path.file_name() == Some(sandbox_path.as_os_str()) {
+            return vec![];
+        }
+        vec![Dependency {
+            file: File::new(&format!("Source file of {:?} as {:?}", path, sandbox_path)),
+            local_path: path.to_owned(),
+            sandbox_path,
+            executable: false,
+        }]
+    }
+
+    fn runtime_command(&self, _path: &Path, _write_to: Option<&Path>) -> ExecutionCommand {
+        ExecutionCommand::system(&self.config.runtime)
+    }
+
+    fn runtime_args(
+        &self,
+        path: &Path,
+        write_to: Option<&Path>,
+        mut args: Vec<String>,
+    ) -> Vec<String> {
+        // will run for example: java <flags> -jar program args...
+        let mut jvm_args: Vec<String> = JVM_FLAGS.iter().map(|s| s.to_string()).collect();
+        jvm_args.push("-jar".to_string());
+        jvm_args.push(
+            self.executable_name(path, write_to)
+                .to_string_lossy()
+                .to_string(),
+        );
+        jvm_args.append(&mut args);
+        jvm_args
+    }
+
+    fn custom_limits(&self, limits: &mut ExecutionLimits) {
+        // the JVM spawns many threads (e.g. for the JIT compiler), each one counting as a process
+        limits.nproc = None;
+    }
+
+    fn adapt_to_limits(&self, execution: &mut Execution) {
+        let memory = match execution.limits.memory {
+            Some(memory) => memory,
+            None => return,
+        };
+        // the JVM needs some memory for itself, the heap can use the rest of the limit. With small
+        // limits at least half of the memory is left to the heap.
+        let overhead = self.config.memory_overhead.min(memory / 2);
+        let flags = vec![
+            format!("-Xmx{}k", memory - overhead),
+            "-XX:CompressedClassSpaceSize=64m".to_string(),
+            "-XX:ReservedCodeCacheSize=64m".to_string(),
+        ];
+        execution.args.splice(0..0, flags);
+        // each thread may otherwise reserve a large memory arena
+        execution.env("MALLOC_ARENA_MAX", "2");
+    }
+}
+
+/// The name of the class defined in the Java source file: the public class, if any, otherwise the
+/// first declared class. If the file cannot be read or the class is not found the name of the file
+/// is used.
+fn class_name(path: &Path) -> String {
+    lazy_static! {
+        static ref PUBLIC_CLASS: Regex =
+            Regex::new(r"(?m)^\s*public\s+(?:(?:final|abstract)\s+)*class\s+(\w+)")
+                .expect("Invalid regex");
+        static ref CLASS: Regex =
+            Regex::new(r"(?m)^\s*(?:(?:final|abstract)\s+)*class\s+(\w+)").expect("Invalid regex");
+    }
+    let stem = path
+        .file_stem()
+        .expect("Invalid source file name")
+        .to_string_lossy()
+        .to_string();
+    let content = match std::fs::read_to_string(path) {
+        Ok(content) => content,
+        _ => return stem,
+    };
+    PUBLIC_CLASS
+        .captures(&content)
+        .or_else(|| CLASS.captures(&content))
+        .and_then(|cap| cap.get(1))
+        .map(|class| class.as_str().to_string())
+        .unwrap_or(stem)
+}
+
+#[cfg(test)]
+mod tests {
+    use std::fs::write;
+
+    use spectral::prelude::*;
+
+    use super::*;
+
+    fn make_lang() -> LanguageJava {
+        LanguageJava::new(LanguageJavaConfiguration {
+            compiler: "javac".into(),
+            jar: "jar".into(),
+            runtime: "java".into(),
+            memory_overhead: DEFAULT_JVM_MEMORY_OVERHEAD,
+        })
+    }
+
+    #[test]
+    fn test_class_name() {
+        let tmpdir = tempdir::TempDir::new("tm-test").unwrap();
+        let path = tmpdir.path().join("sol.java");
+        write(
+            &path,
+            "import java.util.*;\nclass Helper {}\npublic final class Main {}",
+        )
+        .unwrap();
+        assert_that!(class_name(&path)).is_equal_to("Main".to_string());
+        write(
+            &path,
+            "import java.util.*;\n\nclass Solution {\n}\nclass Helper {}",
+        )
+        .unwrap();
+        assert_that!(class_name(&path)).is_equal_to("Solution".to_string());
+        write(&path, "// no classes here").unwrap();
+        assert_that!(class_name(&path)).is_equal_to("sol".to_string());
+    }
+
+    #[test]
+    fn test_compilation_args() {
+        let tmpdir = tempdir::TempDir::new("tm-test").unwrap();
+        let path = tmpdir.path().join("sol.java");
+        write(&path, "public class Main {}").unwrap();
+        let lang = make_lang();
+        let args = lang.compilation_args(&path, None);
+        assert_that!(args[MAIN_CLASS_ARG - 1]).is_equal_to("compiled".to_string());
+        assert_that!(args[MAIN_CLASS_ARG]).is_equal_to("Main".to_string());
+        assert_that!(args.last().unwrap()).is_equal_to(&"Main.java".to_string());
+        let deps = lang.compilation_dependencies(&path);
+        assert_that!(deps).has_length(1);
+        assert_that!(deps[0].local_path).is_equal_to(&path);
+        assert_that!(deps[0].sandbox_path).is_equal_to(PathBuf::from("Main.java"));
+    }
+
+    #[test]
+    fn test_compilation_same_name() {
+        let tmpdir = tempdir::TempDir::new("tm-test").unwrap();
+        let path = tmpdir.path().join("Main.java");
+        write(&path, "public class Main {}").unwrap();
+        let lang = make_lang();
+        assert_that!(lang.compilation_dependencies(&path)).is_empty();
+    }
+
+    #[test]
+    fn test_compilation_add_grader() {
+        let tmpdir = tempdir::TempDir::new("tm-test").unwrap();
+        let path = tmpdir.path().join("sol.java");
+        write(&path, "class Solution {}").unwrap();
+        let lang = make_lang();
+        let args = lang.compilation_args(&path, None);
+        let args = lang.compilation_add_file(args, Path::new("grader.java"));
+        assert_that!(args[MAIN_CLASS_ARG]).is_equal_to("grader".to_string());
+        assert_that!(args).contains("Solution.java".to_string());
+        assert_that!(args).contains("grader.java".to_string());
+    }
+
+    #[test]
+    fn test_runtime_args() {
+        let lang = make_lang();
+        let path = Path::new("sol.java");
+        assert_that!(lang.runtime_command(path, None))
+            .is_equal_to(ExecutionCommand::system("java"));
+        let args = lang.runtime_args(path, None, vec!["arg".to_string()]);
+        let n = args.len();
+        assert_that!(args[n - 3..].to_vec()).is_equal_to(vec![
+            "-jar".to_string(),
+            "sol".to_string(),
+            "arg".to_string(),
+        ]);
+    }
+
+    #[test]
+    fn test_limits() {
+        let lang = make_lang();
+        let mut exec = Execution::new("exec", lang.runtime_command(Path::new("sol.java"), None));
+        exec.args(lang.runtime_args(Path::new("sol.java"), None, vec![]));
+        lang.custom_limits(exec.limits_mut());
+        assert_that!(exec.limits.nproc).is_none();
+        exec.limits_mut().memory(1024 * 1024);
+        lang.adapt_to_limits(&mut exec);
+        // the heap and the JVM fit in the limit
+        assert_that!(exec.args[0]).is_equal_to("-Xmx786432k".to_string());
+        assert_that!(exec.limits.memory).is_equal_to(Some(1024 * 1024));
+        assert_that!(exec.args.last().unwrap()).is_equal_to(&"sol".to_string());
+    }
+
+    #[test]
+    fn test_limits_small_memory() {
+        let lang = make_lang();
+        let mut exec = Execution::new("exec", lang.runtime_command(Path::new("sol.java"), None));
+        exec.limits_mut().memory(128 * 1024);
+        lang.adapt_to_limits(&mut exec);
+        assert_that!(exec.args[0]).is_equal_to("-Xmx65536k".to_string());
+        assert_that!(exec.limits.memory).is_equal_to(Some(128 * 1024));
+    }
+
+    #[test]
+    fn test_limits_custom_overhead() {
+        let mut lang = make_lang();
+        lang.config.memory_overhead = 64 * 1024;
+        let mut exec = Execution::new("exec", lang.runtime_command(Path::new("sol.java"), None));
+        exec.limits_mut().memory(256 * 1024);
+        lang.adapt_to_limits(&mut exec);
+        assert_that!(exec.args[0]).is_equal_to("-Xmx196608k".to_string());
+        assert_that!(exec.limits.memory).is_equal_to(Some(256 * 1024));
+    }
+
+    #[test]
+    fn test_limits_no_memory() {
+        let lang = make_lang();
+        let mut exec = Execution::new("exec", lang.runtime_command(Path::new("sol.java"), None));
+        exec.args(lang.runtime_args(Path::new("sol.java"), None, vec![]));
+        let args = exec.args.clone();
+        lang.adapt_to_limits(&mut exec);
+        assert_that!(exec.args).is_equal_to(args);
+        assert_that!(exec.limits.memory).is_none();
+    }
+}
@@ -1,5 +1,6 @@
 use std::collections::{HashSet, VecDeque};
 use std::path::{Path, PathBuf};
+use std::sync::Arc;
 
 use serde::{Deserialize, Serialize};
 use typescript_definitions::TypeScriptify;
@@ -8,8 +9,12 @@ use task_maker_dag::*;
 
 pub(crate) mod c;
 pub(crate) mod cpp;
+pub(crate) mod custom;
+pub(crate) mod go;
+pub(crate) mod java;
 pub(crate) mod pascal;
 pub(crate) mod python;
+pub(crate) mod rust;
 pub(crate) mod shell;
 
 /// A dependency of an execution, all the sandbox paths must be relative and inside of the sandbox.
@@ -26,6 +31,17 @@ pub struct Dependency {
     pub executable: bool,
 }
 
+/// The settings of a compiler for some source files, in addition to the ones of the language (e.g.
+/// the ones from the environment variables).
+#[derive(Debug, Clone, Default, Serialize, Deserialize, PartialEq)]
+pub struct CompilerSettings {
+    /// The version of the standard to use (e.g. `c++17`), or the dialect of the language. If not
+    /// set the one of the language is used.
+    pub std_version: Option<String>,
+    /// Extra flags to pass to the compiler, after the ones of the language.
+    pub extra_flags: Vec<String>,
+}
+
 /// Trait that defines the properties of the supported languages. Most of the methods have a safe
 /// blanket implementation, note that not all of them are _really_ optional: based on the value
 /// returned by `need_compilation` some of the methods become required.
@@ -38,11 +54,11 @@ pub struct Dependency {
 /// - `compilation_add_file`
 pub trait Language: std::fmt::Debug + Send + Sync {
     /// Full name of the language. This must be unique between all the other languages.
-    fn name(&self) -> &'static str;
+    fn name(&self) -> &str;
 
     /// List of valid extensions for this language. A file is considered in this language if its
     /// extension is inside this list.
-    fn extensions(&self) -> Vec<&'static str>;
+    fn extensions(&self) -> Vec<&str>;
 
     /// Whether this language needs a compilation step. Returning `true` here triggers many changes
     /// in the behaviour of the execution. Of course the compilation step will be added, because of
@@ -78,12 +94,28 @@ pub trait Language: std::fmt::Debug + Send + Sync {
         panic!("Language {} cannot be compiled!", self.name())
     }
 
+    /// Whether the source files can be compiled together with a grader. If not, compiling a source
+    /// file with a grader fails.
+    fn supports_graders(&self) -> bool {
+        true
+    }
+
     /// The dependencies to put inside the compilation sandbox. This does not include the source
     /// file.
     fn compilation_dependencies(&self, _path: &Path) -> Vec<Dependency> {
         vec![]
     }
 
+    /// Update the compilation execution for some language-specific requirements. For example the
+    /// compiler may need some environment variables to find a writable directory.
+    fn custom_compilation(&self, _execution: &mut Execution) {}
+
+    /// A copy of this language whose compiler uses the specified settings. Returns `None` if the
+    /// compiler of this language cannot be configured.
+    fn with_compiler_settings(&self, _settings: &CompilerSettings) -> Option<Arc<dyn Language>> {
+        None
+    }
+
     /// The name that the compiled file will have inside the compilation sandbox.
     fn compiled_file_name(&self, _path: &Path, _write_to: Option<&Path>) -> PathBuf {
         "compiled".into()
@@ -123,6 +155,11 @@ pub trait Language: std::fmt::Debug + Send + Sync {
     /// need to fork (hence use more processes).
     fn custom_limits(&self, _limits: &mut ExecutionLimits) {}
 
+    /// Adapt the execution of the program to its limits, once they are all set. For example a
+    /// virtual machine may need to know how much memory the program can use, and some extra
+    /// memory for itself.
+    fn adapt_to_limits(&self, _execution: &mut Execution) {}
+
     /// The name of the executable inside the sandbox. If this binary will be written elsewhere in
     /// the system, use the same name. Otherwise fallback to the original file name, without
     /// extension.
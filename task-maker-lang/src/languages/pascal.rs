@@ -1,25 +1,38 @@
-use crate::languages::Language;
+use crate::languages::{CompilerSettings, Language};
 use crate::Dependency;
 use std::path::{Path, PathBuf};
+use std::sync::Arc;
 use task_maker_dag::*;
 
+/// Configuration of the Pascal language to use.
+#[derive(Clone, Debug, Default)]
+pub struct LanguagePascalConfiguration {
+    /// The dialect of the language to use (e.g. `objfpc`), if not set the default one of the
+    /// compiler is used.
+    pub mode: Option<String>,
+    /// Extra flags to pass to the compiler.
+    pub extra_flags: Vec<String>,
+}
+
 /// The Pascal language.
 #[derive(Debug)]
-pub struct LanguagePascal;
+pub struct LanguagePascal {
+    pub config: LanguagePascalConfiguration,
+}
 
 impl LanguagePascal {
-    /// Make a new LanguagePascal using the specified version.
-    pub fn new() -> LanguagePascal {
-        LanguagePascal {}
+    /// Make a new LanguagePascal using the specified configuration.
+    pub fn new(config: LanguagePascalConfiguration) -> LanguagePascal {
+        LanguagePascal { config }
     }
 }
 
 impl Language for LanguagePascal {
-    fn name(&self) -> &'static str {
+    fn name(&self) -> &str {
         "Pascal / fpc"
     }
 
-    fn extensions(&self) -> Vec<&'static str> {
+    fn extensions(&self) -> Vec<&str> {
         vec!["pas"]
     }
 
@@ -36,6 +49,12 @@ impl Language for LanguagePascal {
         let exe_name = exe_name.to_string_lossy();
         let args = vec!["-dEVAL", "-Fe/dev/stderr", "-O2", "-XS"];
         let mut args: Vec<_> = args.into_iter().map(|s| s.to_string()).collect();
+        if let Some(mode) = &self.config.mode {
+            args.push(format!("-M{}", mode));
+        }
+        for arg in &self.config.extra_flags {
+            args.push(arg.clone());
+        }
         args.push("-o".to_owned() + exe_name.as_ref());
         args.push(
             path.file_name()
@@ -63,6 +82,17 @@ impl Language for LanguagePascal {
             vec![]
         }
     }
+
+    fn with_compiler_settings(&self, settings: &CompilerSettings) -> Option<Arc<dyn Language>> {
+        let mut config = self.config.clone();
+        if let Some(mode) = &settings.std_version {
+            config.mode = Some(mode.clone());
+        }
+        config
+            .extra_flags
+            .extend(settings.extra_flags.iter().cloned());
+        Some(Arc::new(LanguagePascal::new(config)))
+    }
 }
 
 /// Search `fpc.cfg` in the local system, following the search rules of
@@ -104,7 +134,7 @@ mod tests {
 
     #[test]
     fn test_compilation_args() {
-        let lang = LanguagePascal::new();
+        let lang = LanguagePascal::new(LanguagePascalConfiguration::default());
         let args = lang.compilation_args(Path::new("foo.pas"), None);
         assert_that!(args).contains("foo.pas".to_string());
         assert_that!(args).contains("-ocompiled".to_string());
@@ -112,16 +142,30 @@ mod tests {
 
     #[test]
     fn test_compilation_add_file() {
-        let lang = LanguagePascal::new();
+        let lang = LanguagePascal::new(LanguagePascalConfiguration::default());
         let args = lang.compilation_args(Path::new("foo.pas"), None);
         let new_args = lang.compilation_add_file(args.clone(), Path::new("bar.pas"));
         assert_that!(new_args.iter()).contains_all_of(&args.iter());
         assert_that!(new_args.iter()).contains("bar.pas".to_string());
     }
 
+    #[test]
+    fn test_with_compiler_settings() {
+        let lang = LanguagePascal::new(LanguagePascalConfiguration::default());
+        let settings = CompilerSettings {
+            std_version: Some("objfpc".into()),
+            extra_flags: vec!["-Sa".into()],
+        };
+        let lang = lang.with_compiler_settings(&settings).unwrap();
+        let args = lang.compilation_args(Path::new("foo.pas"), None);
+        assert_that!(args).contains("-Mobjfpc".to_string());
+        assert_that!(args).contains("-Sa".to_string());
+        assert_that!(args.last().unwrap()).is_equal_to(&"foo.pas".to_string());
+    }
+
     #[test]
     fn test_executable_name() {
-        let lang = LanguagePascal::new();
+        let lang = LanguagePascal::new(LanguagePascalConfiguration::default());
         assert_that!(lang.executable_name(Path::new("foo.pas"), None))
             .is_equal_to(PathBuf::from("foo"));
     }
@@ -29,7 +29,7 @@ impl LanguagePython {
 }
 
 impl Language for LanguagePython {
-    fn name(&self) -> &'static str {
+    fn name(&self) -> &str {
         match self.version {
             LanguagePythonVersion::Autodetect => "Python / Autodetect",
             LanguagePythonVersion::Python2 => "Python2",
@@ -37,7 +37,7 @@ impl Language for LanguagePython {
         }
     }
 
-    fn extensions(&self) -> Vec<&'static str> {
+    fn extensions(&self) -> Vec<&str> {
         vec!["py"]
     }
 
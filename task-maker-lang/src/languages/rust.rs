@@ -0,0 +1,239 @@
+use std::path::{Path, PathBuf};
+
+use regex::Regex;
+
+use task_maker_dag::*;
+
+use crate::languages::{find_dependencies, Language};
+use crate::Dependency;
+
+/// Configuration of the Rust language to use.
+#[derive(Clone, Debug)]
+pub struct LanguageRustConfiguration {
+    /// Compiler to use (e.g. ExecutionCommand::system("rustc") ).
+    pub compiler: ExecutionCommand,
+    /// Edition of the language to use (e.g. 2018).
+    pub edition: String,
+    /// Extra flags to pass to the compiler.
+    pub extra_flags: Vec<String>,
+}
+
+/// The Rust language.
+///
+/// Only the modules declared in the source file are compiled with it, the graders are not
+/// supported.
+#[derive(Debug)]
+pub struct LanguageRust {
+    pub config: LanguageRustConfiguration,
+}
+
+impl LanguageRustConfiguration {
+    /// Get the configuration of Rust from the environment variables.
+    pub fn from_env() -> LanguageRustConfiguration {
+        let compiler = std::env::var_os("TM_RUSTC").unwrap_or_else(|| "rustc".into());
+        let edition = std::env::var("TM_RUST_EDITION").unwrap_or_else(|_| "2018".into());
+        let extra_flags = std::env::var("TM_RUSTFLAGS").unwrap_or_else(|_| String::new());
+        let extra_flags = shell_words::split(&extra_flags).expect("Invalid $TM_RUSTFLAGS");
+        LanguageRustConfiguration {
+            compiler: ExecutionCommand::System(compiler.into()),
+            edition,
+            extra_flags,
+        }
+    }
+}
+
+impl LanguageRust {
+    /// Make a new LanguageRust using the specified configuration.
+    pub fn new(config: LanguageRustConfiguration) -> LanguageRust {
+        LanguageRust { config }
+    }
+}
+
+impl Language for LanguageRust {
+    fn name(&self) -> &str {
+        "Rust"
+    }
+
+    fn extensions(&self) -> Vec<&str> {
+        vec!["rs"]
+    }
+
+    fn need_compilation(&self) -> bool {
+        true
+    }
+
+    fn compilation_command(&self, _path: &Path, _write_to: Option<&Path>) -> ExecutionCommand {
+        self.config.compiler.clone()
+    }
+
+    fn compilation_args(&self, path: &Path, write_to: Option<&Path>) -> Vec<String> {
+        let exe_name = self.compiled_file_name(path, write_to);
+        let exe_name = exe_name.to_string_lossy();
+        let args = vec![
+            "-O",
+            "--cfg",
+            "EVAL",
+            "--color=always",
+            "-o",
+            exe_name.as_ref(),
+        ];
+        let mut args: Vec<_> = args.into_iter().map(|s| s.to_string()).collect();
+        args.push(format!("--edition={}", self.config.edition));
+        for arg in &self.config.extra_flags {
+            args.push(arg.clone());
+        }
+        args.push(
+            path.file_name()
+                .expect("Invalid source file name")
+                .to_string_lossy()
+                .to_string(),
+        );
+        args
+    }
+
+    fn compilation_add_file(&self, _args: Vec<String>, file: &Path) -> Vec<String> {
+        panic!("Cannot compile {:?} with a Rust program", file)
+    }
+
+    fn supports_graders(&self) -> bool {
+        // rustc compiles a single crate root, the grader cannot be added to it
+        false
+    }
+
+    fn compilation_dependencies(&self, path: &Path) -> Vec<Dependency> {
+        find_rust_deps(path)
+    }
+}
+
+/// Extract all the modules of a Rust crate, given the path of its root.
+fn find_rust_deps(path: &Path) -> Vec<Dependency> {
+    let root = match path.canonicalize() {
+        Ok(root) => root,
+        _ => return vec![],
+    };
+    find_dependencies(path, |file| extract_modules(&root, file))
+}
+
+/// Extracts all the modules declared in a file of the crate with the specified root. The supported
+/// declarations are the ones in the form `mod name;`, optionally with a visibility. The file of the
+/// module is searched, like the compiler does, in `name.rs` and `name/mod.rs` inside the directory
+/// of the modules of the file: the directory of the file itself for the crate root and for the
+/// `mod.rs` files, otherwise the directory with the name of the file.
+///
+/// The returned values are in the form (local_path, sandbox_path), where the local path is
+/// absolute and the sandbox path is relative to the directory of the crate root.
+fn extract_modules(root: &Path, path: &Path) -> Vec<(PathBuf, PathBuf)> {
+    lazy_static! {
+        static ref RE: Regex = Regex::new(r"(?m)^\s*(?:pub(?:\s*\([^)]*\))?\s+)?mod\s+(\w+)\s*;")
+            .expect("Invalid regex");
+    }
+    let path = match path.canonicalize() {
+        Ok(path) => path,
+        _ => return vec![],
+    };
+    let content = match std::fs::read_to_string(&path) {
+        Ok(content) => content,
+        _ => return vec![],
+    };
+    let root_dir = root.parent().expect("Invalid crate root");
+    let parent = path.parent().expect("Invalid path");
+    let modules_dir = if path == root || path.file_name().map_or(false, |name| name == "mod.rs") {
+        parent.to_owned()
+    } else {
+        parent.join(path.file_stem().expect("Invalid path"))
+    };
+    let mut res = Vec::new();
+    for cap in RE.captures_iter(&content) {
+        if let Some(name) = cap.get(1) {
+            let name = name.as_str();
+            for local in &[
+                modules_dir.join(format!("{}.rs", name)),
+                modules_dir.join(name).join("mod.rs"),
+            ] {
+                if let Ok(sandbox) = local.strip_prefix(root_dir) {
+                    res.push((local.clone(), sandbox.to_owned()));
+                }
+            }
+        }
+    }
+    res
+}
+
+#[cfg(test)]
+mod tests {
+    use std::fs::{create_dir_all, write};
+
+    use spectral::prelude::*;
+
+    use super::*;
+
+    #[test]
+    fn test_compilation_args() {
+        let lang = LanguageRust::new(LanguageRustConfiguration {
+            compiler: ExecutionCommand::System("rustc".into()),
+            edition: "2015".to_string(),
+            extra_flags: vec!["-Ctarget-cpu=native".into()],
+        });
+        let args = lang.compilation_args(Path::new("foo.rs"), None);
+        assert_that!(args).contains("foo.rs".to_string());
+        assert_that!(args).contains("-O".to_string());
+        assert_that!(args).contains("--edition=2015".to_string());
+        assert_that!(args).contains("-Ctarget-cpu=native".to_string());
+        assert_that!(args).contains("-o".to_string());
+        assert_that!(args).contains("compiled".to_string());
+    }
+
+    #[test]
+    fn test_executable_name() {
+        let lang = LanguageRust::new(LanguageRustConfiguration::from_env());
+        assert_that!(lang.executable_name(Path::new("foo.rs"), None))
+            .is_equal_to(PathBuf::from("foo"));
+    }
+
+    #[test]
+    fn test_extract_modules() {
+        let tmpdir = tempdir::TempDir::new("tm-test").unwrap();
+        let root = tmpdir.path().canonicalize().unwrap().join("main.rs");
+        write(
+            &root,
+            "mod foo;\npub mod bar;\n  pub(crate) mod baz ;\nmod tests {\n}\n// mod nope;",
+        )
+        .unwrap();
+        let modules = extract_modules(&root, &root);
+        let sandbox: Vec<_> = modules.iter().map(|(_, sandbox)| sandbox.clone()).collect();
+        assert_that!(sandbox).is_equal_to(vec![
+            PathBuf::from("foo.rs"),
+            PathBuf::from("foo/mod.rs"),
+            PathBuf::from("bar.rs"),
+            PathBuf::from("bar/mod.rs"),
+            PathBuf::from("baz.rs"),
+            PathBuf::from("baz/mod.rs"),
+        ]);
+        assert_that!(modules[0].0).is_equal_to(root.with_file_name("foo.rs"));
+    }
+
+    #[test]
+    fn test_find_rust_deps() {
+        let tmpdir = tempdir::TempDir::new("tm-test").unwrap();
+        let dir = tmpdir.path().canonicalize().unwrap();
+        create_dir_all(dir.join("foo")).unwrap();
+        create_dir_all(dir.join("bar")).unwrap();
+        let path = dir.join("main.rs");
+        write(&path, "mod foo;\nmod bar;\nfn main() {}").unwrap();
+        // foo.rs declares its submodules inside foo/
+        write(dir.join("foo.rs"), "mod baz;").unwrap();
+        write(dir.join("foo/baz.rs"), "pub fn baz() {}").unwrap();
+        // bar/mod.rs declares its submodules next to itself
+        write(dir.join("bar/mod.rs"), "mod qux;").unwrap();
+        write(dir.join("bar/qux.rs"), "mod main;").unwrap();
+        let deps = find_rust_deps(&path);
+        let sandbox: Vec<_> = deps.iter().map(|dep| dep.sandbox_path.clone()).collect();
+        assert_that!(sandbox).is_equal_to(vec![
+            PathBuf::from("foo.rs"),
+            PathBuf::from("bar/mod.rs"),
+            PathBuf::from("foo/baz.rs"),
+            PathBuf::from("bar/qux.rs"),
+        ]);
+        assert_that!(deps[2].local_path).is_equal_to(dir.join("foo/baz.rs"));
+    }
+}
@@ -14,11 +14,11 @@ impl LanguageShell {
 }
 
 impl Language for LanguageShell {
-    fn name(&self) -> &'static str {
+    fn name(&self) -> &str {
         "Shell"
     }
 
-    fn extensions(&self) -> Vec<&'static str> {
+    fn extensions(&self) -> Vec<&str> {
         vec!["sh"]
     }
 
@@ -11,6 +11,10 @@
 //! is able to detect the language of a source file based on its extension. A trait object is used
 //! to keep track of the language.
 //!
+//! The users can define their own languages in a YAML file, see
+//! [`LanguageManager::load_languages`](struct.LanguageManager.html#method.load_languages). They
+//! are known only inside a [`LanguageScope`](struct.LanguageScope.html).
+//!
 //! To actually use the language you can use [`SourceFile`](struct.SourceFile.html), it exposes the
 //! functionalities for compiling and running a source file.
 //!
@@ -33,18 +37,46 @@ mod languages;
 mod source_file;
 
 pub use grader_map::GraderMap;
-pub use languages::{Dependency, Language};
+pub use languages::custom::{
+    CustomLanguage, CustomLanguageConfiguration, CustomLanguageLimits, CustomLanguagesConfiguration,
+};
+pub use languages::{CompilerSettings, Dependency, Language};
 pub use source_file::SourceFile;
 
-use languages::*;
+use std::cell::RefCell;
+use std::marker::PhantomData;
 use std::path::Path;
 use std::sync::Arc;
 
+use failure::Error;
+
+use languages::*;
+
 /// Manager of all the known languages, you should use this to get
 /// [`Language`](languages/trait.Language.html) instances.
+///
+/// The builtin languages are always known, while the user-defined ones are known only inside a
+/// [`LanguageScope`](struct.LanguageScope.html), see
+/// [`LanguageManager::scoped_languages`](struct.LanguageManager.html#method.scoped_languages).
 pub struct LanguageManager {
-    /// The list of all the known languages.
-    known_languages: Vec<Arc<dyn Language + Sync + Send>>,
+    /// The list of all the builtin languages.
+    known_languages: Vec<Arc<dyn Language>>,
+}
+
+/// The languages registered in a thread, the most important first.
+type ScopedLanguages = Vec<Arc<dyn Language>>;
+
+/// The languages registered in the current thread, dropping a `LanguageScope` unregisters them.
+///
+/// The languages of a task are used by the thread that parses the task and builds its DAG, so they
+/// are kept per thread: the tasks evaluated in parallel by different threads don't see each other's
+/// languages. For the same reason the scope cannot be moved to another thread.
+#[must_use = "the languages are unregistered when the scope is dropped"]
+pub struct LanguageScope {
+    /// The languages registered in the thread before this scope, restored on drop.
+    previous: ScopedLanguages,
+    /// Makes the scope `!Send`.
+    _not_send: PhantomData<*const ()>,
 }
 
 impl LanguageManager {
@@ -61,11 +93,28 @@ impl LanguageManager {
                     python::LanguagePythonVersion::Autodetect,
                 )),
                 Arc::new(shell::LanguageShell::new()),
-                Arc::new(pascal::LanguagePascal::new()),
+                Arc::new(pascal::LanguagePascal::new(
+                    pascal::LanguagePascalConfiguration::default(),
+                )),
+                Arc::new(java::LanguageJava::new(
+                    java::LanguageJavaConfiguration::from_env(),
+                )),
+                Arc::new(rust::LanguageRust::new(
+                    rust::LanguageRustConfiguration::from_env(),
+                )),
+                Arc::new(go::LanguageGo::new(go::LanguageGoConfiguration::from_env())),
             ],
         }
     }
 
+    /// All the languages known in the current thread, the most important first: the ones of the
+    /// innermost scope, then the ones of the outer scopes and finally the builtin ones.
+    fn languages() -> Vec<Arc<dyn Language>> {
+        let mut languages = SCOPED_LANGUAGES.with(|scoped| scoped.borrow().clone());
+        languages.extend(LANGUAGE_MANAGER_SINGL.known_languages.iter().cloned());
+        languages
+    }
+
     /// Given a path to a file guess the language that the source file probably is.
     ///
     /// ```
@@ -79,32 +128,119 @@ impl LanguageManager {
     /// assert!(unknown.is_none());
     /// ```
     pub fn detect_language<P: AsRef<Path>>(path: P) -> Option<Arc<dyn Language>> {
-        let manager = &LANGUAGE_MANAGER_SINGL;
         let ext = path
             .as_ref()
             .extension()
             .map(|s| s.to_string_lossy())
             .unwrap_or_else(|| "".into())
             .to_lowercase();
-        for lang in manager.known_languages.iter() {
-            for lang_ext in lang.extensions().iter() {
-                if ext == *lang_ext {
-                    return Some(lang.clone());
-                }
+        LanguageManager::languages()
+            .into_iter()
+            .find(|lang| lang.extensions().iter().any(|lang_ext| ext == *lang_ext))
+    }
+
+    /// The names of all the known languages, the most important first.
+    ///
+    /// ```
+    /// use task_maker_lang::LanguageManager;
+    ///
+    /// let names = LanguageManager::language_names();
+    /// assert!(names.iter().any(|name| name == "Rust"));
+    /// ```
+    pub fn language_names() -> Vec<String> {
+        let mut names: Vec<String> = Vec::new();
+        for lang in LanguageManager::languages() {
+            // a scoped language may replace a builtin one with the same name
+            if !names.iter().any(|name| name == lang.name()) {
+                names.push(lang.name().to_string());
             }
         }
-        None
+        names
     }
 
     /// Search between the known languages the one with the specified name and return it if found.
-    pub(crate) fn from_name<S: AsRef<str>>(name: S) -> Option<Arc<dyn Language>> {
-        let manager = &LANGUAGE_MANAGER_SINGL;
-        for lang in manager.known_languages.iter() {
-            if lang.name() == name.as_ref() {
-                return Some(lang.clone());
+    ///
+    /// ```
+    /// use task_maker_lang::LanguageManager;
+    ///
+    /// let rust = LanguageManager::from_name("Rust").unwrap();
+    /// assert_eq!(rust.name(), "Rust");
+    /// assert!(LanguageManager::from_name("rust").is_none());
+    /// ```
+    pub fn from_name<S: AsRef<str>>(name: S) -> Option<Arc<dyn Language>> {
+        LanguageManager::languages()
+            .into_iter()
+            .find(|lang| lang.name() == name.as_ref())
+    }
+
+    /// Register some languages in the current thread, until the returned scope is dropped. The
+    /// languages take the precedence over all the already known ones, the first one having the
+    /// highest precedence, and replace the ones with the same name.
+    ///
+    /// ```
+    /// use std::sync::Arc;
+    /// use task_maker_lang::{CustomLanguage, CustomLanguageConfiguration, LanguageManager};
+    ///
+    /// let config = CustomLanguageConfiguration {
+    ///     name: "Kotlin".to_string(),
+    ///     extensions: vec!["kt".to_string()],
+    ///     compile: None,
+    ///     run: vec!["kotlin".to_string(), "{source}".to_string()],
+    ///     dependencies: vec![],
+    ///     limits: Default::default(),
+    /// };
+    /// let kotlin = Arc::new(CustomLanguage::new(config).unwrap());
+    /// {
+    ///     let _scope = LanguageManager::scoped_languages(vec![kotlin]);
+    ///     assert!(LanguageManager::detect_language("sol.kt").is_some());
+    /// }
+    /// assert!(LanguageManager::detect_language("sol.kt").is_none());
+    /// ```
+    pub fn scoped_languages(languages: Vec<Arc<dyn Language>>) -> LanguageScope {
+        SCOPED_LANGUAGES.with(|scoped| {
+            let mut scoped = scoped.borrow_mut();
+            let previous = scoped.clone();
+            scoped.retain(|lang| languages.iter().all(|new| new.name() != lang.name()));
+            scoped.splice(0..0, languages);
+            LanguageScope {
+                previous,
+                _not_send: PhantomData,
             }
+        })
+    }
+
+    /// Load the user-defined languages from the specified YAML file, the first language of the
+    /// file being the most important. The format of the file is described in
+    /// [`CustomLanguagesConfiguration`](struct.CustomLanguagesConfiguration.html). The languages
+    /// are not registered, see
+    /// [`LanguageManager::scoped_languages`](struct.LanguageManager.html#method.scoped_languages).
+    ///
+    /// ```
+    /// use task_maker_lang::LanguageManager;
+    /// # use tempdir::TempDir;
+    ///
+    /// # let tempdir = TempDir::new("tm-tests").unwrap();
+    /// # let path = tempdir.path().join("languages.yaml");
+    /// # std::fs::write(&path, "languages:\n  - name: Kotlin\n    extensions: [kt]\n    compile: [kotlinc, '{source}', -include-runtime, -d, '{exe}']\n    run: [java, -jar, '{exe}']").unwrap();
+    /// let languages = LanguageManager::load_languages(path /* languages.yaml */).unwrap();
+    /// let _scope = LanguageManager::scoped_languages(languages);
+    /// let lang = LanguageManager::detect_language("sol.kt").unwrap();
+    /// assert_eq!(lang.name(), "Kotlin");
+    /// ```
+    pub fn load_languages<P: AsRef<Path>>(path: P) -> Result<Vec<Arc<dyn Language>>, Error> {
+        let config = CustomLanguagesConfiguration::from_file(path)?;
+        let mut languages: Vec<Arc<dyn Language>> = Vec::new();
+        for language in config.languages {
+            languages.push(Arc::new(CustomLanguage::new(language)?));
         }
-        None
+        Ok(languages)
+    }
+}
+
+impl Drop for LanguageScope {
+    fn drop(&mut self) {
+        let previous = std::mem::take(&mut self.previous);
+        SCOPED_LANGUAGES.with(|scoped| *scoped.borrow_mut() = previous);
     }
 }
 
@@ -113,6 +249,11 @@ lazy_static! {
     static ref LANGUAGE_MANAGER_SINGL: LanguageManager = LanguageManager::new();
 }
 
+thread_local! {
+    /// The languages registered in the current thread.
+    static SCOPED_LANGUAGES: RefCell<ScopedLanguages> = RefCell::new(Vec::new());
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -122,17 +263,103 @@ mod tests {
     #[test]
     fn test_detect_language() {
         let lang = LanguageManager::detect_language("foo.cpp").unwrap();
-        let name = LanguageCpp::new(LanguageCppConfiguration::from_env()).name();
+        let cpp = LanguageCpp::new(LanguageCppConfiguration::from_env());
+        let name = cpp.name();
         assert_that!(lang.name()).is_equal_to(name);
     }
 
     #[test]
     fn test_detect_language_uppercase() {
         let lang = LanguageManager::detect_language("foo.CPP").unwrap();
-        let name = LanguageCpp::new(LanguageCppConfiguration::from_env()).name();
+        let cpp = LanguageCpp::new(LanguageCppConfiguration::from_env());
+        let name = cpp.name();
         assert_that!(lang.name()).is_equal_to(name);
     }
 
+    #[test]
+    fn test_detect_language_java() {
+        let lang = LanguageManager::detect_language("Main.java").unwrap();
+        assert_that!(lang.name()).is_equal_to("Java / JDK");
+        assert_that!(lang.need_compilation()).is_true();
+    }
+
+    #[test]
+    fn test_detect_language_rust() {
+        let lang = LanguageManager::detect_language("sol.rs").unwrap();
+        assert_that!(lang.name()).is_equal_to("Rust");
+        assert_that!(lang.need_compilation()).is_true();
+    }
+
+    #[test]
+    fn test_detect_language_go() {
+        let lang = LanguageManager::detect_language("gen.go").unwrap();
+        assert_that!(lang.name()).is_equal_to("Go");
+        assert_that!(lang.need_compilation()).is_true();
+    }
+
+    #[test]
+    fn test_scoped_languages() {
+        let config = CustomLanguageConfiguration {
+            name: "Test language".to_string(),
+            extensions: vec!["testlang".to_string()],
+            compile: None,
+            run: vec!["testlang".to_string(), "{source}".to_string()],
+            dependencies: vec![],
+            limits: CustomLanguageLimits::default(),
+        };
+        let lang = CustomLanguage::new(config).unwrap();
+        {
+            let _scope = LanguageManager::scoped_languages(vec![Arc::new(lang)]);
+            let lang = LanguageManager::detect_language("sol.testlang").unwrap();
+            assert_that!(lang.name()).is_equal_to("Test language");
+            let lang = LanguageManager::from_name("Test language").unwrap();
+            assert_that!(lang.extensions()).is_equal_to(vec!["testlang"]);
+            assert_that!(LanguageManager::language_names()).contains("Test language".to_string());
+        }
+        assert_that!(LanguageManager::detect_language("sol.testlang")).is_none();
+        assert_that!(LanguageManager::from_name("Test language")).is_none();
+    }
+
+    #[test]
+    fn test_scoped_languages_replace() {
+        let config = CustomLanguageConfiguration {
+            name: "Rust".to_string(),
+            extensions: vec!["rs".to_string()],
+            compile: None,
+            run: vec!["rust-script".to_string(), "{source}".to_string()],
+            dependencies: vec![],
+            limits: CustomLanguageLimits::default(),
+        };
+        let lang = CustomLanguage::new(config).unwrap();
+        {
+            let _scope = LanguageManager::scoped_languages(vec![Arc::new(lang)]);
+            let lang = LanguageManager::detect_language("sol.rs").unwrap();
+            assert_that!(lang.need_compilation()).is_false();
+            let names = LanguageManager::language_names();
+            assert_that!(names.iter().filter(|name| *name == "Rust").count()).is_equal_to(1);
+        }
+        let lang = LanguageManager::detect_language("sol.rs").unwrap();
+        assert_that!(lang.need_compilation()).is_true();
+    }
+
+    #[test]
+    fn test_scoped_languages_other_thread() {
+        let config = CustomLanguageConfiguration {
+            name: "Test language".to_string(),
+            extensions: vec!["testlang".to_string()],
+            compile: None,
+            run: vec!["testlang".to_string(), "{source}".to_string()],
+            dependencies: vec![],
+            limits: CustomLanguageLimits::default(),
+        };
+        let lang = CustomLanguage::new(config).unwrap();
+        let _scope = LanguageManager::scoped_languages(vec![Arc::new(lang)]);
+        let detected = std::thread::spawn(|| LanguageManager::detect_language("sol.testlang"))
+            .join()
+            .unwrap();
+        assert_that!(detected).is_none();
+    }
+
     #[test]
     fn test_detect_language_unknown() {
         let lang = LanguageManager::detect_language("foo.blah");
@@ -141,7 +368,8 @@ mod tests {
 
     #[test]
     fn test_from_name() {
-        let name = LanguageCpp::new(LanguageCppConfiguration::from_env()).name();
+        let cpp = LanguageCpp::new(LanguageCppConfiguration::from_env());
+        let name = cpp.name();
         let lang = LanguageManager::from_name(name).unwrap();
         assert_that!(lang.name()).is_equal_to(name);
     }
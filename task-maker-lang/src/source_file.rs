@@ -1,7 +1,7 @@
 use std::path::{Path, PathBuf};
 use std::sync::{Arc, Mutex};
 
-use failure::Error;
+use failure::{bail, Error};
 use serde::{Deserialize, Deserializer, Serialize, Serializer};
 use typescript_definitions::TypeScriptify;
 
@@ -31,6 +31,13 @@ pub struct SourceFile {
     language: Arc<dyn Language>,
     /// Handle to the executable after the compilation/provided file.
     executable: Arc<Mutex<Option<File>>>,
+    /// The compiler settings this copy of the source file is compiled with, if any.
+    #[serde(skip)]
+    compiler_settings: Option<CompilerSettings>,
+    /// Handles to the executables compiled with the compiler settings of the copies of this source
+    /// file, one for each set of settings.
+    #[serde(skip)]
+    configured_executables: Arc<Mutex<Vec<(CompilerSettings, File)>>>,
     /// An optional handler to the map of the graders.
     grader_map: Option<Arc<GraderMap>>,
     /// Whether to force the copy-exe option of the DAG for this source file.
@@ -63,6 +70,8 @@ impl SourceFile {
             base_path,
             language: lang.unwrap(),
             executable: Arc::new(Mutex::new(None)),
+            compiler_settings: None,
+            configured_executables: Arc::new(Mutex::new(Vec::new())),
             grader_map,
             write_bin_to: write_bin_to.map(|p| p.into()),
             copy_exe: false,
@@ -158,7 +167,7 @@ impl SourceFile {
         }
         exec.args(self.language.runtime_args(&self.path, write_to, args));
         exec.input(
-            self.executable.lock().unwrap().as_ref().unwrap(),
+            &self.prepared_executable().unwrap(),
             &self.language.executable_name(&self.path, write_to),
             true,
         );
@@ -177,6 +186,18 @@ impl SourceFile {
         Ok((comp, exec))
     }
 
+    /// Make a copy of this source file that is compiled using the specified compiler settings, if
+    /// its language supports them. The copies with the same settings share the same executable,
+    /// while each different set of settings gets its own compilation.
+    pub fn with_compiler_settings(&self, settings: &CompilerSettings) -> SourceFile {
+        let mut source = self.clone();
+        if let Some(language) = self.language.with_compiler_settings(settings) {
+            source.language = language;
+            source.compiler_settings = Some(settings.clone());
+        }
+        source
+    }
+
     /// Force the executable to be copied to `write_bin_to` regardless of the option of the DAG.
     pub fn copy_exe(&mut self) {
         self.copy_exe = true;
@@ -189,7 +210,7 @@ impl SourceFile {
         dag: &mut ExecutionDAG,
     ) -> Result<(FileUuid, Option<ExecutionUuid>), Error> {
         let comp = self.prepare(dag)?;
-        let exe = self.executable.lock().unwrap().as_ref().unwrap().uuid;
+        let exe = self.prepared_executable().unwrap().uuid;
         Ok((exe, comp))
     }
 
@@ -224,9 +245,23 @@ impl SourceFile {
         self.write_bin_to.clone()
     }
 
+    /// The language of the source file.
+    ///
+    /// ```
+    /// use task_maker_lang::SourceFile;
+    /// use std::path::PathBuf;
+    ///
+    /// let source = SourceFile::new("path/to/sourcefile.cpp", "", None, None::<PathBuf>).unwrap();
+    ///
+    /// assert_eq!(source.language().name(), "C++");
+    /// ```
+    pub fn language(&self) -> &dyn Language {
+        self.language.as_ref()
+    }
+
     /// Prepare the source file setting the `executable` and eventually compiling the source file.
     fn prepare(&self, dag: &mut ExecutionDAG) -> Result<Option<ExecutionUuid>, Error> {
-        if self.executable.lock().unwrap().is_some() {
+        if self.prepared_executable().is_some() {
             return Ok(None);
         }
         let write_to = self.write_bin_to.as_deref();
@@ -250,12 +285,22 @@ impl SourceFile {
             // the compilers may need to store some temp files
             comp.limits.read_only(false);
             comp.limits.mount_tmpfs(true);
+            self.language.custom_compilation(&mut comp);
             for dep in self.language.compilation_dependencies(&self.path) {
                 comp.input(&dep.file, &dep.sandbox_path, dep.executable);
                 dag.provide_file(dep.file, &dep.local_path)?;
             }
             if let Some(grader_map) = self.grader_map.as_ref() {
-                for dep in grader_map.get_compilation_deps(self.language.as_ref()) {
+                let graders = grader_map.get_compilation_deps(self.language.as_ref());
+                if !graders.is_empty() && !self.language.supports_graders() {
+                    bail!(
+                        "Cannot compile {} with the grader {:?}: the graders are not supported in {}",
+                        self.name(),
+                        graders[0].local_path,
+                        self.language.name()
+                    );
+                }
+                for dep in graders {
                     comp.input(&dep.file, &dep.sandbox_path, dep.executable);
                     comp.args = self
                         .language
@@ -272,7 +317,7 @@ impl SourceFile {
                     dag.write_file_to(&exec, write_bin_to, true);
                 }
             }
-            *self.executable.lock().unwrap() = Some(exec);
+            self.set_executable(exec);
             Ok(Some(comp_uuid))
         } else {
             let executable = File::new(&format!("Source file of {:?}", self.path));
@@ -281,11 +326,37 @@ impl SourceFile {
                     dag.write_file_to(&executable, write_bin_to, true);
                 }
             }
-            *self.executable.lock().unwrap() = Some(executable.clone());
+            self.set_executable(executable.clone());
             dag.provide_file(executable, &self.path)?;
             Ok(None)
         }
     }
+
+    /// The executable of this source file with its compiler settings, if already prepared.
+    fn prepared_executable(&self) -> Option<File> {
+        match &self.compiler_settings {
+            None => self.executable.lock().unwrap().clone(),
+            Some(settings) => self
+                .configured_executables
+                .lock()
+                .unwrap()
+                .iter()
+                .find(|(s, _)| s == settings)
+                .map(|(_, exe)| exe.clone()),
+        }
+    }
+
+    /// Store the executable of this source file with its compiler settings.
+    fn set_executable(&self, executable: File) {
+        match &self.compiler_settings {
+            None => *self.executable.lock().unwrap() = Some(executable),
+            Some(settings) => self
+                .configured_executables
+                .lock()
+                .unwrap()
+                .push((settings.clone(), executable)),
+        }
+    }
 }
 
 /// Serializer for `Arc<dyn Language>`. It serializes just the name of the language, expecting the
@@ -375,4 +446,90 @@ mod tests {
         assert!(!exec_skipped.load(Ordering::Relaxed));
         assert!(cwd.path().join("bin").exists());
     }
+
+    #[test]
+    fn test_source_file_compiler_settings() {
+        let cwd = TempDir::new("tm-test").unwrap();
+        let source_path = cwd.path().join("source.cpp");
+        std::fs::write(&source_path, "int main() {return 0;}").unwrap();
+
+        let mut dag = ExecutionDAG::new();
+        let source = SourceFile::new(&source_path, "", None, None::<PathBuf>).unwrap();
+        let settings = CompilerSettings {
+            std_version: Some("c++03".into()),
+            extra_flags: vec!["-DTASK_FLAG".into()],
+        };
+        let configured = source.with_compiler_settings(&settings);
+        let (comp, _) = configured
+            .execute(&mut dag, "Testing exec", vec![])
+            .unwrap();
+        let comp = comp.unwrap();
+        let comp = dag
+            .data
+            .execution_groups
+            .values()
+            .flat_map(|group| group.executions.iter())
+            .find(|exec| exec.uuid == comp)
+            .unwrap();
+        assert!(comp.args.contains(&"-std=c++03".to_string()));
+        assert!(comp.args.contains(&"-DTASK_FLAG".to_string()));
+        // the original source file is compiled without the settings
+        let (_, comp) = source.executable(&mut dag).unwrap();
+        assert!(comp.is_some());
+    }
+
+    #[test]
+    fn test_source_file_two_compiler_settings() {
+        let cwd = TempDir::new("tm-test").unwrap();
+        let source_path = cwd.path().join("source.cpp");
+        std::fs::write(&source_path, "int main() {return 0;}").unwrap();
+
+        let mut dag = ExecutionDAG::new();
+        let source = SourceFile::new(&source_path, "", None, None::<PathBuf>).unwrap();
+        let settings1 = CompilerSettings {
+            std_version: Some("c++03".into()),
+            extra_flags: vec![],
+        };
+        let settings2 = CompilerSettings {
+            std_version: Some("c++17".into()),
+            extra_flags: vec![],
+        };
+        let (exe1, comp1) = source
+            .with_compiler_settings(&settings1)
+            .executable(&mut dag)
+            .unwrap();
+        let (exe2, comp2) = source
+            .with_compiler_settings(&settings2)
+            .executable(&mut dag)
+            .unwrap();
+        assert!(comp1.is_some());
+        assert!(comp2.is_some());
+        assert_ne!(exe1, exe2);
+        // the same settings share the same compilation
+        let (exe, comp) = source
+            .with_compiler_settings(&settings1)
+            .executable(&mut dag)
+            .unwrap();
+        assert!(comp.is_none());
+        assert_eq!(exe, exe1);
+    }
+
+    #[test]
+    fn test_source_file_rust_grader() {
+        let cwd = TempDir::new("tm-test").unwrap();
+        let source_path = cwd.path().join("source.rs");
+        std::fs::write(&source_path, "fn main() {}").unwrap();
+        let grader_path = cwd.path().join("grader.rs");
+        std::fs::write(&grader_path, "fn main() {}").unwrap();
+
+        let mut dag = ExecutionDAG::new();
+        let grader_map = Arc::new(GraderMap::new(vec![grader_path]));
+        let source = SourceFile::new(&source_path, "", Some(grader_map), None::<PathBuf>).unwrap();
+        let err = source
+            .execute(&mut dag, "Testing exec", vec![])
+            .unwrap_err();
+        assert!(err
+            .to_string()
+            .contains("graders are not supported in Rust"));
+    }
 }
@@ -140,6 +140,11 @@ impl TestInterface {
                 disabled_sanity_checks: vec![],
                 seed: None,
                 dry_run: false,
+                repetitions: 1,
+                solution_repetitions: vec![],
+                suggest_limits: false,
+                correct_solutions: vec![],
+                too_slow_solutions: vec![],
             },
         )
         .unwrap();
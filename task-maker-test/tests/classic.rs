@@ -16,6 +16,7 @@ fn classic(test: TestInterface) {
         .must_compile("wa.cpp")
         .must_compile("wrong_file.cpp")
         .must_compile("pascal.pas")
+        .must_compile("rust.rs")
         .must_not_compile("not_compile.cpp")
         // .not_compiled(".ignoreme.cpp")
         .not_compiled("bash.sh")
@@ -24,6 +25,7 @@ fn classic(test: TestInterface) {
         .solution_score("soluzione.py", vec![5.0, 45.0, 50.0])
         .solution_score("pascal.pas", vec![5.0, 45.0, 50.0])
         .solution_score("bash.sh", vec![5.0, 45.0, 50.0])
+        .solution_score("rust.rs", vec![5.0, 45.0, 50.0])
         .solution_score("float_error.cpp", vec![0.0, 0.0, 0.0])
         .solution_score("mle.cpp", vec![0.0, 0.0, 0.0])
         .solution_score("noop.py", vec![0.0, 0.0, 0.0])
@@ -35,6 +37,7 @@ fn classic(test: TestInterface) {
         .solution_statuses("soluzione.py", vec![Accepted("Output is correct".into())])
         .solution_statuses("pascal.pas", vec![Accepted("Output is correct".into())])
         .solution_statuses("bash.sh", vec![Accepted("Output is correct".into())])
+        .solution_statuses("rust.rs", vec![Accepted("Output is correct".into())])
         // .solution_statuses("mle.cpp", vec![RuntimeError]) // pretty unreliable
         .solution_statuses("nonzero.cpp", vec![RuntimeError])
         .solution_statuses("sigsegv.c", vec![RuntimeError])
@@ -12,11 +12,14 @@ syntax match GenVariable  /\$\i\+/
 syntax match GenDollar    /\$\$/
 syntax keyword GenGeneratorK contained GEN VAL nextgroup=GenNameGV skipwhite
 syntax keyword GenRunK contained RUN nextgroup=GenRunV skipwhite
-syntax keyword GenCopyK contained COPY nextgroup=GenExe skipwhite
+syntax keyword GenCopyK contained COPY INCLUDE nextgroup=GenExe skipwhite
 syntax keyword GenSubtaskK contained SUBTASK nextgroup=GenScore skipwhite
 syntax keyword GenConstraintK contained CONSTRAINT nextgroup=GenNumber
-syntax match GenCommand   /^:.*/ contains=GenGeneratorK,GenSubtaskK,GenRunK,GenCopyK
-syntax match GenConstraintCommand   /^:\s*CONSTRAINT.*/ contains=GenConstraintK,GenNumber,GenVariable
+syntax keyword GenLoopK contained FOR IN END
+syntax keyword GenSetK contained SET TESTCASE WEIGHT TIME_LIMIT MEMORY_LIMIT
+syntax keyword GenFunctionK contained min max
+syntax match GenCommand   /^:.*/ contains=GenGeneratorK,GenSubtaskK,GenRunK,GenCopyK,GenLoopK,GenSetK
+syntax match GenConstraintCommand   /^:\s*CONSTRAINT.*/ contains=GenConstraintK,GenNumber,GenVariable,GenFunctionK
 syntax match GenComment   /^#.*/
 
 hi def link GenComment Comment
@@ -33,6 +36,9 @@ hi def link GenRunK vimCommand
 hi def link GenCopyK vimCommand
 hi def link GenSubtaskK vimCommand
 hi def link GenConstraintK vimCommand
+hi def link GenLoopK vimCommand
+hi def link GenSetK vimCommand
+hi def link GenFunctionK Function
 hi def link GenVariable Macro
 hi def link GenDollar Macro
 